@@ -1,10 +1,20 @@
-use super::clockify::Token;
+use super::clockify::{EntrySource, HttpApi, Token};
+use super::config::{ConfigFile, CONFIG_FILE};
+use super::utils;
+use super::utils::http::HttpOptions;
+pub(crate) use super::utils::table::{ColorChoice, HoursFormat, Locale, OutputFormat, RowKey};
 use anyhow::Error;
-use chrono::{NaiveDate, Utc};
-use clap::{Parser, ValueEnum};
+use chrono::{NaiveDate, NaiveTime, Utc};
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use env_logger::Target;
 use lazy_static::lazy_static;
 use log::LevelFilter;
+use std::fs::OpenOptions;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
 use tokio::sync::{RwLock, RwLockReadGuard};
+use url::Url;
 
 lazy_static! {
     static ref SETTINGS: RwLock<Args> = RwLock::new(Args::parse_validate());
@@ -13,32 +23,643 @@ lazy_static! {
 #[derive(Parser, Debug, Clone)]
 pub(crate) struct Args {
     /// Include today in calculations
-    #[arg(short, long, default_value = "false")]
+    #[arg(
+        short,
+        long,
+        default_value = "false",
+        env = "CLOCKIFY_FLEX_INCLUDE_TODAY"
+    )]
     pub include_today: bool,
 
     /// Clockify API token
-    #[arg(short, long)]
+    #[arg(short, long, env = "TOKEN", hide_env_values = true)]
     pub token: Option<Token>,
 
-    /// Start date equal or greater than 2022-01-01 in the format YYYY-MM-DD.
-    #[arg(short, long, value_parser = validate_date)]
+    /// Where the token was given, for doctor
+    #[arg(skip)]
+    pub token_source: Option<&'static str>,
+
+    /// Start date in the format YYYY-MM-DD, by default the date of the first time entry
+    #[arg(short, long, value_parser = validate_date, env = "CLOCKIFY_FLEX_START_DATE")]
     pub start_date: Option<NaiveDate>,
 
+    /// Calculate the results as if it was this date. Later items are treated as future.
+    #[arg(long, value_parser = validate_date)]
+    pub as_of: Option<NaiveDate>,
+
+    /// Last date of a closed period in the format YYYY-MM-DD, e.g. the end of the last fiscal
+    /// year. The date is included and later items are left out.
+    #[arg(short, long, value_parser = validate_date, env = "CLOCKIFY_FLEX_END_DATE", conflicts_with = "as_of")]
+    pub end_date: Option<NaiveDate>,
+
     /// Optional start balance in minutes
-    #[arg(short = 'b', long, requires = "start_date")]
+    #[arg(short = 'b', long, env = "CLOCKIFY_FLEX_START_BALANCE")]
     pub start_balance: Option<i64>,
 
+    /// Hours of a work day without expected working hours in the extra settings
+    #[arg(long, value_name = "HOURS", default_value = "7.5", value_parser = validate_work_day_hours, env = "CLOCKIFY_FLEX_WORK_DAY_HOURS")]
+    pub work_day_hours: f32,
+
+    /// Country of the public holidays as an ISO 3166 code, e.g. FI
+    #[arg(long, value_name = "CODE", default_value = "FI", value_parser = validate_country, env = "CLOCKIFY_FLEX_COUNTRY")]
+    pub country: String,
+
+    /// Use the options of this profile in config.toml, a table under [profiles], over the
+    /// options at the top of the file
+    #[arg(long, value_name = "NAME", env = "CLOCKIFY_FLEX_PROFILE")]
+    pub profile: Option<String>,
+
     /// Logging level
     #[arg(long, default_value = "warn")]
     pub log_level: LogLevel,
 
-    /// Logging output
+    /// Logging output, the file is `log` in the cache directory
     #[arg(long, default_value = "file")]
     pub log_output: LogOutput,
 
-    /// Enable debug features, such as saving clockify JSONs to disk.
+    /// Also fetch pending time off requests. They are shown separately and counted in forecasts.
+    #[arg(long, default_value = "false")]
+    pub include_pending: bool,
+
+    /// Don't count the time logged on these projects as worked, adds to excludeProjects
+    #[arg(long = "exclude-project", value_delimiter = ',')]
+    pub exclude_projects: Vec<String>,
+
+    /// Don't count the time logged with these tags as worked, adds to excludeTags
+    #[arg(long = "exclude-tag", value_delimiter = ',')]
+    pub exclude_tags: Vec<String>,
+
+    /// Show absence patterns, e.g. sick leaves clustering on Mondays
+    #[arg(long, default_value = "false")]
+    pub insights: bool,
+
+    /// Show the logged time per tag
+    #[arg(long, default_value = "false")]
+    pub by_tag: bool,
+
+    /// Show the logged time per client of the projects
+    #[arg(long, default_value = "false")]
+    pub by_client: bool,
+
+    /// Show the average, median and deviation of the logged time per working day and week,
+    /// and the average start and stop times per weekday
+    #[arg(long, default_value = "false")]
+    pub stats: bool,
+
+    /// Fetch the balance from every time entry, or from the daily totals of the summary report
+    /// with far fewer requests. The totals can't be broken down by tag or description.
+    #[arg(long, value_enum, default_value_t = EntrySource::Detailed)]
+    pub source: EntrySource,
+
+    /// Maximum number of date ranges fetched from Clockify at the same time
+    #[arg(long, default_value = "8")]
+    pub concurrency: usize,
+
+    /// Abort on Clockify records that can't be parsed instead of skipping them
+    #[arg(long, default_value = "false")]
+    pub strict: bool,
+
+    /// Print only the balance, e.g. +47:37, for shell prompts and scripts
+    #[arg(short, long, default_value = "false")]
+    pub quiet: bool,
+
+    /// Don't show the spinners and the progress bar, e.g. in the logs of cron jobs
+    #[arg(long, default_value = "false")]
+    pub no_spinner: bool,
+
+    /// Output format of the results
+    #[arg(short, long, default_value = "table", env = "CLOCKIFY_FLEX_OUTPUT")]
+    pub output: OutputFormat,
+
+    /// Language, number and date format of the results
+    #[arg(long, default_value = "en", env = "CLOCKIFY_FLEX_LOCALE")]
+    pub locale: Locale,
+
+    /// Format of the durations, `decimal` shows e.g. 7.62 h instead of 7 hours, 37 minutes
+    #[arg(long, default_value = "locale", env = "CLOCKIFY_FLEX_HOURS_FORMAT")]
+    pub hours_format: HoursFormat,
+
+    /// Colors of the results table, `auto` colors a terminal unless NO_COLOR is set
+    #[arg(long, default_value = "auto")]
+    pub color: ColorChoice,
+
+    /// Only these rows of the results in this order, e.g. `balance,worked,expected`
+    #[arg(long, value_delimiter = ',')]
+    pub rows: Vec<RowKey>,
+
+    /// Directory of the extra settings, defaults to ~/.config/clockify-flex
+    #[arg(long, value_name = "DIR")]
+    pub config: Option<PathBuf>,
+
+    /// Directory of the cache, history, audit log and debug dumps, defaults to
+    /// ~/.cache/clockify-flex
+    #[arg(long, value_name = "DIR")]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Enable debug features, such as saving clockify JSONs to the cache directory.
     #[arg(long, default_value = "false")]
     pub debug: bool,
+
+    /// Calculate from work_items_*.json and time_off_items_*.json files saved with --debug,
+    /// without the network
+    #[arg(long, num_args = 1..)]
+    pub from_dump: Vec<PathBuf>,
+
+    /// Answer the requests with the responses recorded with --debug in the directory instead
+    /// of the Clockify API, e.g. to reproduce a bug from the dumps of a user
+    #[arg(long, value_name = "DIR")]
+    pub replay: Option<PathBuf>,
+
+    /// URL of the Clockify API, e.g. a regional endpoint or a proxy in front of the API
+    #[arg(long, value_name = "URL", env = "CLOCKIFY_API_URL")]
+    pub api_url: Option<Url>,
+
+    /// URL of the Clockify reports API
+    #[arg(long, value_name = "URL", env = "CLOCKIFY_REPORTS_API_URL")]
+    pub reports_api_url: Option<Url>,
+
+    /// Proxy of all the requests, overrides HTTPS_PROXY, HTTP_PROXY and ALL_PROXY
+    #[arg(long, value_name = "URL")]
+    pub proxy: Option<Url>,
+
+    /// PEM file of root certificates to trust in addition to the system ones, e.g. of a
+    /// corporate proxy intercepting TLS
+    #[arg(long, value_name = "FILE", env = "CLOCKIFY_FLEX_CA_BUNDLE")]
+    pub ca_bundle: Option<PathBuf>,
+
+    /// Seconds until a request without a response fails
+    #[arg(long, value_name = "SECONDS", default_value = "30", value_parser = clap::value_parser!(u64).range(1..))]
+    pub request_timeout: u64,
+
+    /// Seconds until fetching the time entries or the time off fails, by default no limit
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub timeout: Option<u64>,
+
+    /// Fetch everything again instead of asking whether the stored responses have changed
+    #[arg(long, default_value = "false")]
+    pub fresh: bool,
+
+    /// Calculate from the archive imported with `import`, without the network
+    #[arg(long, default_value = "false")]
+    pub offline: bool,
+
+    /// Post a Slack compatible summary of the results to this webhook, overrides notifyWebhook
+    #[arg(long, value_name = "URL")]
+    pub notify_webhook: Option<Url>,
+
+    #[arg(long, value_name = "MINUTES", value_parser = clap::value_parser!(u64).range(1..))]
+    pub watch: Option<u64>,
+
+    /// Serve the balance as Prometheus metrics on this address, e.g. 127.0.0.1:9184
+    #[arg(long, value_name = "ADDR")]
+    pub serve_metrics: Option<SocketAddr>,
+
+    /// Refresh interval of the served metrics in minutes
+    #[arg(long, default_value = "15", requires = "serve_metrics", value_parser = clap::value_parser!(u64).range(1..))]
+    pub metrics_interval: u64,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum Command {
+    /// Sit in the system tray and show the balance in the tooltip
+    #[cfg(feature = "tray")]
+    Tray {
+        /// Refresh interval in minutes
+        #[arg(long, default_value = "15", value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+    },
+    /// Issue raw requests against the Clockify API, e.g. to capture payloads for bug reports
+    Api {
+        #[command(subcommand)]
+        action: ApiAction,
+    },
+    /// Save the token to the OS keyring, so that it's not needed in .env or the arguments
+    Auth {
+        #[command(subcommand)]
+        action: AuthAction,
+    },
+    /// List days that are likely data errors, e.g. missing or overlapping entries
+    Audit {
+        /// Also report unlogged gaps between entries longer than this many hours
+        #[arg(long, value_name = "HOURS")]
+        gaps: Option<f32>,
+    },
+    /// Print a contribution grid of the year, colored by worked vs expected hours
+    Calendar {
+        /// Year to show, defaults to the current year
+        #[arg(short, long)]
+        year: Option<i32>,
+    },
+    /// Cost of the overtime balance per workspace member, requires an admin token
+    Cost {
+        /// Only include the members with these emails
+        #[arg(long, value_delimiter = ',')]
+        emails: Vec<String>,
+
+        /// Also write the report as CSV to the path
+        #[arg(long)]
+        csv: Option<PathBuf>,
+    },
+    /// Plot the cumulative balance per week since the start date
+    Chart,
+    /// Create or check the config files
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Check the versions and the local files, e.g. the holidays dataset
+    Doctor,
+    /// Export the days to other tools
+    Export {
+        #[command(subcommand)]
+        action: ExportAction,
+    },
+    /// Project the balance to a future date using the booked vacations and flex days
+    Forecast {
+        /// Future date in the format YYYY-MM-DD
+        #[arg(long, value_parser = validate_future_date)]
+        until: NaiveDate,
+    },
+    /// Track progress toward the balance goals in the extra settings
+    Goals {
+        #[command(subcommand)]
+        action: Option<GoalsAction>,
+    },
+    /// Import an archive written with `export raw` for --offline, replacing the previous one
+    Import {
+        /// Directory of the archive
+        dir: PathBuf,
+    },
+    /// Manage the public holidays dataset
+    Holidays {
+        #[command(subcommand)]
+        action: HolidaysAction,
+    },
+    /// Per user and month matrix of the whole workspace for HR, requires an admin token
+    HrExport {
+        /// Year to export, defaults to the current year
+        #[arg(short, long)]
+        year: Option<i32>,
+
+        /// Only include the members with these emails
+        #[arg(long, value_delimiter = ',')]
+        emails: Vec<String>,
+
+        /// Path of the report, the format is chosen by the extension: .csv or .xlsx
+        #[arg(long)]
+        output: PathBuf,
+    },
+    /// Create a time entry for a day, e.g. one flagged by the audit as empty
+    Log {
+        /// Date of the entry
+        #[arg(value_parser = parse_date)]
+        date: NaiveDate,
+
+        /// Length of the entry in hours
+        #[arg(long)]
+        hours: f32,
+
+        /// Local start time of the entry
+        #[arg(long, default_value = "08:00", value_parser = parse_time)]
+        start: NaiveTime,
+
+        /// Exact name of the project
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Description of the time entry
+        #[arg(short, long, default_value = "")]
+        description: String,
+
+        /// Add the entry even if the day already has logged time
+        #[arg(long, default_value = "false")]
+        force: bool,
+    },
+    /// Per month breakdown of the worked and expected time with the timesheet completeness
+    Monthly {
+        /// Year to show, defaults to the current year
+        #[arg(short, long)]
+        year: Option<i32>,
+    },
+    /// Reports of the worked and expected time over longer periods
+    Report {
+        #[command(subcommand)]
+        action: ReportAction,
+    },
+    /// Calculate the paid out and carried over hours at a cutoff date
+    Payout {
+        /// Cutoff date in the format YYYY-MM-DD, today or in the future
+        #[arg(long, value_parser = parse_date)]
+        cutoff: NaiveDate,
+
+        /// Balance hours above this are paid out, overrides the payout rules of the settings
+        #[arg(long, requires = "multiplier")]
+        above: Option<f32>,
+
+        /// Multiplier for the paid out hours
+        #[arg(long, requires = "above")]
+        multiplier: Option<f32>,
+    },
+    /// Propose a schedule to recover a negative balance by a target date
+    Plan {
+        /// Target date in the format YYYY-MM-DD
+        #[arg(long, value_parser = validate_future_date)]
+        until: NaiveDate,
+
+        /// How the extra time is spread
+        #[arg(long, default_value = "even")]
+        strategy: PlanStrategy,
+
+        /// Longer days per week with the long-days strategy
+        #[arg(long, default_value = "2")]
+        long_days: usize,
+
+        /// Maximum working hours of a day, overrides maxDailyHours of the settings
+        #[arg(long)]
+        max_daily_hours: Option<f32>,
+    },
+    /// Request a flex day off in Clockify, checking the balance left after it
+    TakeFlex {
+        /// Date of the day off
+        #[arg(value_parser = parse_date)]
+        date: NaiveDate,
+
+        /// Note of the time off request
+        #[arg(long)]
+        note: Option<String>,
+
+        /// Don't ask for confirmation
+        #[arg(short, long, default_value = "false")]
+        yes: bool,
+
+        /// Request even if the balance would turn negative
+        #[arg(long, default_value = "false")]
+        force: bool,
+    },
+    /// Balance of every teammate, from their own tokens or with an admin token and the emails
+    Team {
+        /// API tokens of the teammates, one row per token
+        #[arg(
+            long,
+            value_delimiter = ',',
+            env = "CLOCKIFY_FLEX_TEAM_TOKENS",
+            hide_env_values = true,
+            conflicts_with = "emails"
+        )]
+        tokens: Vec<Token>,
+
+        /// Emails of the workspace members, all the members by default. Requires an admin token.
+        #[arg(long, value_delimiter = ',')]
+        emails: Vec<String>,
+
+        /// Also write the rows as CSV to the path
+        #[arg(long)]
+        csv: Option<PathBuf>,
+    },
+    /// Start or stop a Clockify timer
+    Timer {
+        #[command(subcommand)]
+        action: TimerAction,
+    },
+    /// Delete an entry or time off created by this tool, using the audit log
+    Undo {
+        /// ID of the created resource, defaults to the latest
+        id: Option<String>,
+
+        /// List the resources that can be undone
+        #[arg(long, default_value = "false")]
+        list: bool,
+    },
+    /// Balance trend over the saved snapshots of past runs
+    History {
+        /// Number of past months to show, at most 1200, all snapshots by default
+        #[arg(short, long, value_parser = clap::value_parser!(u32).range(1..=1200))]
+        months: Option<u32>,
+    },
+    /// Compact balance segment for shell prompts, from the latest snapshot without fetching
+    Prompt {
+        /// Format of the segment, `{balance}` is e.g. +6d2h, `{hours}` +47:37 and `{date}` the
+        /// date of the snapshot
+        #[arg(short, long, default_value = "⏱ {balance}")]
+        format: String,
+    },
+    /// Future booked absences by type per month with their balance impact
+    Upcoming {
+        /// Number of months to show, starting from the current month, at most 120
+        #[arg(short, long, default_value = "6", value_parser = clap::value_parser!(u32).range(1..=120))]
+        months: u32,
+    },
+    /// Show or edit the expected hours schedule of the settings
+    Schedule {
+        #[command(subcommand)]
+        action: ScheduleAction,
+    },
+    /// Print the work items of a day with its expected time and contribution to the balance
+    Show {
+        /// Date in the format YYYY-MM-DD
+        #[arg(value_parser = parse_date)]
+        date: NaiveDate,
+    },
+    /// Export or import the local state, e.g. to migrate to another machine
+    State {
+        #[command(subcommand)]
+        action: StateAction,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum ApiAction {
+    /// Pretty-print the JSON response of a GET request
+    Get {
+        /// Path relative to the API URL, e.g. v1/workspaces/{workspaceId}/users/{userId}
+        path: String,
+
+        /// Query parameter in the format key=value, can be repeated
+        #[arg(long, value_parser = parse_key_value)]
+        query: Vec<(String, String)>,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum AuthAction {
+    /// Prompt for the token and save it to the keyring after checking it works
+    Login,
+    /// Delete the token from the keyring
+    Logout,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum ConfigAction {
+    /// Create settings.json and config.toml, asking for the values not given as options
+    Init {
+        /// Email of the Clockify user
+        #[arg(long)]
+        email: Option<String>,
+
+        /// Expected working hours per weekday
+        #[arg(long)]
+        hours: Option<f32>,
+
+        /// Start date of the calculation in the format YYYY-MM-DD
+        #[arg(long, value_parser = validate_date)]
+        start_date: Option<NaiveDate>,
+
+        /// Balance on the start date in minutes
+        #[arg(long, requires = "start_date")]
+        start_balance: Option<i64>,
+
+        /// Overwrite the existing files
+        #[arg(long, default_value = "false")]
+        force: bool,
+    },
+    /// Report unknown fields, invalid values and overlapping or reversed date ranges of the
+    /// extra settings
+    Validate,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum HolidaysAction {
+    /// Download the latest dataset from the releases, verified with its SHA-256 checksum
+    Update,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum GoalsAction {
+    /// Print the progress toward the goals, the default
+    Show,
+    /// Add a goal or replace the one with the same name
+    Add {
+        /// Name of the goal, e.g. "Summer vacation"
+        name: String,
+
+        /// Target balance in hours
+        #[arg(long, allow_negative_numbers = true)]
+        hours: f32,
+
+        /// Date by which the balance should be reached in the format YYYY-MM-DD
+        #[arg(long, value_parser = parse_date)]
+        until: NaiveDate,
+    },
+    /// Remove a goal by name
+    Remove { name: String },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum ScheduleAction {
+    /// Print the effective expected hours per weekday as a grid of periods
+    Show,
+    /// Set the expected hours of a period, trimming the overlapping ranges
+    Set {
+        /// Start date in the format YYYY-MM-DD
+        #[arg(long, value_parser = parse_date)]
+        from: NaiveDate,
+
+        /// End date in the format YYYY-MM-DD
+        #[arg(long, value_parser = parse_date)]
+        until: NaiveDate,
+
+        /// Expected hours per day in half-hour steps, e.g. 7.5
+        #[arg(long)]
+        hours: f32,
+
+        /// Name of the range in the settings
+        #[arg(long)]
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum ReportAction {
+    /// Worked and expected time, vacations, sick days and the balance change per calendar year
+    Yearly {
+        /// Show the years side by side with the change from the previous year
+        #[arg(long, default_value = "false")]
+        compare: bool,
+    },
+    /// The same totals per quarter of the fiscal year
+    Quarterly {
+        /// Month when the fiscal year starts, e.g. 4 for April
+        #[arg(
+            long,
+            default_value = "1",
+            value_parser = clap::value_parser!(u32).range(1..=12),
+            env = "CLOCKIFY_FLEX_FISCAL_YEAR_START"
+        )]
+        fiscal_year_start: u32,
+    },
+    /// Time of the entries whose description matches the regular expression, per ISO week
+    Grep {
+        /// Regular expression matched anywhere in the description, e.g. "meeting|standup"
+        pattern: String,
+        /// Match regardless of the case
+        #[arg(short, long, default_value = "false")]
+        ignore_case: bool,
+    },
+    /// Consumed and remaining hours of the project budgets of the extra settings in the
+    /// current period, with the exhaustion date projected from the burn rate
+    Budgets,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum ExportAction {
+    /// Write the public holidays and time off as all-day events of an iCalendar file
+    Ics {
+        /// Path of the calendar, e.g. days-off.ics
+        #[arg(long)]
+        output: PathBuf,
+    },
+    /// Write the fetched time entries, time off requests and public holidays since the start
+    /// date to versioned JSON files: entries.json, timeoff.json and holidays.json
+    Raw {
+        /// Directory of the archive, created if it doesn't exist
+        #[arg(long)]
+        out: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum TimerAction {
+    /// Start a timer now
+    Start {
+        /// Exact name of the project
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Description of the time entry
+        #[arg(short, long, default_value = "")]
+        description: String,
+    },
+    /// Stop the running timer now
+    Stop,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum StateAction {
+    /// Bundle the cache and settings into a .tar.zst archive. The token is not included.
+    Export {
+        /// Archive path, e.g. state.tar.zst
+        path: PathBuf,
+    },
+    /// Restore the cache and settings from an exported archive
+    Import {
+        /// Archive path, e.g. state.tar.zst
+        path: PathBuf,
+
+        /// Overwrite the existing settings file
+        #[arg(long, default_value = "false")]
+        force: bool,
+    },
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub(crate) enum PlanStrategy {
+    /// A little extra on every working day
+    Even,
+    /// A few longer days per week
+    LongDays,
 }
 
 #[derive(ValueEnum, Clone, Debug)]
@@ -70,20 +691,72 @@ impl From<LogLevel> for LevelFilter {
     }
 }
 
-fn validate_date(s: &str) -> Result<NaiveDate, Error> {
+/// Setup logger
+pub(crate) fn setup_log(output: &LogOutput, level: &LogLevel) -> Result<(), Error> {
+    // TODO Async logger: https://docs.rs/tracing/0.1.40/tracing/
+    env_logger::builder()
+        .filter_level(level.clone().into())
+        .target(match output {
+            LogOutput::File => {
+                let path = utils::dirs::cache_path("log");
+                utils::dirs::create_parent(&path)?;
+                let log_file = OpenOptions::new()
+                    .append(true) // Open in append mode
+                    .create(true) // Create the file if it doesn't exist
+                    .open(path)?;
+                let boxed_log_file = Box::new(log_file);
+                Target::Pipe(boxed_log_file)
+            }
+            LogOutput::Console => Target::Stdout,
+        })
+        .init();
+    Ok(())
+}
+
+pub(crate) fn validate_date(s: &str) -> Result<NaiveDate, Error> {
     let date = NaiveDate::parse_from_str(s, "%Y-%m-%d")?;
-    let date2022 = NaiveDate::from_ymd_opt(2022, 1, 1).unwrap();
-    let today = Utc::now().date_naive();
+    if date > Utc::now().date_naive() {
+        return Err(Error::msg("Input cannot be greater than today!"));
+    }
+    Ok(date)
+}
+
+pub(crate) fn validate_work_day_hours(s: &str) -> Result<f32, Error> {
+    let hours = s.parse::<f32>()?;
+    if !(0.5..=24.0).contains(&hours) {
+        return Err(Error::msg("Work day hours must be between 0.5 and 24!"));
+    }
+    Ok(hours)
+}
 
-    let err_msg = if date > today {
-        "Input cannot be greater than today!"
-    } else if date < date2022 {
-        "Input has to be equal or greater than 2022-01-01!"
-    } else {
-        return Ok(date);
-    };
+pub(crate) fn validate_country(s: &str) -> Result<String, Error> {
+    if s.len() != 2 || !s.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(Error::msg("Expected a two letter country code, e.g. FI"));
+    }
+    Ok(s.to_ascii_uppercase())
+}
+
+fn parse_key_value(s: &str) -> Result<(String, String), Error> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| Error::msg(format!("Expected key=value, got '{s}'")))?;
+    Ok((key.to_owned(), value.to_owned()))
+}
+
+fn parse_date(s: &str) -> Result<NaiveDate, Error> {
+    Ok(NaiveDate::parse_from_str(s, "%Y-%m-%d")?)
+}
+
+fn parse_time(s: &str) -> Result<NaiveTime, Error> {
+    Ok(NaiveTime::parse_from_str(s, "%H:%M")?)
+}
 
-    Err(Error::msg(err_msg))
+fn validate_future_date(s: &str) -> Result<NaiveDate, Error> {
+    let date = parse_date(s)?;
+    if date <= Utc::now().date_naive() {
+        return Err(Error::msg("Input has to be in the future!"));
+    }
+    Ok(date)
 }
 
 impl Args {
@@ -93,12 +766,65 @@ impl Args {
             println!("If start_date is today, --include-today option must be used.");
             std::process::exit(1);
         }
+        if self.start_balance.is_some() && self.start_date.is_none() {
+            println!("--start-balance requires start_date.");
+            std::process::exit(1);
+        }
+        if let Some((start_date, end_date)) = self.start_date.zip(self.end_date) {
+            if end_date < start_date {
+                println!("--end-date cannot be before start_date.");
+                std::process::exit(1);
+            }
+        }
+        if let Some((start_date, as_of)) = self.start_date.zip(self.as_of) {
+            if as_of < start_date {
+                println!("--as-of cannot be before start_date.");
+                std::process::exit(1);
+            }
+        }
         Ok(())
     }
 
-    /// Parse and validate arguments
+    /// Spinners and progress bars are shown on the terminal
+    pub(crate) fn show_progress(&self) -> bool {
+        !self.quiet && !self.no_spinner
+    }
+
+    /// The Clockify API of the token at the URLs of the arguments
+    pub(crate) fn http_api(&self, token: &Token) -> Result<HttpApi, Error> {
+        let mut api = HttpApi::new(token)?;
+        if let Some(url) = &self.api_url {
+            api = api.api_url(url.clone());
+        }
+        if let Some(url) = &self.reports_api_url {
+            api = api.reports_api_url(url.clone());
+        }
+        if !self.fresh {
+            api = api.conditional(utils::dirs::cache_path("responses"));
+        }
+        Ok(api)
+    }
+
+    /// Read config.toml from the config directory for the options not given as arguments
+    fn apply_config_file(&mut self, matches: &ArgMatches) -> Result<(), Error> {
+        utils::dirs::init(self.config.clone(), self.cache_dir.clone())?;
+        let path = utils::dirs::config_path(CONFIG_FILE);
+        ConfigFile::read(&path, self.profile.as_deref())?.apply(self, matches)?;
+        utils::http::set_options(HttpOptions {
+            proxy: self.proxy.clone(),
+            ca_bundle: self.ca_bundle.clone(),
+            timeout: Duration::from_secs(self.request_timeout),
+        })
+    }
+
+    /// Parse and validate arguments, the arguments and the environment override config.toml
     pub(crate) fn parse_validate() -> Self {
-        let args = Self::parse();
+        let matches = Self::command().get_matches();
+        let mut args = Self::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+        if let Err(e) = args.apply_config_file(&matches) {
+            println!("{e}");
+            std::process::exit(1);
+        }
         if let Err(e) = args.validate() {
             println!("{e}");
             std::process::exit(1);
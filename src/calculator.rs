@@ -0,0 +1,903 @@
+use crate::clockify::{get_days_off, get_working_days, ClockifyClient};
+use crate::extra_settings::rules::DayEffect;
+use crate::extra_settings::schema::{
+    BalancePolicy, CustomDayCounting, CustomDayType, DayType, ExtraSettings, SchedulePeriod,
+};
+use crate::models::{Day, HolidayType, WorkDay};
+use crate::utils::resolution::{resolve_days, Conflict};
+use crate::utils::{self, get_public_holidays};
+use anyhow::Error;
+use chrono::{Datelike, Days, Months, NaiveDate};
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet, VecDeque};
+use tokio::join;
+
+/// Public holidays, working days and days off of a user, with one type per date
+#[derive(Clone)]
+pub struct Items {
+    pub public_holidays: Vec<Day>,
+    pub working_days: Vec<WorkDay>,
+    pub days_off: Vec<Day>,
+    /// Dates that had several classifications before the resolution
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Items {
+    /// Replace the items from the date on with the newer items, e.g. after refetching
+    /// only the recent days
+    pub fn merge(self, newer: Items, since: NaiveDate) -> Items {
+        fn merge<T>(
+            old: Vec<T>,
+            newer: Vec<T>,
+            date: impl Fn(&T) -> NaiveDate,
+            since: NaiveDate,
+        ) -> Vec<T> {
+            old.into_iter()
+                .filter(|item| date(item) < since)
+                .chain(newer.into_iter().filter(|item| date(item) >= since))
+                .collect()
+        }
+
+        Items {
+            public_holidays: merge(
+                self.public_holidays,
+                newer.public_holidays,
+                Day::date,
+                since,
+            ),
+            working_days: merge(self.working_days, newer.working_days, |wd| wd.date, since),
+            days_off: merge(self.days_off, newer.days_off, Day::date, since),
+            conflicts: merge(self.conflicts, newer.conflicts, |c| c.date, since),
+        }
+    }
+}
+
+/// Fetch the items and resolve the public holidays and days off into one type per date
+pub async fn get_items(
+    client: ClockifyClient,
+    since: NaiveDate,
+    user_settings: &ExtraSettings,
+) -> Result<Items, Error> {
+    get_items_between(client, since, None, user_settings).await
+}
+
+/// Items of the period, the time entries are fetched until the end date instead of today
+pub async fn get_items_between(
+    client: ClockifyClient,
+    since: NaiveDate,
+    until: Option<NaiveDate>,
+    user_settings: &ExtraSettings,
+) -> Result<Items, Error> {
+    let public_holidays = get_public_holidays(&since, user_settings);
+    let working_days = get_working_days(client.clone(), &since, until);
+    let days_off = get_days_off(client, &since, user_settings);
+    let (public_holidays, working_days, days_off) = join!(public_holidays, working_days, days_off);
+    Ok(build_items(
+        public_holidays
+            .map_err(|e| Error::msg(format!("Failed to get public holidays: {:?}", e)))?,
+        working_days.map_err(|e| Error::msg(format!("Failed to get working days: {:?}", e)))?,
+        days_off.map_err(|e| Error::msg(format!("Failed to get fays off: {:?}", e)))?,
+        user_settings,
+    ))
+}
+
+/// Drop the excluded work items and resolve the public holidays and days off
+pub fn build_items(
+    public_holidays: Vec<Day>,
+    working_days: Vec<WorkDay>,
+    days_off: Vec<Day>,
+    user_settings: &ExtraSettings,
+) -> Items {
+    let working_days = working_days
+        .into_iter()
+        .filter_map(|mut wd| {
+            wd.items.retain(|wi| !user_settings.is_excluded(wi));
+            (!wd.items.is_empty()).then_some(wd)
+        })
+        .collect();
+    let resolution = resolve_days(public_holidays, days_off, user_settings);
+    Items {
+        public_holidays: resolution.public_holidays,
+        working_days,
+        days_off: resolution.days_off,
+        conflicts: resolution.conflicts,
+    }
+}
+
+/// Vacation days of the current year against the yearly allowance
+pub struct VacationBudget {
+    pub earned: f32,
+    pub used: usize,
+    pub booked: usize,
+}
+
+impl VacationBudget {
+    pub fn remaining(&self) -> f32 {
+        self.earned - (self.used + self.booked) as f32
+    }
+}
+
+/// Days and time of a custom day type
+pub struct CustomDayResult {
+    pub name: String,
+    pub counting: CustomDayCounting,
+    /// Time off days and days with time logged on the projects of the type
+    pub day_count: usize,
+    /// Logged time and the expected time of the time off days
+    pub time_sec: i64,
+}
+
+/// Take the entries on the projects of the custom day types out of the working days, except
+/// for the types counted as worked. Days left without entries are dropped.
+fn take_custom_time<'a>(
+    working_days: &mut Vec<WorkDay>,
+    user_settings: &'a ExtraSettings,
+) -> Vec<(NaiveDate, &'a CustomDayType, i64)> {
+    let mut custom_time = Vec::new();
+    for wd in working_days.iter_mut() {
+        wd.items
+            .retain(|wi| match user_settings.custom_project_type(wi) {
+                Some(custom) => {
+                    custom_time.push((wd.date, custom, wi.duration()));
+                    custom.counting == CustomDayCounting::Worked
+                }
+                None => true,
+            });
+    }
+    working_days.retain(|wd| !wd.items.is_empty());
+    custom_time
+}
+
+pub struct Results {
+    pub first_working_day: NaiveDate,
+    pub working_day_count: usize,
+    pub worked_time: i64,
+    pub parental_leave_day_count: usize,
+    pub partial_parental_leave_day_count: usize,
+    pub partial_parental_leave_sec: i64,
+    pub held_vacation_day_count: usize,
+    pub future_vacation_day_count: usize,
+    pub unpaid_leave_day_count: usize,
+    pub filtered_expected_working_day_count: usize,
+    pub public_holiday_count: usize,
+    pub sick_leave_day_count: usize,
+    pub held_flex_time_off_day_count: usize,
+    pub future_flex_time_off_day_count: usize,
+    pub longest_working_day: WorkDay,
+    pub vacation_budget: Option<VacationBudget>,
+    pub custom_days: Vec<CustomDayResult>,
+    /// Long days with a break deducted, and the deducted time
+    pub break_deducted_day_count: usize,
+    pub break_deducted_sec: i64,
+    pub weekend_work_day_count: usize,
+    /// Time logged on weekends as counted in the worked time
+    pub weekend_work_sec: i64,
+    /// Extra worked time of the overtime rules
+    pub overtime_bonus_sec: i64,
+    /// Days with overlapping entries, the overlap is counted once
+    pub overlap_day_count: usize,
+    /// Weekdays of pending time off requests, when they are fetched
+    pub pending_time_off_day_count: Option<usize>,
+    /// Balance after the cap and the expiry, when the settings have a balance policy
+    pub policy_balance: Option<PolicyBalance>,
+    /// Weekdays of booked vacation that fell on a public holiday and were not used
+    pub reclaimed_vacation_day_count: usize,
+    pub expected_working_time_sec: i64,
+    pub balance: i64,
+    /// Length of a work day of the calculation, the days of the durations are this long
+    pub work_day_hours: f32,
+}
+
+impl Results {
+    /// Balance adjusted by the balance policy, when the settings have one
+    pub fn policy_adjusted_balance(&self) -> i64 {
+        self.policy_balance
+            .as_ref()
+            .map_or(self.balance, |policy| policy.balance)
+    }
+
+    pub fn total_flex_time_off_day_count(&self) -> usize {
+        self.held_flex_time_off_day_count + self.future_flex_time_off_day_count
+    }
+
+    pub fn unfiltered_expected_working_day_count(&self) -> usize {
+        self.filtered_expected_working_day_count
+            + self.public_holiday_count
+            + self.sick_leave_day_count
+    }
+
+    pub fn total_weekdays_since_start(&self) -> usize {
+        self.public_holiday_count + self.sick_leave_day_count + self.working_day_count
+    }
+
+    pub fn weekdays_sick_leaves_filtered_since_start(&self) -> usize {
+        self.working_day_count + self.public_holiday_count
+    }
+
+    pub fn weekdays_public_holidays_filtered_since_start(&self) -> usize {
+        self.working_day_count + self.sick_leave_day_count
+    }
+}
+
+/// Everything the calculation depends on besides the items, so that the results are
+/// reproducible and can be calculated as of any date
+#[derive(Clone, Debug)]
+pub struct CalculationContext {
+    /// Later items are in the future
+    pub today: NaiveDate,
+    /// Last date of a closed period, later items are left out
+    pub end: Option<NaiveDate>,
+    /// Expected hours of a weekday without expected working hours in the settings
+    pub work_day_hours: f32,
+    pub settings: ExtraSettings,
+}
+
+impl CalculationContext {
+    /// Context of the current date with the work day of the run
+    pub fn new(settings: ExtraSettings) -> Self {
+        Self {
+            today: utils::today(),
+            end: None,
+            work_day_hours: utils::work_day_hours(),
+            settings,
+        }
+    }
+
+    /// Calculate as if the date was today
+    pub fn as_of(mut self, today: NaiveDate) -> Self {
+        self.today = today;
+        self
+    }
+
+    /// Calculate the period until the date, the date included
+    pub fn until(mut self, end: NaiveDate) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    /// Last date of the calculation, today or the end of the period
+    pub fn last_date(&self) -> NaiveDate {
+        self.end.map_or(self.today, |end| end.min(self.today))
+    }
+
+    /// Last date counted in the balance, the day before the last date when it is not included
+    pub fn last_counted_date(&self, include_today: bool) -> NaiveDate {
+        let last = self.last_date();
+        if self.includes_last_date(include_today) {
+            last
+        } else {
+            last.pred_opt().unwrap_or(last)
+        }
+    }
+
+    /// Whether the last date is counted, the end of a closed period always is
+    pub fn includes_last_date(&self, include_today: bool) -> bool {
+        include_today || self.end.is_some_and(|end| end <= self.today)
+    }
+
+    fn is_past(&self, date: &NaiveDate) -> bool {
+        date <= &self.last_date()
+    }
+
+    /// Expected seconds of the date without the partial parental leave
+    pub fn expected_working_secs(&self, date: &NaiveDate) -> i64 {
+        self.settings
+            .expected_working_secs(date)
+            .unwrap_or((self.work_day_hours * 3600.0) as i64)
+    }
+
+    /// Expected seconds of the date with the partial parental leave
+    pub fn expected_day_secs(&self, date: &NaiveDate) -> i64 {
+        let ratio = self.settings.parental_leave_work_ratio(date).unwrap_or(1.0);
+        (self.expected_working_secs(date) as f32 * ratio) as i64
+    }
+
+    /// Effective expected hours per weekday between the dates, merging the weeks with the same
+    /// hours
+    pub fn schedule_periods(&self, start: NaiveDate, end: NaiveDate) -> Vec<SchedulePeriod> {
+        let mut periods: Vec<SchedulePeriod> = Vec::new();
+        let mut monday = start - Days::new(start.weekday().num_days_from_monday() as u64);
+        while monday <= end {
+            let mut hours = [0.0; 5];
+            for (i, hours) in hours.iter_mut().enumerate() {
+                let date = monday + Days::new(i as u64);
+                *hours = self.expected_day_secs(&date) as f32 / 3600.0;
+            }
+            let sunday = (monday + Days::new(6)).min(end);
+            match periods.last_mut() {
+                Some(period) if period.hours == hours => period.end = sunday,
+                _ => periods.push(SchedulePeriod {
+                    start: monday.max(start),
+                    end: sunday,
+                    hours,
+                }),
+            }
+            monday = monday + Days::new(7);
+        }
+        periods
+    }
+}
+
+/// Calculates the balance of a user from the resolved items
+#[derive(Clone, Debug)]
+pub struct BalanceCalculator {
+    context: CalculationContext,
+    include_today: bool,
+    /// In minutes
+    start_balance: i64,
+}
+
+impl BalanceCalculator {
+    pub fn new(context: CalculationContext) -> Self {
+        Self {
+            context,
+            include_today: false,
+            start_balance: 0,
+        }
+    }
+
+    /// Include today in the calculations
+    pub fn include_today(mut self, include_today: bool) -> Self {
+        self.include_today = include_today;
+        self
+    }
+
+    /// Balance before the start date, in minutes
+    pub fn start_balance(mut self, minutes: i64) -> Self {
+        self.start_balance = minutes;
+        self
+    }
+
+    pub fn calculate(
+        &self,
+        mut public_holidays: Vec<Day>,
+        mut working_days: Vec<WorkDay>,
+        mut days_off: Vec<Day>,
+    ) -> Result<Results, Error> {
+        let context = &self.context;
+        let include_today = context.includes_last_date(self.include_today);
+        let user_settings = &context.settings;
+        let today = context.last_date();
+        let first_working_day = working_days
+            .iter()
+            .min_by_key(|wd| wd.date)
+            .ok_or(Error::msg("Working days is empty"))?
+            .date;
+        let mut all_weekdays = utils::DateRange(first_working_day, today)
+            .filter(utils::is_weekday)
+            .collect_vec();
+
+        // Items are fetched until the actual today
+        working_days.retain(|wd| wd.date <= today);
+        days_off.retain(|do_| !matches!(do_, Day::Sick(_)) || do_.date() <= today);
+        // Nothing after a closed period is booked in it
+        if context.end.is_some() {
+            public_holidays.retain(|phd| phd.date() <= today);
+            days_off.retain(|do_| do_.date() <= today);
+        }
+        if !include_today {
+            working_days.retain(|wd| wd.date < today);
+            public_holidays.retain(|phd| phd.date() < today);
+            days_off.retain(|do_| {
+                matches!(do_, Day::Holiday(_)) || matches!(do_, Day::Sick(_)) && do_.date() < today
+            });
+            all_weekdays.retain(|d| d < &today)
+        }
+        working_days.retain(|wd| !user_settings.is_ignored_work_day(wd));
+        let custom_time = take_custom_time(&mut working_days, user_settings);
+
+        // How the classified days count, from the day rules or the policies of the types
+        let effects = public_holidays
+            .iter()
+            .filter(|day| first_working_day < day.date())
+            .chain(&days_off)
+            .filter(|day| utils::is_weekday(&day.date()) && !user_settings.is_ignored(day))
+            .map(|day| (day.date(), user_settings.day_effect(day)))
+            .collect::<HashMap<NaiveDate, DayEffect>>();
+        let day_types = public_holidays
+            .iter()
+            .chain(&days_off)
+            .filter(|day| !user_settings.is_ignored(day))
+            .map(|day| (day.date(), day.type_()))
+            .collect::<HashMap<NaiveDate, DayType>>();
+
+        // Overlapping time is counted once in the durations
+        let mut overlap_day_count = 0;
+        for wd in &working_days {
+            let overlaps = wd.overlaps();
+            for (a, b) in &overlaps {
+                log::warn!(
+                    "Overlapping entries on {}: \"{}\" and \"{}\"",
+                    wd.date,
+                    a.description(),
+                    b.description()
+                );
+            }
+            if !overlaps.is_empty() {
+                overlap_day_count += 1;
+            }
+        }
+
+        let longest_working_day = working_days
+            .iter()
+            .max_by_key(|wd| wd.duration())
+            .ok_or(Error::msg("Days iterator is empty!"))?
+            .to_owned();
+
+        let public_holidays_filtered = public_holidays
+            .into_iter()
+            .filter_map(|day| {
+                let date = day.date();
+                if context.is_past(&date)
+                    && utils::is_weekday(&date)
+                    && first_working_day < date
+                    && !user_settings.is_ignored(&day)
+                {
+                    Some(date)
+                } else {
+                    None
+                }
+            })
+            .collect_vec();
+
+        let public_holiday_count = public_holidays_filtered.len();
+
+        let (sick_leave_days, time_off_days): (Vec<Day>, Vec<Day>) = days_off
+            .into_iter()
+            .partition(|day| matches!(day, Day::Sick(_)));
+        let sick_leave_days = sick_leave_days
+            .into_iter()
+            .map(Day::into_date)
+            .collect_vec();
+        let sick_leave_day_count = sick_leave_days.len();
+
+        let (parental_leave_days, time_off_days): (Vec<Day>, Vec<Day>) =
+            time_off_days.into_iter().partition(|day| match day {
+                Day::Holiday(hd) => matches!(hd.type_, HolidayType::ParentalLeave),
+                _ => false,
+            });
+        let parental_leave_days = parental_leave_days
+            .into_iter()
+            .filter_map(|d| {
+                if !utils::is_weekday(&d.date()) || user_settings.is_ignored(&d) {
+                    return None;
+                }
+                Some(Day::into_date(d))
+            })
+            .collect_vec();
+        let parental_leave_day_count = parental_leave_days.len();
+
+        let (vacation_days, time_off_days): (Vec<Day>, Vec<Day>) =
+            time_off_days.into_iter().partition(|day| match day {
+                Day::Holiday(hd) => matches!(hd.type_, HolidayType::Vacation),
+                _ => false,
+            });
+        let vacation_days = vacation_days
+            .into_iter()
+            .filter_map(|d| {
+                if !utils::is_weekday(&d.date()) || user_settings.is_ignored(&d) {
+                    return None;
+                }
+                Some(Day::into_date(d))
+            })
+            .collect_vec();
+        let (held_vacation_days, future_vacation_days): (Vec<NaiveDate>, Vec<NaiveDate>) =
+            vacation_days
+                .into_iter()
+                .partition(|day| day < &today || (include_today && day == &today));
+        let held_vacation_day_count = held_vacation_days.len();
+        let future_vacation_day_count = future_vacation_days.len();
+
+        // Vacation days of the current year against the yearly allowance
+        let vacation_budget = user_settings.vacation_allowance().map(|allowance| {
+            let in_this_year =
+                |days: &Vec<NaiveDate>| days.iter().filter(|d| d.year() == today.year()).count();
+            VacationBudget {
+                earned: allowance.earned_by(&today),
+                used: in_this_year(&held_vacation_days),
+                booked: in_this_year(&future_vacation_days),
+            }
+        });
+
+        let (unpaid_leave_days, time_off_days): (Vec<Day>, Vec<Day>) =
+            time_off_days.into_iter().partition(|day| match day {
+                Day::Holiday(hd) => matches!(hd.type_, HolidayType::UnpaidLeave),
+                _ => false,
+            });
+        let unpaid_leave_days = unpaid_leave_days
+            .into_iter()
+            .filter_map(|d| {
+                if !utils::is_weekday(&d.date()) || user_settings.is_ignored(&d) {
+                    return None;
+                }
+                Some(Day::into_date(d))
+            })
+            .filter(|d| context.is_past(d))
+            .collect_vec();
+        let unpaid_leave_day_count = unpaid_leave_days.len();
+
+        let (custom_days, time_off_days): (Vec<Day>, Vec<Day>) = time_off_days
+            .into_iter()
+            .partition(|day| matches!(day.type_(), DayType::Custom(_)));
+        let custom_days = custom_days
+            .into_iter()
+            .filter_map(|d| match d.type_() {
+                DayType::Custom(name)
+                    if utils::is_weekday(&d.date())
+                        && context.is_past(&d.date())
+                        && !user_settings.is_ignored(&d) =>
+                {
+                    Some((d.date(), name))
+                }
+                _ => None,
+            })
+            .collect_vec();
+
+        let (held_flex_time_off_days, future_flex_time_off_days): (Vec<NaiveDate>, Vec<NaiveDate>) =
+            time_off_days
+                .into_iter()
+                .filter_map(|d| {
+                    if !utils::is_weekday(&d.date()) || user_settings.is_ignored(&d) {
+                        return None;
+                    }
+                    Some(Day::into_date(d))
+                })
+                .partition(|d| context.is_past(d));
+        let held_flex_time_off_day_count = held_flex_time_off_days.len();
+        let future_flex_time_off_day_count = future_flex_time_off_days.len();
+
+        let filtered_expected_working_days = all_weekdays
+            .into_iter()
+            .filter(|day| {
+                !matches!(
+                    effects.get(day),
+                    Some(DayEffect::NotExpected | DayEffect::PartialCredit)
+                )
+            })
+            .collect_vec();
+
+        let filtered_expected_working_day_count = filtered_expected_working_days.len();
+
+        // Partial parental leave scales down the expected time, the rest is parental leave
+        let (partial_parental_leave_day_count, partial_parental_leave_sec) =
+            filtered_expected_working_days
+                .iter()
+                .filter_map(|day| {
+                    let ratio = user_settings.parental_leave_work_ratio(day)?;
+                    let day_secs = context.expected_working_secs(day);
+                    Some(day_secs - (day_secs as f32 * ratio) as i64)
+                })
+                .fold((0, 0), |(count, total), secs| (count + 1, total + secs));
+
+        // With partial credit, e.g. sick days, logged time only counts beyond the expected time
+        let partial_credit_deducted_sec = working_days
+            .iter()
+            .filter(|wd| effects.get(&wd.date) == Some(&DayEffect::PartialCredit))
+            .map(|wd| {
+                user_settings
+                    .logged_secs(wd)
+                    .min(context.expected_day_secs(&wd.date))
+            })
+            .sum::<i64>();
+        let credited_sec = filtered_expected_working_days
+            .iter()
+            .filter(|day| effects.get(day) == Some(&DayEffect::Credited))
+            .map(|day| context.expected_day_secs(day))
+            .sum::<i64>();
+
+        // Time logged on the custom types reducing the expected time is deducted from it, on
+        // the expected weekdays and up to the expected time of the day
+        let expected_dates = filtered_expected_working_days
+            .iter()
+            .filter(|day| effects.get(day).is_none_or(|e| *e == DayEffect::Expected))
+            .collect::<HashSet<_>>();
+        let mut custom_deducted = HashMap::new();
+        for (date, custom, secs) in &custom_time {
+            if custom.counting == CustomDayCounting::ReducesExpected
+                && expected_dates.contains(date)
+            {
+                *custom_deducted.entry(*date).or_insert(0) += secs;
+            }
+        }
+        let custom_deducted_sec = custom_deducted
+            .into_iter()
+            .map(|(date, secs)| secs.min(context.expected_working_secs(&date)))
+            .sum::<i64>();
+        let custom_days = user_settings
+            .custom_day_types()
+            .iter()
+            .map(|custom| {
+                let days_off = custom_days
+                    .iter()
+                    .filter(|(_, name)| *name == custom.name)
+                    .map(|(date, _)| *date);
+                let logged = custom_time
+                    .iter()
+                    .filter(|(_, t, _)| t.name == custom.name)
+                    .collect_vec();
+                CustomDayResult {
+                    name: custom.name.clone(),
+                    counting: custom.counting,
+                    day_count: days_off
+                        .clone()
+                        .chain(logged.iter().map(|(date, _, _)| *date))
+                        .unique()
+                        .count(),
+                    time_sec: days_off
+                        .map(|date| context.expected_working_secs(&date))
+                        .sum::<i64>()
+                        + logged.iter().map(|(_, _, secs)| secs).sum::<i64>(),
+                }
+            })
+            .collect_vec();
+
+        let expected_working_time_sec = filtered_expected_working_days
+            .iter()
+            .map(|day| context.expected_working_secs(day))
+            .sum::<i64>()
+            - partial_parental_leave_sec
+            - custom_deducted_sec;
+        // Breaks are deducted from the long days when they are not tracked
+        let break_days = working_days
+            .iter()
+            .map(|wd| user_settings.break_secs(user_settings.rounded_secs(wd)))
+            .filter(|secs| *secs > 0)
+            .collect_vec();
+        let break_deducted_day_count = break_days.len();
+        let break_deducted_sec = break_days.iter().sum::<i64>();
+
+        // Time logged on weekends is counted by the weekendWork setting
+        let weekend_work_days = working_days
+            .iter()
+            .filter(|wd| !utils::is_weekday(&wd.date))
+            .collect_vec();
+        let weekend_work_day_count = weekend_work_days.len();
+        let weekend_work_sec = weekend_work_days
+            .iter()
+            .map(|wd| user_settings.counted_work_secs(&wd.date, user_settings.logged_secs(wd)))
+            .sum::<i64>();
+        // Enhanced accrual of the overtime rules, by the resolved type of the date
+        let overtime_bonus_sec = working_days
+            .iter()
+            .map(|wd| {
+                let day_type = day_types.get(&wd.date).unwrap_or(&DayType::WorkingDay);
+                user_settings.overtime_bonus_secs(&wd.date, day_type, user_settings.logged_secs(wd))
+            })
+            .sum::<i64>();
+        let total_worked_time_sec = working_days
+            .iter()
+            .map(|wd| user_settings.counted_work_secs(&wd.date, user_settings.logged_secs(wd)))
+            .sum::<i64>()
+            - partial_credit_deducted_sec
+            + credited_sec
+            + overtime_bonus_sec;
+        let working_day_count = working_days.len();
+
+        let start_balance = 60i64 * self.start_balance;
+        let balance = start_balance + total_worked_time_sec - expected_working_time_sec;
+
+        Ok(Results {
+            first_working_day,
+            working_day_count,
+            public_holiday_count,
+            parental_leave_day_count,
+            partial_parental_leave_day_count,
+            partial_parental_leave_sec,
+            held_vacation_day_count,
+            future_vacation_day_count,
+            unpaid_leave_day_count,
+            filtered_expected_working_day_count,
+            sick_leave_day_count,
+            held_flex_time_off_day_count,
+            expected_working_time_sec,
+            future_flex_time_off_day_count,
+            longest_working_day,
+            vacation_budget,
+            custom_days,
+            break_deducted_day_count,
+            break_deducted_sec,
+            weekend_work_day_count,
+            weekend_work_sec,
+            overtime_bonus_sec,
+            overlap_day_count,
+            pending_time_off_day_count: None,
+            reclaimed_vacation_day_count: 0,
+            policy_balance: None,
+            worked_time: total_worked_time_sec,
+            balance,
+            work_day_hours: context.work_day_hours,
+        })
+    }
+}
+
+/// Worked time counted to the balance and the expected time of a single day, in seconds
+#[derive(Clone, Debug)]
+pub struct DayBalance {
+    pub date: NaiveDate,
+    pub counted: i64,
+    pub expected: i64,
+}
+
+impl DayBalance {
+    /// Balance contribution of the day
+    pub fn contribution(&self) -> i64 {
+        self.counted - self.expected
+    }
+
+    /// Balance of the day from the worked time and the resolved day on the date
+    pub fn new(
+        date: NaiveDate,
+        worked_sec: i64,
+        day: Option<&Day>,
+        context: &CalculationContext,
+    ) -> Self {
+        let user_settings = &context.settings;
+        let expected_day_sec = context.expected_day_secs(&date);
+        let effect = day.map(|d| user_settings.day_effect(d));
+        let (expected, counted) = match effect {
+            _ if !utils::is_weekday(&date) => {
+                (0, user_settings.counted_work_secs(&date, worked_sec))
+            }
+            None | Some(DayEffect::Expected) => (expected_day_sec, worked_sec),
+            Some(DayEffect::NotExpected) => (0, worked_sec),
+            Some(DayEffect::Credited) => (expected_day_sec, worked_sec + expected_day_sec),
+            Some(DayEffect::PartialCredit) => (0, worked_sec - worked_sec.min(expected_day_sec)),
+        };
+        let day_type = day.map_or(DayType::WorkingDay, Day::type_);
+        Self {
+            date,
+            counted: counted + user_settings.overtime_bonus_secs(&date, &day_type, worked_sec),
+            expected,
+        }
+    }
+}
+
+/// Balance of every day from the first working day until the last counted date of the
+/// context. Ignored days are left out.
+pub fn daily_balances(
+    items: &Items,
+    context: &CalculationContext,
+    include_today: bool,
+) -> Vec<DayBalance> {
+    let user_settings = &context.settings;
+    let until = context.last_counted_date(include_today);
+    let mut working_days = items
+        .working_days
+        .iter()
+        .filter(|wd| !user_settings.is_ignored_work_day(wd))
+        .cloned()
+        .collect_vec();
+    let Some(first_working_day) = working_days.iter().map(|wd| wd.date).min() else {
+        return Vec::new();
+    };
+
+    let mut deducted = HashMap::new();
+    for (date, custom, secs) in take_custom_time(&mut working_days, user_settings) {
+        if custom.counting == CustomDayCounting::ReducesExpected {
+            *deducted.entry(date).or_insert(0) += secs;
+        }
+    }
+    let mut worked = HashMap::new();
+    for wd in working_days {
+        *worked.entry(wd.date).or_insert(0) += user_settings.logged_secs(&wd);
+    }
+    let days = items
+        .public_holidays
+        .iter()
+        .chain(&items.days_off)
+        .map(|d| (d.date(), d))
+        .collect::<HashMap<NaiveDate, &Day>>();
+
+    utils::DateRange(first_working_day, until)
+        .filter(|date| !days.get(date).is_some_and(|d| user_settings.is_ignored(d)))
+        .map(|date| {
+            let worked_sec = worked.get(&date).copied().unwrap_or(0);
+            let day = days.get(&date).copied();
+            let mut balance = DayBalance::new(date, worked_sec, day, context);
+            // Only the expected weekdays are reduced, up to the expected time of the day
+            let expected_day =
+                day.is_none_or(|d| user_settings.day_effect(d) == DayEffect::Expected);
+            if expected_day {
+                let deducted_sec = deducted.get(&date).copied().unwrap_or(0);
+                balance.expected -= deducted_sec.min(balance.expected);
+            }
+            balance
+        })
+        .collect()
+}
+
+/// Balance adjusted by the cap and the expiry of the balance policy, in seconds
+#[derive(Clone, Debug)]
+pub struct PolicyBalance {
+    pub balance: i64,
+    /// Hours lost to the cap and the expiry so far
+    pub lost_sec: i64,
+    /// Hours that expire within the warning days unless they are used
+    pub expiring_sec: i64,
+    pub warn_days: u32,
+    /// The balance is at the cap, further hours are lost
+    pub at_cap: bool,
+}
+
+/// Apply the cap and the expiry of the balance policy to the daily balances. Earned hours
+/// are kept as lots by date, and the oldest lots are used first.
+pub fn apply_balance_policy(
+    days: &[DayBalance],
+    start_balance_sec: i64,
+    balance: i64,
+    policy: &BalancePolicy,
+    today: NaiveDate,
+) -> PolicyBalance {
+    let cap_sec = policy.cap_hours.map(|hours| (hours * 3600.0) as i64);
+    let expires = |date: NaiveDate| {
+        policy
+            .expiry_months
+            .and_then(|months| date.checked_add_months(Months::new(months)))
+    };
+    let mut lots: VecDeque<(NaiveDate, i64)> = VecDeque::new();
+    let mut debt = 0;
+    let mut lost_sec = 0;
+    let first_day = days.first().map_or(today, |day| day.date);
+    let contributions = std::iter::once((first_day, start_balance_sec))
+        .chain(days.iter().map(|day| (day.date, day.contribution())));
+
+    for (date, mut secs) in contributions {
+        while let Some((earned, lot)) = lots.front().copied() {
+            if expires(earned).is_none_or(|expiry| expiry > date) {
+                break;
+            }
+            lost_sec += lot;
+            lots.pop_front();
+        }
+        if secs > 0 {
+            let paid = secs.min(debt);
+            debt -= paid;
+            secs -= paid;
+            if secs > 0 {
+                lots.push_back((date, secs));
+            }
+        } else {
+            secs = -secs;
+            while secs > 0 {
+                let Some(lot) = lots.front_mut() else {
+                    debt += secs;
+                    break;
+                };
+                let used = secs.min(lot.1);
+                lot.1 -= used;
+                secs -= used;
+                if lot.1 == 0 {
+                    lots.pop_front();
+                }
+            }
+        }
+        if let Some(cap_sec) = cap_sec {
+            let mut excess = lots.iter().map(|(_, lot)| lot).sum::<i64>() - cap_sec;
+            while excess > 0 {
+                let Some(lot) = lots.back_mut() else { break };
+                let removed = excess.min(lot.1);
+                lot.1 -= removed;
+                excess -= removed;
+                lost_sec += removed;
+                if lot.1 == 0 {
+                    lots.pop_back();
+                }
+            }
+        }
+    }
+
+    let warn_until = today + Days::new(policy.warn_days.into());
+    let expiring_sec = lots
+        .iter()
+        .filter(|(earned, _)| expires(*earned).is_some_and(|expiry| expiry <= warn_until))
+        .map(|(_, lot)| lot)
+        .sum();
+    let kept_sec = lots.iter().map(|(_, lot)| lot).sum::<i64>();
+    PolicyBalance {
+        balance: balance - lost_sec,
+        lost_sec,
+        expiring_sec,
+        warn_days: policy.warn_days,
+        at_cap: cap_sec.is_some_and(|cap_sec| kept_sec >= cap_sec),
+    }
+}
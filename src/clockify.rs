@@ -1,44 +1,89 @@
-use crate::args::get_settings;
+use crate::extra_settings::schema::ExtraSettings;
 use crate::models::{Day, Holiday, HolidayType, SickLeaveDay, WorkDay, WorkItem};
+use crate::utils::audit::{self, AuditAction, AuditEntry};
 use crate::utils::{self, json_to_disk};
 use anyhow::Error;
-use chrono::{DateTime, NaiveDate, NaiveTime, TimeDelta, TimeZone, Utc};
-use futures::future::join_all;
+use chrono::{DateTime, NaiveDate, NaiveTime, SecondsFormat, TimeDelta, TimeZone, Utc};
+use futures::stream::{self, StreamExt};
+use indicatif::{ProgressBar, ProgressStyle};
 use itertools::Itertools;
-use lazy_static::lazy_static;
-use reqwest::header::{HeaderMap, HeaderValue};
-use reqwest::Client;
-use serde::{Deserialize, Deserializer, Serialize};
+use reqwest::header::HeaderValue;
+use serde::de::{DeserializeOwned, DeserializeSeed, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::value::RawValue;
 use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
-use std::{fmt, thread, time};
 use tokio::time::sleep;
-use url::Url;
 
-lazy_static! {
-    static ref API_URL: Url =
-        Url::parse("https://global.api.clockify.me/").expect("Cannot parse clockify url!");
-}
+pub mod api;
+
+pub use api::{ApiHost, ApiRequest, ApiResponse, ClockifyApi, HttpApi, RecordedApi};
+
+/// Clockify API token. It's never printed, logged or serialized, and the hash is used as
+/// the cache key instead.
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct Token(String);
 
-#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq, PartialOrd, Hash)]
-pub(crate) struct Token(String);
+const KEYRING_SERVICE: &str = "clockify-flex-rust";
+const KEYRING_USER: &str = "token";
 
 impl Token {
-    pub(crate) fn new(token: &str) -> Self {
+    pub fn new(token: &str) -> Self {
         Token(token.to_owned())
     }
+
+    /// Hex SHA-256 of the token, e.g. for keys of the cache
+    pub fn hash(&self) -> String {
+        utils::sha256_hex(self.0.as_bytes())
+    }
+
+    fn keyring_entry() -> Result<keyring::Entry, Error> {
+        Ok(keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)?)
+    }
+
+    /// Token saved in the OS keyring, if any
+    pub fn from_keyring() -> Result<Option<Self>, Error> {
+        match Self::keyring_entry()?.get_password() {
+            Ok(token) => Ok(Some(Token(token))),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Save the token to the OS keyring, replacing the earlier one
+    pub fn save_to_keyring(&self) -> Result<(), Error> {
+        Ok(Self::keyring_entry()?.set_password(&self.0)?)
+    }
+
+    /// Delete the token from the OS keyring, returns false if there was none
+    pub fn delete_from_keyring() -> Result<bool, Error> {
+        match Self::keyring_entry()?.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
 }
 
-impl fmt::Display for Token {
+impl fmt::Debug for Token {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "Token(<redacted>)")
     }
 }
 
 impl From<&Token> for HeaderValue {
     fn from(val: &Token) -> Self {
-        HeaderValue::from_str(&val.0)
-            .unwrap_or_else(|_| panic!("Failed to transform '{}' to header!", val.0))
+        let mut value = HeaderValue::from_str(&val.0)
+            .unwrap_or_else(|_| panic!("Failed to transform the token to a header!"));
+        // Sensitive values are left out of the debug output of the requests
+        value.set_sensitive(true);
+        value
     }
 }
 
@@ -48,6 +93,10 @@ impl From<&str> for Token {
     }
 }
 
+fn u128_to_str_hex<S: Serializer>(value: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{value:x}"))
+}
+
 fn str_hex_to_u128<'de, D>(deserializer: D) -> Result<u128, D::Error>
 where
     D: Deserializer<'de>,
@@ -56,15 +105,22 @@ where
     u128::from_str_radix(&s, 16).map_err(serde::de::Error::custom)
 }
 
-#[derive(Deserialize, Clone, Debug)]
-pub(crate) struct User {
-    #[serde(deserialize_with = "str_hex_to_u128")]
+/// Serialized in the format of the API, so that the saved user can be replayed
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct User {
+    #[serde(
+        deserialize_with = "str_hex_to_u128",
+        serialize_with = "u128_to_str_hex"
+    )]
     id: u128,
-    #[serde(rename(deserialize = "activeWorkspace"))]
-    #[serde(deserialize_with = "str_hex_to_u128")]
+    #[serde(rename = "activeWorkspace")]
+    #[serde(
+        deserialize_with = "str_hex_to_u128",
+        serialize_with = "u128_to_str_hex"
+    )]
     workspace: u128,
-    name: String,
-    pub(crate) email: String,
+    pub name: String,
+    pub email: String,
 }
 
 impl User {
@@ -77,14 +133,40 @@ impl User {
     }
 }
 
-async fn get_user(client: Client, token: &Token) -> Result<User, Error> {
-    let user_url = API_URL.join("v1/user")?;
-    let response = client
-        .get(user_url)
-        .header("x-api-key", token)
-        .send()
-        .await?;
-    response.json::<User>().await.map_err(Error::from)
+#[derive(Deserialize, Clone, Debug)]
+pub struct Rate {
+    /// Amount in cents
+    pub amount: i64,
+    pub currency: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Membership {
+    membership_type: String,
+    hourly_rate: Option<Rate>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct WorkspaceMember {
+    #[serde(flatten)]
+    pub user: User,
+    #[serde(default)]
+    memberships: Vec<Membership>,
+}
+
+impl WorkspaceMember {
+    /// Hourly rate of the member in the workspace, if set
+    pub fn hourly_rate(&self) -> Option<&Rate> {
+        self.memberships
+            .iter()
+            .find(|m| m.membership_type == "WORKSPACE")
+            .and_then(|m| m.hourly_rate.as_ref())
+    }
+}
+
+async fn get_user(api: &dyn ClockifyApi) -> Result<User, Error> {
+    api.send(ApiRequest::get("v1/user")).await?.json::<User>()
 }
 
 fn get_string_field<E: serde::de::Error>(obj: &Value, field: &'static str) -> Result<String, E> {
@@ -106,9 +188,10 @@ fn get_datetime_field<E: serde::de::Error>(
 }
 
 #[derive(Debug, Serialize)]
-pub(crate) struct TimeEntry {
+pub struct TimeEntry {
     pub description: String,
     pub project_name: String,
+    pub tags: Vec<String>,
     pub user_id: String,
     pub start: DateTime<Utc>,
     pub end: DateTime<Utc>,
@@ -120,10 +203,22 @@ impl<'de> Deserialize<'de> for TimeEntry {
 
         let description = get_string_field(&v, "description")?;
 
-        let project = v
+        // Entries without a project have null project
+        let project_name = v
             .get("project")
-            .ok_or_else(|| serde::de::Error::missing_field("project"))?;
-        let project_name = get_string_field(project, "name")?;
+            .and_then(|p| get_string_field::<D::Error>(p, "name").ok())
+            .unwrap_or_default();
+
+        // Tags are objects with a name, entries without tags may have null
+        let tags = v
+            .get("tags")
+            .and_then(Value::as_array)
+            .map(|tags| {
+                tags.iter()
+                    .filter_map(|t| get_string_field::<D::Error>(t, "name").ok())
+                    .collect()
+            })
+            .unwrap_or_default();
 
         let user = v
             .get("user")
@@ -139,6 +234,7 @@ impl<'de> Deserialize<'de> for TimeEntry {
         Ok(TimeEntry {
             description,
             project_name,
+            tags,
             user_id,
             start,
             end,
@@ -146,19 +242,38 @@ impl<'de> Deserialize<'de> for TimeEntry {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
-pub(crate) enum TimeOffType {
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum TimeOffType {
     DayOff,
     SickLeave,
     Vacation,
     ParentalLeave,
+    UnpaidLeave,
+    /// Custom day type of the extra settings, mapped with its policies
+    #[serde(skip)]
+    Custom(String),
+}
+
+impl TimeOffType {
+    /// Type of the default English policy names, used when the policy is not mapped in the settings
+    pub fn from_policy_name(policy_name: &str) -> Option<Self> {
+        match policy_name {
+            "Day off" => Some(Self::DayOff),
+            "Sick leave" => Some(Self::SickLeave),
+            "Vacation" => Some(Self::Vacation),
+            "Parental leave" => Some(Self::ParentalLeave),
+            "Unpaid leave" => Some(Self::UnpaidLeave),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize)]
-pub(crate) struct TimeOffItem {
+pub struct TimeOffItem {
     pub note: String,
     pub user_id: String,
-    pub type_: TimeOffType,
+    pub policy_id: String,
+    pub policy_name: String,
     pub start: DateTime<Utc>,
     pub end: DateTime<Utc>,
     pub status: String,
@@ -168,19 +283,17 @@ impl<'de> Deserialize<'de> for TimeOffItem {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
         let v: Value = Deserialize::deserialize(deserializer)?;
 
-        // Assert that time unit is days
+        // Only full-day time off is counted, e.g. hourly time off would be miscounted
         let time_unit = get_string_field(&v, "timeUnit")?;
-        assert!(&time_unit == "DAYS", "Time unit wasn't 'DAYS'! {time_unit}");
+        if time_unit != "DAYS" {
+            return Err(serde::de::Error::custom(format!(
+                "Time unit wasn't 'DAYS'! {time_unit}"
+            )));
+        }
 
         let user_id = get_string_field(&v, "userId")?;
+        let policy_id = get_string_field::<D::Error>(&v, "policyId").unwrap_or_default();
         let policy_name = get_string_field(&v, "policyName")?;
-        let type_ = match policy_name.as_str() {
-            "Day off" => TimeOffType::DayOff,
-            "Sick leave" => TimeOffType::SickLeave,
-            "Vacation" => TimeOffType::Vacation,
-            "Parental leave" => TimeOffType::ParentalLeave,
-            x => return Err(serde::de::Error::custom(format!("unknown policyName: {x}"))),
-        };
 
         let status_object = v
             .get("status")
@@ -200,8 +313,9 @@ impl<'de> Deserialize<'de> for TimeOffItem {
 
         Ok(TimeOffItem {
             note,
-            type_,
             user_id,
+            policy_id,
+            policy_name,
             start,
             end,
             status,
@@ -209,67 +323,762 @@ impl<'de> Deserialize<'de> for TimeOffItem {
     }
 }
 
+/// Where `get_working_days` gets the logged time from
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EntrySource {
+    /// Every time entry of the timesheet, a request per 41 days and page
+    #[default]
+    Detailed,
+    /// Totals per day and project of the summary report, a request per year. The entries
+    /// have no descriptions, tags or times of the day.
+    Summary,
+}
+
+/// Days per request of the summary report
+const SUMMARY_WINDOW_DAYS: i64 = 365;
+
+/// Summary report grouped by date and project
+#[derive(Deserialize, Debug)]
+struct SummaryReport {
+    #[serde(rename = "groupOne", default)]
+    group_one: Vec<SummaryGroup>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SummaryGroup {
+    #[serde(rename = "_id", default)]
+    id: Option<String>,
+    #[serde(default)]
+    name: String,
+    /// Seconds
+    duration: i64,
+    #[serde(default)]
+    children: Vec<SummaryGroup>,
+}
+
+impl SummaryGroup {
+    fn date(&self) -> Result<NaiveDate, Error> {
+        let id = self.id.as_deref().unwrap_or(&self.name);
+        NaiveDate::parse_from_str(id, "%Y-%m-%d")
+            .or_else(|_| NaiveDate::parse_from_str(&self.name, "%Y-%m-%d"))
+            .map_err(|_| Error::msg(format!("Invalid date '{id}' in the summary report")))
+    }
+
+    /// Project name of a child group, entries without a project have an empty ID
+    fn project_name(&self) -> String {
+        match self.id.as_deref() {
+            Some("") | None => String::new(),
+            Some(_) => self.name.clone(),
+        }
+    }
+}
+
+/// Options of the timesheet query. The API limits one request to 999 hours, so the range is
+/// fetched in windows, a chunk of windows at a time.
+#[derive(Clone, Debug)]
+pub struct TimesheetQuery {
+    start: NaiveDate,
+    end: Option<NaiveDate>,
+    window_days: i64,
+    concurrency: usize,
+    page_size: usize,
+}
+
+impl TimesheetQuery {
+    /// Entries from the start of the date until the end of today
+    pub fn since(start: NaiveDate) -> Self {
+        Self {
+            start,
+            end: None,
+            window_days: 41,
+            concurrency: 8,
+            page_size: 200,
+        }
+    }
+
+    /// Last date of the range, inclusive
+    pub fn until(mut self, end: NaiveDate) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    /// Days per request, at most 41
+    pub fn window_days(mut self, days: i64) -> Self {
+        self.window_days = days.clamp(1, 41);
+        self
+    }
+
+    /// Entries per page, the pages of a window are fetched until exhaustion
+    pub fn page_size(mut self, size: usize) -> Self {
+        self.page_size = size.max(1);
+        self
+    }
+
+    /// Windows fetched at the same time
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+}
+
+/// Options of the time off requests query
+#[derive(Clone, Debug)]
+pub struct TimeOffQuery {
+    statuses: Vec<String>,
+    page_size: usize,
+}
+
+impl TimeOffQuery {
+    /// Approved requests of the user
+    pub fn new() -> Self {
+        Self {
+            statuses: vec!["APPROVED".into()],
+            page_size: 500,
+        }
+    }
+
+    /// Only requests with the statuses, e.g. APPROVED, PENDING or REJECTED
+    pub fn statuses(mut self, statuses: &[&str]) -> Self {
+        self.statuses = statuses.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+}
+
+impl Default for TimeOffQuery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Time off policy of the workspace
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Policy {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub archived: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatePeriod {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+}
+
+/// Project of the workspace
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    /// Empty or missing when the project has no client
+    #[serde(default, rename = "clientName")]
+    pub client_name: Option<String>,
+}
+
+/// Time entry stopped with the timer
+#[derive(Clone, Debug)]
+pub struct StoppedTimer {
+    pub description: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl<'de> Deserialize<'de> for StoppedTimer {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let v: Value = Deserialize::deserialize(deserializer)?;
+        let time_interval = v
+            .get("timeInterval")
+            .ok_or_else(|| serde::de::Error::missing_field("timeInterval"))?;
+        Ok(StoppedTimer {
+            description: get_string_field::<D::Error>(&v, "description").unwrap_or_default(),
+            start: get_datetime_field(time_interval, "start")?,
+            end: get_datetime_field(time_interval, "end")?,
+        })
+    }
+}
+
+/// Holiday configured in the workspace
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceHoliday {
+    pub id: String,
+    pub name: String,
+    pub date_period: DatePeriod,
+}
+
+/// Client for the subset of the Clockify API used by this tool. Requests are made as the user
+/// of the token, in the user's active workspace.
 #[derive(Clone, Debug)]
-pub(crate) struct ClockifyClient {
-    base_url: &'static Url,
-    pub(crate) user: User,
-    client: Client,
+pub struct ClockifyClient {
+    api: Arc<dyn ClockifyApi>,
+    pub user: User,
+    strict: bool,
+    save_responses: bool,
+    concurrency: usize,
+    progress: bool,
+    timeout: Option<Duration>,
+    entry_source: EntrySource,
 }
 
 impl ClockifyClient {
-    pub(crate) fn new(token: &Token) -> Result<Self, Error> {
-        let token_ = &token.clone();
-
-        let mut headers = HeaderMap::new();
-        headers.insert("x-api-key", token_.into());
-        let client = Client::builder().default_headers(headers).build()?;
-        let client_ = client.clone();
-
-        let user = tokio::task::block_in_place(move || {
-            tokio::runtime::Handle::current().block_on(async move {
-                let mut attempts = 0u8;
-                loop {
-                    match get_user(client_.clone(), token_).await {
-                        Ok(user) => return Ok(user),
-                        Err(e) if attempts < 3 => {
-                            log::error!("Failed to get user from clockify API: {e}");
-                            attempts += 1;
-                            sleep(Duration::from_secs(2)).await;
-                        }
-                        Err(e) => {
-                            log::error!("Failed to get user from clockify API three times: {e}");
-                            return Err(e);
-                        }
-                    }
+    /// Client of the token saved in the OS keyring
+    pub async fn from_keyring() -> Result<Self, Error> {
+        let token = Token::from_keyring()?.ok_or(Error::msg(
+            "No token in the keyring, save one with `auth login`!",
+        ))?;
+        Self::new(&token).await
+    }
+
+    /// Fetch the user of the token and build the client, retrying the user request
+    pub async fn new(token: &Token) -> Result<Self, Error> {
+        Self::with_api(Arc::new(HttpApi::new(token)?)).await
+    }
+
+    /// Client answering with the responses recorded with `save_responses` in the directory
+    /// instead of the API
+    pub async fn replay(dir: &Path) -> Result<Self, Error> {
+        Self::with_api(Arc::new(RecordedApi::new(dir)?)).await
+    }
+
+    /// Fetch the user through the API and build the client, retrying the user request
+    pub async fn with_api(api: Arc<dyn ClockifyApi>) -> Result<Self, Error> {
+        let mut attempts = 0u8;
+        let user = loop {
+            match get_user(api.as_ref()).await {
+                Ok(user) => break user,
+                Err(e) if attempts < 3 => {
+                    log::error!("Failed to get user from clockify API: {e}");
+                    attempts += 1;
+                    sleep(Duration::from_secs(2)).await;
                 }
-            })
-        })?;
+                Err(e) => {
+                    log::error!("Failed to get user from clockify API three times: {e}");
+                    return Err(e);
+                }
+            }
+        };
 
         Ok(ClockifyClient {
+            api,
             user,
-            base_url: &API_URL,
-            client,
+            strict: false,
+            save_responses: false,
+            concurrency: 8,
+            progress: true,
+            timeout: None,
+            entry_source: EntrySource::Detailed,
         })
     }
 
-    pub(crate) async fn get_work_items_since(
+    /// Client for another user of the same workspace. Requires an admin token.
+    pub fn for_user(&self, user: &User) -> Self {
+        let mut user = user.clone();
+        user.workspace = self.user.workspace;
+        ClockifyClient {
+            user,
+            ..self.clone()
+        }
+    }
+
+    /// Abort on records that can't be parsed instead of skipping them with a warning
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Save the raw responses to disk, they can be read back with `utils::dump::load`
+    pub fn save_responses(mut self, save_responses: bool) -> Self {
+        self.save_responses = save_responses;
+        self
+    }
+
+    /// Show the progress bar of `get_work_items_since`
+    pub fn progress(mut self, progress: bool) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Timesheet windows fetched at the same time by `get_work_items_since`
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Fail fetching the time entries or the time off after this long, however many requests
+    /// it takes
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Fetch the logged time of `get_working_days` from the timesheet or the summary report
+    pub fn entry_source(mut self, source: EntrySource) -> Self {
+        self.entry_source = source;
+        self
+    }
+
+    pub fn get_entry_source(&self) -> EntrySource {
+        self.entry_source
+    }
+
+    /// The fetch within the timeout
+    async fn with_timeout<T>(
+        &self,
+        what: &str,
+        fetch: impl Future<Output = Result<T, Error>>,
+    ) -> Result<T, Error> {
+        match self.timeout {
+            Some(limit) => tokio::time::timeout(limit, fetch).await.map_err(|_| {
+                Error::msg(format!(
+                    "Fetching {what} took longer than {} s!",
+                    limit.as_secs()
+                ))
+            })?,
+            None => fetch.await,
+        }
+    }
+
+    /// All active members of the workspace with their memberships. Requires an admin token.
+    pub async fn get_workspace_members(&self) -> Result<Vec<WorkspaceMember>, Error> {
+        let users_path = format!("v1/workspaces/{}/users", self.user.workspace_str());
+
+        let page_size = 200;
+        let mut members = Vec::new();
+        for page in 1.. {
+            let request = ApiRequest::get(&users_path).query(&[
+                ("memberships", "WORKSPACE".to_string()),
+                ("status", "ACTIVE".to_string()),
+                ("page", page.to_string()),
+                ("page-size", page_size.to_string()),
+            ]);
+            let response = self.api.send(request).await?;
+            if !response.is_success() {
+                return Err(Error::msg(format!(
+                    "Failed to fetch workspace members ({}), is the token an admin token?",
+                    response.status
+                )));
+            }
+            let page_members = response.json::<Vec<WorkspaceMember>>()?;
+            let page_len = page_members.len();
+            members.extend(page_members);
+            if page_len < page_size {
+                break;
+            }
+        }
+        Ok(members)
+    }
+
+    /// Authenticated GET request against the API. `{workspaceId}` and `{userId}` in the path
+    /// are replaced with the ids of the user. Returns the status and the body, as JSON if it parses.
+    pub async fn get_raw(
+        &self,
+        path: &str,
+        query: &[(String, String)],
+    ) -> Result<(reqwest::StatusCode, Value), Error> {
+        let path = path
+            .trim_start_matches('/')
+            .replace("{workspaceId}", &self.user.workspace_str())
+            .replace("{userId}", &self.user.id_str());
+        let response = self.api.send(ApiRequest::get(&path).query(query)).await?;
+        let body = serde_json::from_str(&response.body).unwrap_or(Value::String(response.body));
+        Ok((response.status, body))
+    }
+
+    /// Create a resource by posting the payload to the collection path and record it in the
+    /// audit log. Returns the response body.
+    pub async fn create_audited(
+        &self,
+        collection_path: &str,
+        payload: &Value,
+    ) -> Result<Value, Error> {
+        let response = self
+            .api
+            .send(ApiRequest::post(collection_path, payload.clone()))
+            .await?;
+        // Errors may come without a JSON body, e.g. from a proxy
+        if !response.is_success() {
+            return Err(Error::msg(format!(
+                "Failed to create: {} {}",
+                response.status, response.body
+            )));
+        }
+        let body = response.json::<Value>()?;
+
+        let id = get_string_field::<serde_json::Error>(&body, "id")?;
+        let entry = AuditEntry::new(
+            AuditAction::Create,
+            id.clone(),
+            format!("{}/{id}", collection_path.trim_end_matches('/')),
+            payload.clone(),
+        );
+        audit::append(&entry)?;
+        Ok(body)
+    }
+
+    /// Request full-day time off of the policy for the period, recorded in the audit log.
+    /// Returns the ID of the request.
+    pub async fn request_time_off(
+        &self,
+        policy_id: &str,
+        note: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<String, Error> {
+        let path = format!(
+            "v1/workspaces/{}/time-off/policies/{policy_id}/requests",
+            self.user.workspace_str()
+        );
+        let payload = serde_json::json!({
+            "note": note,
+            "timeOffPeriod": {
+                "isHalfDay": false,
+                "period": { "start": start, "end": end }
+            }
+        });
+        let body = self.create_audited(&path, &payload).await?;
+        get_string_field::<serde_json::Error>(&body, "id").map_err(Error::from)
+    }
+
+    /// Project of the workspace by its exact name
+    pub async fn find_project(&self, name: &str) -> Result<Project, Error> {
+        let path = format!("v1/workspaces/{}/projects", self.user.workspace_str());
+        let query = [
+            ("name", name.to_owned()),
+            ("strict-name-search", true.to_string()),
+        ];
+        let projects: Vec<Project> = self.get_json(&path, &query).await?;
+        projects
+            .into_iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| Error::msg(format!("Project {name} not found!")))
+    }
+
+    /// Client names of the projects of the workspace by the project name, projects without a
+    /// client are left out
+    pub async fn get_project_clients(&self) -> Result<HashMap<String, String>, Error> {
+        let path = format!("v1/workspaces/{}/projects", self.user.workspace_str());
+        let page_size = 200;
+        let mut clients = HashMap::new();
+        for page in 1.. {
+            let query = [
+                ("page", page.to_string()),
+                ("page-size", page_size.to_string()),
+            ];
+            let projects: Vec<Project> = self.get_json(&path, &query).await?;
+            let page_len = projects.len();
+            clients.extend(projects.into_iter().filter_map(|p| {
+                p.client_name
+                    .filter(|client| !client.is_empty())
+                    .map(|client| (p.name, client))
+            }));
+            if page_len < page_size {
+                break;
+            }
+        }
+        Ok(clients)
+    }
+
+    /// Create a time entry, recorded in the audit log. Without the end, the entry is a running
+    /// timer. Returns the ID of the time entry.
+    pub async fn create_time_entry(
+        &self,
+        description: &str,
+        project_id: Option<&str>,
+        start: DateTime<Utc>,
+        end: Option<DateTime<Utc>>,
+    ) -> Result<String, Error> {
+        let path = format!("v1/workspaces/{}/time-entries", self.user.workspace_str());
+        let payload = serde_json::json!({
+            "start": start,
+            "end": end,
+            "description": description,
+            "projectId": project_id,
+        });
+        let body = self.create_audited(&path, &payload).await?;
+        get_string_field::<serde_json::Error>(&body, "id").map_err(Error::from)
+    }
+
+    /// Start a timer now, recorded in the audit log. Returns the ID of the time entry.
+    pub async fn start_timer(
+        &self,
+        description: &str,
+        project_id: Option<&str>,
+    ) -> Result<String, Error> {
+        self.create_time_entry(description, project_id, Utc::now(), None)
+            .await
+    }
+
+    /// Stop the running timer of the user now. Returns None if no timer was running.
+    pub async fn stop_timer(&self) -> Result<Option<StoppedTimer>, Error> {
+        let path = format!(
+            "v1/workspaces/{}/user/{}/time-entries",
+            self.user.workspace_str(),
+            self.user.id_str()
+        );
+        let payload = serde_json::json!({ "end": Utc::now() });
+        let response = self.api.send(ApiRequest::patch(&path, payload)).await?;
+        if response.status == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.is_success() {
+            return Err(Error::msg(format!(
+                "Failed to stop the timer: {} {}",
+                response.status, response.body
+            )));
+        }
+        Ok(Some(response.json::<StoppedTimer>()?))
+    }
+
+    /// Delete a resource created earlier and record the undo in the audit log
+    pub async fn undo(&self, entry: &AuditEntry) -> Result<(), Error> {
+        let response = self
+            .api
+            .send(ApiRequest::delete(&entry.resource_path))
+            .await?;
+        if !response.is_success() && response.status != reqwest::StatusCode::NOT_FOUND {
+            return Err(Error::msg(format!(
+                "Failed to delete: {} {}",
+                response.status, response.body
+            )));
+        }
+
+        let undo = AuditEntry::new(
+            AuditAction::Undo,
+            entry.response_id.clone(),
+            entry.resource_path.clone(),
+            Value::Null,
+        );
+        audit::append(&undo)
+    }
+
+    /// Send the request, and again after a growing cooldown while Clockify is rate limiting.
+    /// The last response is returned as is.
+    async fn send_with_cooldown(&self, request: ApiRequest) -> Result<ApiResponse, Error> {
+        let mut response = self.api.send(request.clone()).await?;
+        for millis in [600, 750, 1250, 2000] {
+            if response.status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+                break;
+            }
+            log::warn!(
+                "Clockify is rate limiting {}, trying again in {millis} ms",
+                request.path
+            );
+            sleep(Duration::from_millis(millis)).await;
+            response = self.api.send(request.clone()).await?;
+        }
+        Ok(response)
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<T, Error> {
+        let response = self.api.send(ApiRequest::get(path).query(query)).await?;
+        if !response.is_success() {
+            return Err(Error::msg(format!(
+                "Request to {path} failed: {} {}",
+                response.status, response.body
+            )));
+        }
+        response.json::<T>()
+    }
+
+    /// Time off policies of the workspace, including the archived ones
+    pub async fn get_policies(&self) -> Result<Vec<Policy>, Error> {
+        let path = format!(
+            "v1/workspaces/{}/time-off/policies",
+            self.user.workspace_str()
+        );
+        self.get_json(&path, &[("page-size", 200.to_string())])
+            .await
+    }
+
+    /// Holidays configured in the workspace
+    pub async fn get_workspace_holidays(&self) -> Result<Vec<WorkspaceHoliday>, Error> {
+        let path = format!("v1/workspaces/{}/holidays", self.user.workspace_str());
+        self.get_json(&path, &[]).await
+    }
+
+    /// Date of the earliest time entry of the user, from the detailed report sorted ascending.
+    /// None if the user has no time entries.
+    pub async fn get_first_entry_date(&self) -> Result<Option<NaiveDate>, Error> {
+        let path = format!(
+            "v1/workspaces/{}/reports/detailed",
+            self.user.workspace_str()
+        );
+        // Clockify has no entries before it was launched
+        let start = Utc.with_ymd_and_hms(2015, 1, 1, 0, 0, 0).unwrap();
+        let body = serde_json::json!({
+            "dateRangeStart": start.to_rfc3339_opts(SecondsFormat::Millis, true),
+            "dateRangeEnd": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            "timeZone": "UTC",
+            "exportType": "JSON",
+            "amountShown": "HIDE_AMOUNT",
+            "sortOrder": "ASCENDING",
+            "users": {
+                "ids": [self.user.id_str()],
+                "contains": "CONTAINS",
+                "status": "ALL"
+            },
+            "detailedFilter": { "page": 1, "pageSize": 1, "sortColumn": "DATE" }
+        });
+        let response = self
+            .api
+            .send(ApiRequest::post(&path, body).reports())
+            .await?;
+        if !response.is_success() {
+            return Err(Error::msg(format!(
+                "Detailed report request failed: {} {}",
+                response.status, response.body
+            )));
+        }
+        let report = response.json::<Value>()?;
+        let Some(entry) = report
+            .get("timeentries")
+            .and_then(Value::as_array)
+            .and_then(|entries| entries.first())
+        else {
+            return Ok(None);
+        };
+        let start = entry
+            .get("timeInterval")
+            .ok_or(Error::msg("The first time entry has no timeInterval"))
+            .and_then(|interval| {
+                get_datetime_field::<serde_json::Error>(interval, "start").map_err(Error::from)
+            })?;
+        Ok(Some(start.date_naive()))
+    }
+
+    /// Time entries of the user from the start of the date until the end of today
+    pub async fn get_work_items_since(&self, date: &NaiveDate) -> Result<Vec<TimeEntry>, Error> {
+        self.get_time_entries(&TimesheetQuery::since(*date).concurrency(self.concurrency))
+            .await
+    }
+
+    /// Logged time of the user per day and project from the start of the date until the end of
+    /// the last date, one entry each starting at midnight UTC. The projects of a day follow
+    /// each other so that they don't overlap.
+    pub async fn get_daily_totals(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<TimeEntry>, Error> {
+        self.with_timeout("daily totals", self.fetch_daily_totals(start, end))
+            .await
+    }
+
+    async fn fetch_daily_totals(
         &self,
-        date: &NaiveDate,
+        start: NaiveDate,
+        end: NaiveDate,
     ) -> Result<Vec<TimeEntry>, Error> {
+        let path = format!(
+            "v1/workspaces/{}/reports/summary",
+            self.user.workspace_str()
+        );
+        let mut windows = Vec::new();
+        let mut current_start = start;
+        while current_start <= end {
+            let current_end = std::cmp::min(
+                current_start + TimeDelta::days(SUMMARY_WINDOW_DAYS - 1),
+                end,
+            );
+            windows.push((current_start, current_end));
+            current_start = current_end + TimeDelta::days(1);
+        }
+
+        let path = &path;
+        let mut reports = stream::iter(windows)
+            .map(|(start, end)| async move { self.get_summary_window(path, start, end).await })
+            .buffer_unordered(self.concurrency);
+        let mut entries = Vec::new();
+        while let Some(groups) = reports.next().await {
+            for day in groups? {
+                let date = day.date()?;
+                let mut start = Utc.from_utc_datetime(&date.and_time(NaiveTime::MIN));
+                for project in day.children.iter().filter(|p| p.duration > 0) {
+                    let end = start + TimeDelta::seconds(project.duration);
+                    entries.push(TimeEntry {
+                        description: String::new(),
+                        project_name: project.project_name(),
+                        tags: Vec::new(),
+                        user_id: self.user.id_str(),
+                        start,
+                        end,
+                    });
+                    start = end;
+                }
+            }
+        }
+        entries.sort_by_key(|entry| entry.start);
+        Ok(entries)
+    }
+
+    /// Days of one summary report request, both dates inclusive
+    async fn get_summary_window(
+        &self,
+        path: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<SummaryGroup>, Error> {
+        let start = Utc.from_utc_datetime(&start.and_time(NaiveTime::MIN));
+        let end = Utc.from_utc_datetime(
+            &end.and_hms_milli_opt(23, 59, 59, 999)
+                .ok_or(Error::msg("Cannot create NaiveTime"))?,
+        );
+        let body = serde_json::json!({
+            "dateRangeStart": start.to_rfc3339_opts(SecondsFormat::Millis, true),
+            "dateRangeEnd": end.to_rfc3339_opts(SecondsFormat::Millis, true),
+            "timeZone": "UTC",
+            "exportType": "JSON",
+            "amountShown": "HIDE_AMOUNT",
+            "users": {
+                "ids": [self.user.id_str()],
+                "contains": "CONTAINS",
+                "status": "ALL"
+            },
+            "summaryFilter": { "groups": ["DATE", "PROJECT"] }
+        });
+        let response = self
+            .api
+            .send(ApiRequest::post(path, body).reports())
+            .await?;
+        if !response.is_success() {
+            return Err(Error::msg(format!(
+                "Summary report request failed: {} {}",
+                response.status, response.body
+            )));
+        }
+        Ok(response.json::<SummaryReport>()?.group_one)
+    }
+
+    /// Time entries of the user in the range of the query. Fails if any page can't be fetched.
+    pub async fn get_time_entries(&self, query: &TimesheetQuery) -> Result<Vec<TimeEntry>, Error> {
+        self.with_timeout("time entries", self.fetch_time_entries(query))
+            .await
+    }
+
+    async fn fetch_time_entries(&self, query: &TimesheetQuery) -> Result<Vec<TimeEntry>, Error> {
         let time_entries_path = format!(
             "workspaces/{}/timeEntries/users/{}/timesheet",
             self.user.workspace_str(),
             self.user.id_str()
         );
-        let url = self.base_url.join(&time_entries_path)?;
 
         // Default is end of today
-        let end = Utc::now().date_naive().and_time(
+        let end = query.end.unwrap_or(Utc::now().date_naive()).and_time(
             NaiveTime::from_hms_opt(23, 59, 59).ok_or(Error::msg("Cannot create NaiveTime"))?,
         );
         let end = Utc.from_utc_datetime(&end);
 
-        let start = date.and_time(NaiveTime::MIN);
+        let start = query.start.and_time(NaiveTime::MIN);
         let start = Utc.from_utc_datetime(&start);
 
         // The clockify API limits queries to 999 hours (approx. 41.625 days)
@@ -279,7 +1088,7 @@ impl ClockifyClient {
         while current_start < end {
             let current_end = std::cmp::min(
                 current_start
-                    .checked_add_signed(TimeDelta::days(41))
+                    .checked_add_signed(TimeDelta::days(query.window_days))
                     .unwrap_or(end),
                 end,
             );
@@ -288,158 +1097,357 @@ impl ClockifyClient {
             current_start = current_end;
         }
 
-        let mut request_futures = queries
-            .into_iter()
-            .map(|(start, end)| {
-                self.client
-                    .get(url.clone())
-                    .query(&[
-                        (
-                            "start",
-                            start.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
-                        ),
-                        (
-                            "end",
-                            end.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
-                        ),
-                        ("in-progress", false.to_string()),
-                        ("page", 0.to_string()),
-                        ("page-size", 0.to_string()),
-                    ])
-                    .send()
+        let bar = if self.progress {
+            ProgressBar::new(queries.len() as u64).with_style(
+                ProgressStyle::with_template(
+                    "Fetching time entries {bar:30} {pos}/{len} date ranges, {msg}",
+                )
+                .map_err(Error::msg)?,
+            )
+        } else {
+            ProgressBar::hidden()
+        };
+        let progress = &FetchProgress {
+            bar,
+            entries: AtomicUsize::new(0),
+        };
+        let path = &time_entries_path;
+        let mut windows = stream::iter(queries)
+            .map(|(start, end)| async move {
+                self.get_timesheet_window(path, &start, &end, query.page_size, progress)
+                    .await
             })
-            .collect_vec();
-
-        let mut responses = Vec::with_capacity(request_futures.len());
-        let request_chunks = request_futures.chunks_mut(18);
-
-        '_chunk: for chunk in request_chunks {
-            'result: for result in join_all(chunk).await {
-                match result {
-                    Err(e) => return Err(e.into()),
-                    Ok(res) => {
-                        if !res.status().is_success() {
-                            println!("Unsuccessful response!");
-                            continue 'result;
-                        }
-
-                        let headers = res.headers();
-                        let cont_len = headers
-                            .get("content-length")
-                            .map(|v| v.to_str().map_err(Error::msg));
-                        let transfer_encoding = headers
-                            .get("transfer-encoding")
-                            .map(|v| v.to_str().map_err(Error::msg));
-
-                        if let Some(Ok(encoding)) = transfer_encoding {
-                            if encoding == "chunked" {
-                                responses.push(res);
-                                continue 'result;
-                            }
-                        }
-                        if let Some(Ok(cont_len)) = cont_len {
-                            if !["0", "2"].contains(&cont_len) {
-                                responses.push(res);
-                            }
-                        }
-                    }
-                };
-            }
-            if responses.capacity() > 18 {
-                thread::sleep(time::Duration::from_millis(1000));
-            }
+            .buffer_unordered(query.concurrency);
+        let mut records = Records::new("time entry", self.strict);
+        let mut raw = Vec::new();
+        while let Some(result) = windows.next().await {
+            let window = result?;
+            records.append(window.records);
+            raw.extend(window.raw);
+            progress.bar.inc(1);
         }
-
-        let json_futures = responses
-            .into_iter()
-            .map(|response| response.json::<Vec<TimeEntry>>());
-
-        let mut jsons = Vec::with_capacity(json_futures.len());
-        for result in join_all(json_futures).await {
-            if let Err(e) = result {
-                return Err(e.into());
+        progress.bar.finish_and_clear();
+        // Raw responses, so that the dumps can be parsed again with --from-dump or --replay
+        if self.save_responses {
+            let time = Utc::now().format("%Y%m%d%H%M%S");
+            let path = utils::dirs::cache_path(&format!("work_items_{time}.json"));
+            if let Err(e) = json_to_disk(path, &raw).await {
+                println!("Failed to save work items to disk! {e}")
+            };
+            // The user has the email of the extra settings
+            let path = utils::dirs::cache_path(&format!("user_{time}.json"));
+            if let Err(e) = json_to_disk(path, &[&self.user]).await {
+                println!("Failed to save the user to disk! {e}")
             };
-            jsons.push(result.unwrap())
         }
 
-        if get_settings().await.debug {
-            let path = format!("work_items_{}.json", Utc::now().format("%Y%m%d%H%M%S"));
-            if let Err(e) = json_to_disk(path, &jsons).await {
-                println!("Failed to save work items to disk! {e}")
+        let mut jsons = records.finish();
+        // The windows finish in any order
+        jsons.sort_by_key(|entry| entry.start);
+
+        Ok(jsons)
+    }
+
+    /// All pages of the timesheet between the times, parsed page by page. The raw records are
+    /// kept only for saving the responses.
+    async fn get_timesheet_window(
+        &self,
+        path: &str,
+        start: &DateTime<Utc>,
+        end: &DateTime<Utc>,
+        page_size: usize,
+        progress: &FetchProgress,
+    ) -> Result<Window, Error> {
+        let mut window = Window {
+            records: Records::new("time entry", self.strict),
+            raw: Vec::new(),
+        };
+        for page in 1.. {
+            let request = ApiRequest::get(path).query(&[
+                (
+                    "start",
+                    start.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                ),
+                (
+                    "end",
+                    end.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                ),
+                ("in-progress", false.to_string()),
+                ("page", page.to_string()),
+                ("page-size", page_size.to_string()),
+            ]);
+            let response = self.send_with_cooldown(request).await?;
+            if !response.is_success() {
+                // A missing page would silently lower the balance
+                return Err(Error::msg(format!(
+                    "Failed to fetch the time entries from {start} to {end}: {} {}",
+                    response.status, response.body
+                )));
+            }
+            let mut deserializer = serde_json::Deserializer::from_str(&response.body);
+            let page = PageSeed {
+                window: &mut window,
+                save_raw: self.save_responses,
+                progress,
             };
+            let count = page.deserialize(&mut deserializer)?;
+            deserializer.end()?;
+            if count < page_size {
+                break;
+            }
         }
+        Ok(window)
+    }
 
-        Ok(jsons.into_iter().flatten().collect())
+    /// Time off requests of the user matching the query
+    pub async fn get_time_off_items(
+        &self,
+        query: &TimeOffQuery,
+    ) -> Result<Vec<TimeOffItem>, Error> {
+        self.with_timeout("time off requests", self.fetch_time_off_items(query))
+            .await
     }
 
-    pub(crate) async fn get_time_off_items(&self) -> Result<Vec<TimeOffItem>, Error> {
+    async fn fetch_time_off_items(&self, query: &TimeOffQuery) -> Result<Vec<TimeOffItem>, Error> {
         let time_entries_path =
             format!("workspaces/{}/time-off/requests", self.user.workspace_str());
-        let url = self.base_url.join(&time_entries_path)?;
 
-        let body = &serde_json::json!({
-            "page": 1,
-            "pageSize": 500,
-            "status": ["APPROVED"],
-            "users": {
-                "contains": "CONTAINS",
-                "ids": [self.user.id_str()],
-                "status": "ALL"
-            },
-            "userGroups": {}
-        });
+        let mut records = Records::<TimeOffItem>::new("time off request", self.strict);
+        let mut raw = Vec::new();
+        let mut fetched = 0;
+        for page in 1.. {
+            let body = serde_json::json!({
+                "page": page,
+                "pageSize": query.page_size,
+                "status": query.statuses,
+                "users": {
+                    "contains": "CONTAINS",
+                    "ids": [self.user.id_str()],
+                    "status": "ALL"
+                },
+                "userGroups": {}
+            });
 
-        let mut response = self.client.post(url.clone()).json(body).send().await?;
-        if response.status().as_u16() == 429 {
-            'cooldown: for x in [600, 750, 1250, 2000] {
-                // Clockify is rate limiting.. Cooling down a bit and trying again... ({} ms)
-                std::thread::sleep(std::time::Duration::from_millis(x));
-                response = self.client.post(url.clone()).json(body).send().await?;
-                if response.status().is_success() {
-                    break 'cooldown;
+            let request = ApiRequest::post(&time_entries_path, body);
+            let response = self.send_with_cooldown(request).await?;
+            if !response.is_success() {
+                // A missing page would silently raise the expected time
+                return Err(Error::msg(format!(
+                    "Failed to fetch the time off requests: {} {}",
+                    response.status, response.body
+                )));
+            }
+            let page_values = serde_json::from_str::<TimeOffPage>(&response.body)
+                .map_err(|e| Error::msg(format!("Invalid time off requests: {e}")))?;
+            let is_last =
+                page_values.requests.is_empty() || page_values.requests.len() < query.page_size;
+            fetched += page_values.requests.len();
+            for value in page_values.requests {
+                records.push(value.get())?;
+                if self.save_responses {
+                    raw.push(value.to_owned());
                 }
             }
-        }
-        let response_json = response.json::<Value>().await?;
-        let count = response_json
-            .get("count")
-            .ok_or_else(|| Error::msg("missing count"))?
-            .as_u64()
-            .ok_or_else(|| Error::msg("count is not a number"))? as usize;
-        let time_off_item_results = response_json
-            .get("requests")
-            .ok_or(Error::msg("requests not found"))?
-            .as_array()
-            .ok_or(Error::msg("Array couldn't be formed!"))?
-            .iter()
-            .cloned()
-            .map(serde_json::from_value::<TimeOffItem>);
-
-        let mut time_off_items = Vec::with_capacity(count);
-        for result in time_off_item_results {
-            match result {
-                Ok(result) => time_off_items.push(result),
-                Err(err) => return Err(err.into()),
+            if is_last || fetched >= page_values.count {
+                break;
             }
         }
-
-        if get_settings().await.debug {
-            let path = format!("time_off_items_{}.json", Utc::now().format("%Y%m%d%H%M%S"));
-            if let Err(e) = json_to_disk(path, &time_off_items).await {
+        if self.save_responses {
+            let path = utils::dirs::cache_path(&format!(
+                "time_off_items_{}.json",
+                Utc::now().format("%Y%m%d%H%M%S")
+            ));
+            if let Err(e) = json_to_disk(path, &raw).await {
                 println!("Failed to time off items to disk! {e}")
             };
         }
 
+        let count = fetched;
+        let time_off_items = records.finish();
+        log::debug!(
+            "Parsed {} of {count} time off requests",
+            time_off_items.len()
+        );
+
         Ok(time_off_items)
     }
 }
 
-pub(crate) async fn get_working_days(
+/// Progress bar of the date ranges with the count of the fetched entries
+struct FetchProgress {
+    bar: ProgressBar,
+    entries: AtomicUsize,
+}
+
+impl FetchProgress {
+    fn add_entries(&self, count: usize) {
+        let total = self.entries.fetch_add(count, Ordering::Relaxed) + count;
+        self.bar.set_message(format!("{total} entries"));
+    }
+}
+
+/// Page of the time off requests, the requests are parsed one by one
+#[derive(Deserialize)]
+struct TimeOffPage<'a> {
+    count: usize,
+    #[serde(borrow)]
+    requests: Vec<&'a RawValue>,
+}
+
+/// Entries of a timesheet window
+struct Window {
+    records: Records<TimeEntry>,
+    raw: Vec<Box<RawValue>>,
+}
+
+/// Time entries of a timesheet page, pushed to the window one by one while the array is
+/// deserialized, so that the page is never collected before parsing. The value is the count
+/// of the entries on the page.
+struct PageSeed<'a> {
+    window: &'a mut Window,
+    save_raw: bool,
+    progress: &'a FetchProgress,
+}
+
+impl<'de> DeserializeSeed<'de> for PageSeed<'_> {
+    type Value = usize;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<usize, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for PageSeed<'_> {
+    type Value = usize;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array of time entries")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<usize, A::Error> {
+        let mut count = 0;
+        while let Some(value) = seq.next_element::<&RawValue>()? {
+            self.window
+                .records
+                .push(value.get())
+                .map_err(serde::de::Error::custom)?;
+            if self.save_raw {
+                self.window.raw.push(value.to_owned());
+            }
+            self.progress.add_entries(1);
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+/// Records parsed one by one from their JSON text, so that a response is never held as a
+/// whole `Value`. In strict mode the first failure aborts, otherwise the records that can't be
+/// parsed are skipped with a warning.
+struct Records<T> {
+    kind: &'static str,
+    strict: bool,
+    parsed: Vec<T>,
+    skipped: usize,
+}
+
+impl<T: DeserializeOwned> Records<T> {
+    fn new(kind: &'static str, strict: bool) -> Self {
+        Self {
+            kind,
+            strict,
+            parsed: Vec::new(),
+            skipped: 0,
+        }
+    }
+
+    fn push(&mut self, json: &str) -> Result<(), Error> {
+        match serde_json::from_str::<T>(json) {
+            Ok(record) => self.parsed.push(record),
+            Err(e) => {
+                let id = serde_json::from_str::<Value>(json)
+                    .ok()
+                    .and_then(|v| v.get("id").cloned());
+                self.skip(id, e)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn push_value(&mut self, value: Value) -> Result<(), Error> {
+        let id = value.get("id").cloned();
+        match serde_json::from_value::<T>(value) {
+            Ok(record) => self.parsed.push(record),
+            Err(e) => self.skip(id, e)?,
+        }
+        Ok(())
+    }
+
+    fn skip(&mut self, id: Option<Value>, e: serde_json::Error) -> Result<(), Error> {
+        let id = id.unwrap_or_default();
+        if self.strict {
+            return Err(Error::msg(format!(
+                "Failed to parse {} {id}: {e}",
+                self.kind
+            )));
+        }
+        log::warn!("Skipped {} {id}: {e}", self.kind);
+        self.skipped += 1;
+        Ok(())
+    }
+
+    fn append(&mut self, other: Records<T>) {
+        self.parsed.extend(other.parsed);
+        self.skipped += other.skipped;
+    }
+
+    fn finish(self) -> Vec<T> {
+        if self.skipped > 0 {
+            eprintln!(
+                "Skipped {} unparseable {}(s), see the log or run with --strict.",
+                self.skipped, self.kind
+            );
+        }
+        self.parsed
+    }
+}
+
+/// Parse the records one by one. In strict mode the first failure aborts, otherwise the
+/// records that can't be parsed are skipped with a warning.
+pub fn parse_records<T: DeserializeOwned>(
+    values: Vec<Value>,
+    kind: &'static str,
+    strict: bool,
+) -> Result<Vec<T>, Error> {
+    let mut records = Records::new(kind, strict);
+    for value in values {
+        records.push_value(value)?;
+    }
+    Ok(records.finish())
+}
+
+/// Working days from the date until the end date, by default until today
+pub async fn get_working_days(
     client: ClockifyClient,
     since: &NaiveDate,
+    until: Option<NaiveDate>,
 ) -> Result<Vec<WorkDay>, Error> {
-    let work_items = client.get_work_items_since(since).await?;
-    let work_days = work_items
+    let end = until.unwrap_or(Utc::now().date_naive());
+    let work_items = match client.entry_source {
+        EntrySource::Detailed => {
+            let query = TimesheetQuery::since(*since)
+                .until(end)
+                .concurrency(client.concurrency);
+            client.get_time_entries(&query).await?
+        }
+        EntrySource::Summary => client.get_daily_totals(*since, end).await?,
+    };
+    Ok(time_entries_to_work_days(work_items))
+}
+
+/// Group the entries sorted by the start into days
+pub fn time_entries_to_work_days(entries: Vec<TimeEntry>) -> Vec<WorkDay> {
+    entries
         .into_iter()
         .chunk_by(|wi| wi.start.date_naive())
         .into_iter()
@@ -447,19 +1455,44 @@ pub(crate) async fn get_working_days(
             let work_items = group.map(WorkItem::from).collect();
             WorkDay::new(date, work_items)
         })
-        .collect::<Vec<WorkDay>>();
+        .collect::<Vec<WorkDay>>()
+}
 
-    Ok(work_days)
+pub async fn get_days_off(
+    client: ClockifyClient,
+    since: &NaiveDate,
+    user_settings: &ExtraSettings,
+) -> Result<Vec<Day>, Error> {
+    let items = client.get_time_off_items(&TimeOffQuery::new()).await?;
+    time_off_items_to_days(items, since, user_settings)
 }
 
-pub(crate) async fn get_days_off(
+/// Time off requests that are waiting for approval
+pub async fn get_pending_days_off(
     client: ClockifyClient,
     since: &NaiveDate,
+    user_settings: &ExtraSettings,
+) -> Result<Vec<Day>, Error> {
+    let items = client
+        .get_time_off_items(&TimeOffQuery::new().statuses(&["PENDING"]))
+        .await?;
+    time_off_items_to_days(items, since, user_settings)
+}
+
+pub fn time_off_items_to_days(
+    items: Vec<TimeOffItem>,
+    since: &NaiveDate,
+    user_settings: &ExtraSettings,
 ) -> Result<Vec<Day>, Error> {
-    let items = client.get_time_off_items().await?;
     let days_off = items
         .into_iter()
-        .flat_map(|toi| {
+        .map(|toi| {
+            let type_ = user_settings
+                .time_off_type(&toi.policy_id, &toi.policy_name)
+                .ok_or(Error::msg(format!(
+                    "unknown policyName: {}, add it to policyMapping in the extra settings",
+                    toi.policy_name
+                )))?;
             // TODO support users datetime
             // Use date_naive because:
             // "start": "2024-01-30T22:00:00Z",
@@ -469,28 +1502,43 @@ pub(crate) async fn get_days_off(
             let mut days_off = Vec::new();
             for date in utils::DateRange(start + TimeDelta::days(1), end).filter(|d| d >= since) {
                 let note = toi.note.clone();
-                let day_off = match toi.type_ {
+                let day_off = match &type_ {
                     TimeOffType::SickLeave => {
                         let day = SickLeaveDay::new(note, date);
                         Day::Sick(day)
                     }
                     TimeOffType::Vacation => {
-                        let day = Holiday::new(String::new(), date, HolidayType::Vacation);
+                        let day = Holiday::new(String::new(), date, HolidayType::Vacation)
+                            .with_note(note);
                         Day::Holiday(day)
                     }
                     TimeOffType::ParentalLeave => {
-                        let day = Holiday::new(String::new(), date, HolidayType::ParentalLeave);
+                        let day = Holiday::new(String::new(), date, HolidayType::ParentalLeave)
+                            .with_note(note);
+                        Day::Holiday(day)
+                    }
+                    TimeOffType::UnpaidLeave => {
+                        let day = Holiday::new(String::new(), date, HolidayType::UnpaidLeave)
+                            .with_note(note);
                         Day::Holiday(day)
                     }
                     TimeOffType::DayOff => {
-                        let day = Holiday::new(String::new(), date, HolidayType::Flex);
+                        let day =
+                            Holiday::new(String::new(), date, HolidayType::Flex).with_note(note);
+                        Day::Holiday(day)
+                    }
+                    TimeOffType::Custom(name) => {
+                        let day =
+                            Holiday::new(String::new(), date, HolidayType::Custom(name.clone()))
+                                .with_note(note);
                         Day::Holiday(day)
                     }
                 };
                 days_off.push(day_off);
             }
-            days_off
+            Ok(days_off)
         })
-        .collect::<Vec<Day>>();
+        .flatten_ok()
+        .collect::<Result<Vec<Day>, Error>>()?;
     Ok(days_off)
 }
@@ -0,0 +1,437 @@
+use super::{get_datetime_field, get_string_field, Token};
+use crate::utils;
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use lazy_static::lazy_static;
+use reqwest::header::{
+    HeaderMap, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use reqwest::{Client, Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+lazy_static! {
+    static ref API_URL: Url =
+        Url::parse("https://global.api.clockify.me/").expect("Cannot parse clockify url!");
+    static ref REPORTS_API_URL: Url =
+        Url::parse("https://reports.api.clockify.me/").expect("Cannot parse clockify url!");
+}
+
+/// API of the request, the reports have a host of their own
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ApiHost {
+    #[default]
+    Api,
+    Reports,
+}
+
+/// Request to the Clockify API, the path is relative to the URL of the host
+#[derive(Clone, Debug)]
+pub struct ApiRequest {
+    pub host: ApiHost,
+    pub method: Method,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub body: Option<Value>,
+}
+
+impl ApiRequest {
+    fn new(method: Method, path: &str) -> Self {
+        Self {
+            host: ApiHost::Api,
+            method,
+            path: path.to_owned(),
+            query: Vec::new(),
+            body: None,
+        }
+    }
+
+    pub fn get(path: &str) -> Self {
+        Self::new(Method::GET, path)
+    }
+
+    pub fn post(path: &str, body: Value) -> Self {
+        Self::new(Method::POST, path).body(body)
+    }
+
+    pub fn patch(path: &str, body: Value) -> Self {
+        Self::new(Method::PATCH, path).body(body)
+    }
+
+    pub fn delete(path: &str) -> Self {
+        Self::new(Method::DELETE, path)
+    }
+
+    pub fn query<K: ToString, V: ToString>(mut self, query: &[(K, V)]) -> Self {
+        self.query.extend(
+            query
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string())),
+        );
+        self
+    }
+
+    pub fn body(mut self, body: Value) -> Self {
+        self.body = Some(body);
+        self
+    }
+
+    /// Send the request to the reports API instead
+    pub fn reports(mut self) -> Self {
+        self.host = ApiHost::Reports;
+        self
+    }
+
+    /// Value of the query parameter, if given
+    pub fn query_value(&self, key: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Status and body of a response
+#[derive(Clone, Debug)]
+pub struct ApiResponse {
+    pub status: StatusCode,
+    pub body: String,
+}
+
+impl ApiResponse {
+    pub fn new(status: StatusCode, body: String) -> Self {
+        Self { status, body }
+    }
+
+    /// Successful response with the JSON body
+    pub fn ok(body: &Value) -> Self {
+        Self::new(StatusCode::OK, body.to_string())
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.status.is_success()
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        serde_json::from_str(&self.body).map_err(Error::from)
+    }
+}
+
+/// Transport of the requests of `ClockifyClient`, so that the client can be backed by
+/// something else than HTTP, e.g. recorded responses or the mocks of the tests
+pub trait ClockifyApi: fmt::Debug + Send + Sync {
+    fn send(&self, request: ApiRequest) -> BoxFuture<'_, Result<ApiResponse, Error>>;
+}
+
+/// The Clockify API over HTTP, authenticated with the token
+#[derive(Clone, Debug)]
+pub struct HttpApi {
+    base_url: Url,
+    reports_url: Url,
+    client: Client,
+    responses: Option<ResponseStore>,
+    token_hash: String,
+}
+
+/// Validators and body of a response, sent back with the next request of the URL
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredResponse {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: String,
+}
+
+/// Responses with validators, a file per URL and token
+#[derive(Clone, Debug)]
+struct ResponseStore {
+    dir: PathBuf,
+    key: String,
+}
+
+impl ResponseStore {
+    fn path(&self, url: &Url) -> PathBuf {
+        let name = utils::sha256_hex(format!("{}{url}", self.key).as_bytes());
+        self.dir.join(format!("{name}.json"))
+    }
+
+    /// The stored response of the URL, unreadable files are ignored
+    fn get(&self, url: &Url) -> Option<StoredResponse> {
+        let text = std::fs::read_to_string(self.path(url)).ok()?;
+        serde_json::from_str::<StoredResponse>(&text)
+            .ok()
+            .filter(|stored| stored.url == url.as_str())
+    }
+
+    /// Store the response if it has validators
+    fn save(&self, url: &Url, headers: &HeaderMap, body: &str) {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value: &HeaderValue| value.to_str().ok())
+                .map(str::to_owned)
+        };
+        let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+        if etag.is_none() && last_modified.is_none() {
+            return;
+        }
+        let stored = StoredResponse {
+            url: url.to_string(),
+            etag,
+            last_modified,
+            body: body.to_owned(),
+        };
+        let result = serde_json::to_vec(&stored)
+            .map_err(Error::from)
+            .and_then(|json| utils::dirs::write_atomic(&self.path(url), &json));
+        if let Err(e) = result {
+            log::warn!("Failed to store the response of {url}: {e}");
+        }
+    }
+}
+
+/// The paths are joined to the URL, so it has to end with a slash to keep its own path
+fn with_trailing_slash(mut url: Url) -> Url {
+    if !url.path().ends_with('/') {
+        let path = format!("{}/", url.path());
+        url.set_path(&path);
+    }
+    url
+}
+
+impl HttpApi {
+    pub fn new(token: &Token) -> Result<Self, Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-api-key", token.into());
+        let client = utils::http::client_builder()?
+            .default_headers(headers)
+            .build()?;
+        Ok(Self {
+            base_url: API_URL.clone(),
+            reports_url: REPORTS_API_URL.clone(),
+            client,
+            responses: None,
+            token_hash: token.hash(),
+        })
+    }
+
+    /// Store the responses of the GET requests that have an ETag or a Last-Modified header in
+    /// the directory, and make the next requests of the same URLs conditional. A response of
+    /// 304 Not Modified is answered with the stored body.
+    pub fn conditional(mut self, dir: PathBuf) -> Self {
+        self.responses = Some(ResponseStore {
+            dir,
+            key: self.token_hash.clone(),
+        });
+        self
+    }
+
+    /// URL of the API instead of the global one, e.g. a regional endpoint or a proxy in front
+    /// of the API
+    pub fn api_url(mut self, url: Url) -> Self {
+        self.base_url = with_trailing_slash(url);
+        self
+    }
+
+    /// URL of the reports API instead of the global one
+    pub fn reports_api_url(mut self, url: Url) -> Self {
+        self.reports_url = with_trailing_slash(url);
+        self
+    }
+
+    pub fn url(&self, host: ApiHost) -> &Url {
+        match host {
+            ApiHost::Api => &self.base_url,
+            ApiHost::Reports => &self.reports_url,
+        }
+    }
+}
+
+impl ClockifyApi for HttpApi {
+    fn send(&self, request: ApiRequest) -> BoxFuture<'_, Result<ApiResponse, Error>> {
+        Box::pin(async move {
+            let mut url = self.url(request.host).join(&request.path)?;
+            if !request.query.is_empty() {
+                url.query_pairs_mut().extend_pairs(&request.query);
+            }
+            let responses = self
+                .responses
+                .as_ref()
+                .filter(|_| request.method == Method::GET);
+            let stored = responses.and_then(|responses| responses.get(&url));
+
+            let mut builder = self.client.request(request.method, url.clone());
+            if let Some(body) = &request.body {
+                builder = builder.json(body);
+            }
+            if let Some(stored) = &stored {
+                if let Some(etag) = &stored.etag {
+                    builder = builder.header(IF_NONE_MATCH, etag);
+                }
+                if let Some(last_modified) = &stored.last_modified {
+                    builder = builder.header(IF_MODIFIED_SINCE, last_modified);
+                }
+            }
+            let response = builder.send().await?;
+            let status = response.status();
+            if let (StatusCode::NOT_MODIFIED, Some(stored)) = (status, stored) {
+                log::debug!("Not modified: {url}");
+                return Ok(ApiResponse::new(StatusCode::OK, stored.body));
+            }
+            let headers = response.headers().clone();
+            let body = response.text().await?;
+            if let Some(responses) = responses.filter(|_| status.is_success()) {
+                responses.save(&url, &headers, &body);
+            }
+            Ok(ApiResponse::new(status, body))
+        })
+    }
+}
+
+/// Raw records of the recorded files with the prefix, oldest file first
+fn read_recorded(dir: &Path, prefix: &str) -> Result<Vec<Value>, Error> {
+    let mut paths = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(prefix) && name.ends_with(".json"))
+        })
+        .collect::<Vec<PathBuf>>();
+    // The names end with the time of the recording
+    paths.sort();
+    let mut values = Vec::new();
+    for path in paths {
+        let text = std::fs::read_to_string(&path)?;
+        values.extend(
+            serde_json::from_str::<Vec<Value>>(&text)
+                .map_err(|e| Error::msg(format!("Failed to read {}: {e}", path.display())))?,
+        );
+    }
+    Ok(values)
+}
+
+/// Page of the records, pages start from 1
+fn page(values: Vec<Value>, page: usize, page_size: usize) -> Vec<Value> {
+    values
+        .into_iter()
+        .skip(page.saturating_sub(1) * page_size)
+        .take(page_size)
+        .collect()
+}
+
+/// Answers the requests of time entries and time off with the responses recorded with
+/// `ClockifyClient::save_responses` in the directory, paged and filtered like the API does
+#[derive(Clone, Debug)]
+pub struct RecordedApi {
+    dir: PathBuf,
+}
+
+impl RecordedApi {
+    pub fn new(dir: &Path) -> Result<Self, Error> {
+        if !dir.is_dir() {
+            return Err(Error::msg(format!("{} is not a directory!", dir.display())));
+        }
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    /// The newest recorded user, older recordings without one get a user without an email
+    fn user(&self) -> Result<Value, Error> {
+        Ok(match read_recorded(&self.dir, "user_")?.pop() {
+            Some(user) => user,
+            None => {
+                log::warn!(
+                    "No user_*.json in {}, the extra settings of the user are not found",
+                    self.dir.display()
+                );
+                serde_json::json!({
+                    "id": "0",
+                    "activeWorkspace": "0",
+                    "name": "Replay",
+                    "email": "",
+                })
+            }
+        })
+    }
+
+    /// Entries starting in the range of the query
+    fn timesheet(&self, request: &ApiRequest) -> Result<Value, Error> {
+        let time = |key: &str| -> Result<DateTime<Utc>, Error> {
+            let value = request
+                .query_value(key)
+                .ok_or(Error::msg(format!("Missing {key} of the timesheet query")))?;
+            Ok(DateTime::parse_from_rfc3339(value)?.with_timezone(&Utc))
+        };
+        let (start, end) = (time("start")?, time("end")?);
+        let entries = read_recorded(&self.dir, "work_items_")?
+            .into_iter()
+            .filter(|v| {
+                v.get("timeInterval")
+                    .and_then(|t| get_datetime_field::<serde_json::Error>(t, "start").ok())
+                    .is_some_and(|entry_start| start <= entry_start && entry_start < end)
+            })
+            .collect();
+        let number = request.query_value("page").unwrap_or("1").parse()?;
+        let size = request.query_value("page-size").unwrap_or("50").parse()?;
+        Ok(Value::Array(page(entries, number, size)))
+    }
+
+    /// Requests with the statuses of the body
+    fn time_off(&self, request: &ApiRequest) -> Result<Value, Error> {
+        let body = request.body.clone().unwrap_or_default();
+        let statuses = body
+            .get("status")
+            .and_then(Value::as_array)
+            .map(|statuses| {
+                statuses
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let requests = read_recorded(&self.dir, "time_off_items_")?
+            .into_iter()
+            .filter(|v| {
+                v.get("status")
+                    .and_then(|s| get_string_field::<serde_json::Error>(s, "statusType").ok())
+                    .is_none_or(|status| statuses.contains(&status.as_str()))
+            })
+            .collect::<Vec<Value>>();
+        let count = requests.len();
+        let number = body.get("page").and_then(Value::as_u64).unwrap_or(1) as usize;
+        let size = body.get("pageSize").and_then(Value::as_u64).unwrap_or(50) as usize;
+        Ok(serde_json::json!({
+            "count": count,
+            "requests": page(requests, number, size),
+        }))
+    }
+}
+
+impl ClockifyApi for RecordedApi {
+    fn send(&self, request: ApiRequest) -> BoxFuture<'_, Result<ApiResponse, Error>> {
+        Box::pin(async move {
+            let body = match (&request.method, request.path.as_str()) {
+                (&Method::GET, "v1/user") => self.user()?,
+                (&Method::GET, path) if path.ends_with("/timesheet") => self.timesheet(&request)?,
+                (&Method::POST, path) if path.ends_with("/time-off/requests") => {
+                    self.time_off(&request)?
+                }
+                (method, path) => {
+                    return Err(Error::msg(format!(
+                        "Only time entries and time off requests can be replayed from {}, not {method} {path}!",
+                        self.dir.display()
+                    )))
+                }
+            };
+            Ok(ApiResponse::ok(&body))
+        })
+    }
+}
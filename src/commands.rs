@@ -0,0 +1,30 @@
+pub(crate) mod anomalies;
+pub(crate) mod api;
+pub(crate) mod auth;
+pub(crate) mod calendar;
+pub(crate) mod chart;
+pub(crate) mod config;
+pub(crate) mod cost;
+pub(crate) mod doctor;
+pub(crate) mod export;
+pub(crate) mod forecast;
+pub(crate) mod goals;
+pub(crate) mod history;
+pub(crate) mod holidays;
+pub(crate) mod hr_export;
+pub(crate) mod import;
+pub(crate) mod log;
+pub(crate) mod members;
+pub(crate) mod monthly;
+pub(crate) mod payout;
+pub(crate) mod plan;
+pub(crate) mod prompt;
+pub(crate) mod report;
+pub(crate) mod schedule;
+pub(crate) mod show;
+pub(crate) mod state;
+pub(crate) mod take_flex;
+pub(crate) mod team;
+pub(crate) mod timer;
+pub(crate) mod undo;
+pub(crate) mod upcoming;
@@ -0,0 +1,135 @@
+use crate::args::get_settings;
+use crate::clockify::Token;
+use crate::models::{Day, WorkDay};
+use crate::utils::table::format_duration;
+use crate::{get_context, get_items, get_since_date, utils, Items};
+use anyhow::Error;
+use chrono::{Local, NaiveDate};
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+/// Days with more logged time are likely forgotten timers
+const MAX_DAY_SEC: i64 = 14 * 3600;
+
+const NO_TIME_LOGGED: &str = "No time logged and no time off or holiday";
+
+/// Days that are likely data errors, with the reason
+fn find_anomalies(
+    first_date: NaiveDate,
+    last_date: NaiveDate,
+    public_holidays: &[Day],
+    working_days: &[WorkDay],
+    days_off: &[Day],
+    min_gap_sec: Option<i64>,
+) -> Vec<(NaiveDate, String)> {
+    let recognized = public_holidays
+        .iter()
+        .chain(days_off)
+        .map(Day::date)
+        .collect::<HashSet<NaiveDate>>();
+    let worked = working_days
+        .iter()
+        .map(|wd| (wd.date, wd))
+        .collect::<HashMap<NaiveDate, &WorkDay>>();
+
+    let mut anomalies = Vec::new();
+    for date in utils::DateRange(first_date, last_date) {
+        let Some(wd) = worked.get(&date) else {
+            if utils::is_weekday(&date) && !recognized.contains(&date) {
+                anomalies.push((date, NO_TIME_LOGGED.into()));
+            }
+            continue;
+        };
+
+        if wd.duration() > MAX_DAY_SEC {
+            anomalies.push((date, format!("{} logged", format_duration(wd.duration()))));
+        }
+        for (previous, item) in wd.overlaps() {
+            anomalies.push((
+                date,
+                format!(
+                    "Overlapping entries \"{}\" and \"{}\" at {}",
+                    previous.description(),
+                    item.description(),
+                    item.start().with_timezone(&Local).format("%H:%M")
+                ),
+            ));
+        }
+        for (previous, item) in min_gap_sec.map(|sec| wd.gaps(sec)).unwrap_or_default() {
+            let stop = previous.stop().with_timezone(&Local);
+            let start = item.start().with_timezone(&Local);
+            anomalies.push((
+                date,
+                format!(
+                    "Unlogged gap of {} between {} and {}",
+                    format_duration((start - stop).num_seconds()),
+                    stop.format("%H:%M"),
+                    start.format("%H:%M")
+                ),
+            ));
+        }
+        for item in wd.items.iter().filter(|wi| wi.project().is_empty()) {
+            anomalies.push((
+                date,
+                format!("Entry \"{}\" has no project", item.description()),
+            ));
+        }
+    }
+    anomalies
+}
+
+pub(crate) async fn run(token: &Token, gap_hours: Option<f32>) -> Result<(), Error> {
+    if gap_hours.is_some_and(|hours| hours <= 0.0) {
+        return Err(Error::msg("Gap threshold must be positive!"));
+    }
+
+    let args = get_settings().await;
+    let since_date = get_since_date(&args, token).await?;
+    let (client, context) = get_context(token).await?;
+    let user_settings = &context.settings;
+    let Items {
+        public_holidays,
+        working_days,
+        days_off,
+        ..
+    } = get_items(client, since_date, user_settings).await?;
+
+    let working_days = working_days
+        .into_iter()
+        .filter(|wd| !user_settings.is_ignored_work_day(wd))
+        .collect_vec();
+    let Some(first_date) = working_days.iter().map(|wd| wd.date).min() else {
+        println!("No working days found.");
+        return Ok(());
+    };
+    let last_date = context.last_counted_date(args.include_today);
+
+    let anomalies = find_anomalies(
+        first_date,
+        last_date,
+        &public_holidays,
+        &working_days,
+        &days_off,
+        gap_hours.map(|hours| (hours * 3600.0) as i64),
+    );
+    if anomalies.is_empty() {
+        println!("No anomalies found since {first_date}.");
+        return Ok(());
+    }
+
+    let has_empty_days = anomalies.iter().any(|(_, issue)| issue == NO_TIME_LOGGED);
+    let mut builder = Builder::default();
+    builder.push_record(["Date", "Issue"]);
+    for (date, issue) in anomalies {
+        builder.push_record([format!("{date} {}", date.format("%a")), issue]);
+    }
+    let mut table = builder.build();
+    table.with(Style::modern_rounded());
+    println!("{table}");
+    if has_empty_days {
+        println!("Fill the days without logged time with `log <date> --hours <hours> --project <project>`.");
+    }
+    Ok(())
+}
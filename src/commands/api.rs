@@ -0,0 +1,18 @@
+use crate::args::ApiAction;
+use crate::clockify::Token;
+use crate::new_client;
+use anyhow::Error;
+
+pub(crate) async fn run(token: &Token, action: &ApiAction) -> Result<(), Error> {
+    let client = new_client(token).await?;
+    match action {
+        ApiAction::Get { path, query } => {
+            let (status, body) = client.get_raw(path, query).await?;
+            if !status.is_success() {
+                eprintln!("Request failed with status {status}");
+            }
+            println!("{}", serde_json::to_string_pretty(&body)?);
+        }
+    }
+    Ok(())
+}
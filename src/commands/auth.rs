@@ -0,0 +1,26 @@
+use crate::args::{get_settings, AuthAction};
+use crate::clockify::{ClockifyClient, Token};
+use anyhow::Error;
+use std::sync::Arc;
+
+pub(crate) async fn run(action: &AuthAction) -> Result<(), Error> {
+    match action {
+        AuthAction::Login => {
+            let token = rpassword::prompt_password("Clockify API token: ")?;
+            let token = Token::new(token.trim());
+            // Only working tokens are saved
+            let api = get_settings().await.http_api(&token)?;
+            let client = ClockifyClient::with_api(Arc::new(api)).await?;
+            token.save_to_keyring()?;
+            println!("Token of {} saved to the keyring.", client.user.email);
+        }
+        AuthAction::Logout => {
+            if Token::delete_from_keyring()? {
+                println!("Token deleted from the keyring.");
+            } else {
+                println!("No token in the keyring.");
+            }
+        }
+    }
+    Ok(())
+}
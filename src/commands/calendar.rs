@@ -0,0 +1,219 @@
+use crate::clockify::Token;
+use crate::models::{Day, WorkDay};
+use crate::{get_context, get_items, utils, CalculationContext, Items};
+use anyhow::Error;
+use chrono::{Datelike, Duration, NaiveDate};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+const RESET: &str = "\x1b[0m";
+const CELL: &str = "■ ";
+
+const FREE: &str = "\x1b[38;5;238m";
+const ABSENCE: &str = "\x1b[38;5;33m";
+const EXTRA: &str = "\x1b[38;5;28m";
+const OVER: &str = "\x1b[38;5;46m";
+const ON_TARGET: &str = "\x1b[38;5;34m";
+const UNDER: &str = "\x1b[38;5;178m";
+const FAR_UNDER: &str = "\x1b[38;5;208m";
+const NOTHING: &str = "\x1b[38;5;160m";
+
+enum Cell {
+    Outside,
+    Future,
+    Free,
+    Absence,
+    Worked { worked: i64, expected: i64 },
+}
+
+impl Cell {
+    fn color(&self) -> &'static str {
+        match self {
+            Cell::Outside | Cell::Future => "",
+            Cell::Free => FREE,
+            Cell::Absence => ABSENCE,
+            // Work on a day without expected time, e.g. weekends
+            Cell::Worked { worked, expected } if *expected == 0 => {
+                if *worked > 0 {
+                    EXTRA
+                } else {
+                    FREE
+                }
+            }
+            Cell::Worked { worked, expected } => {
+                let ratio = *worked as f32 / *expected as f32;
+                if ratio >= 1.1 {
+                    OVER
+                } else if ratio >= 0.95 {
+                    ON_TARGET
+                } else if ratio >= 0.5 {
+                    UNDER
+                } else if ratio > 0.0 {
+                    FAR_UNDER
+                } else {
+                    NOTHING
+                }
+            }
+        }
+    }
+
+    fn render(&self) -> String {
+        match self {
+            Cell::Outside | Cell::Future => "  ".into(),
+            _ => format!("{}{CELL}{RESET}", self.color()),
+        }
+    }
+}
+
+struct Calendar {
+    year: i32,
+    worked: HashMap<NaiveDate, i64>,
+    /// Public holidays with their names
+    holidays: BTreeMap<NaiveDate, String>,
+    absences: HashSet<NaiveDate>,
+    context: CalculationContext,
+}
+
+impl Calendar {
+    fn new(
+        year: i32,
+        public_holidays: Vec<Day>,
+        working_days: Vec<WorkDay>,
+        days_off: Vec<Day>,
+        context: CalculationContext,
+    ) -> Self {
+        let user_settings = &context.settings;
+        let mut worked = HashMap::new();
+        for wd in working_days
+            .into_iter()
+            .filter(|wd| !user_settings.is_ignored_work_day(wd))
+        {
+            *worked.entry(wd.date).or_insert(0) += wd.duration();
+        }
+        let holidays = public_holidays
+            .into_iter()
+            .filter(|d| !user_settings.is_ignored(d))
+            .map(|d| (d.date(), d.title().unwrap_or_default().to_string()))
+            .collect();
+        let absences = days_off
+            .into_iter()
+            .filter(|d| !user_settings.is_ignored(d))
+            .map(Day::into_date)
+            .collect();
+        Self {
+            year,
+            worked,
+            holidays,
+            absences,
+            context,
+        }
+    }
+
+    fn cell(&self, date: NaiveDate) -> Cell {
+        if date.year() != self.year {
+            return Cell::Outside;
+        }
+        if date > self.context.today {
+            return Cell::Future;
+        }
+
+        let worked = self.worked.get(&date).copied().unwrap_or(0);
+        let is_free = !utils::is_weekday(&date) || self.holidays.contains_key(&date);
+        if self.absences.contains(&date) && worked == 0 {
+            return Cell::Absence;
+        }
+        if is_free && worked == 0 {
+            return Cell::Free;
+        }
+
+        let expected = if is_free || self.absences.contains(&date) {
+            0
+        } else {
+            self.context.expected_day_secs(&date)
+        };
+        Cell::Worked { worked, expected }
+    }
+
+    fn render(&self) -> String {
+        let first_day = NaiveDate::from_ymd_opt(self.year, 1, 1).unwrap();
+        let start = first_day - Duration::days(first_day.weekday().num_days_from_monday() as i64);
+        let weeks =
+            (NaiveDate::from_ymd_opt(self.year, 12, 31).unwrap() - start).num_days() / 7 + 1;
+
+        // Month labels above the first week of each month
+        let mut months = vec![' '; weeks as usize * 2 + 1];
+        let mut previous_month = 0;
+        for week in 0..weeks {
+            let sunday = start + Duration::days(week * 7 + 6);
+            if sunday.year() != self.year || sunday.month() == previous_month {
+                continue;
+            }
+            previous_month = sunday.month();
+            let label = sunday.format("%b").to_string();
+            let position = week as usize * 2;
+            if position + label.len() <= months.len() {
+                for (i, c) in label.chars().enumerate() {
+                    months[position + i] = c;
+                }
+            }
+        }
+
+        let mut output = format!("{}\n    {}\n", self.year, months.iter().collect::<String>());
+        for (row, weekday) in ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]
+            .iter()
+            .enumerate()
+        {
+            output.push_str(&format!("{weekday} "));
+            for week in 0..weeks {
+                let date = start + Duration::days(week * 7 + row as i64);
+                output.push_str(&self.cell(date).render());
+            }
+            output.push('\n');
+        }
+
+        let legend = [
+            (OVER, "over"),
+            (ON_TARGET, "on target"),
+            (UNDER, "under"),
+            (NOTHING, "nothing logged"),
+            (EXTRA, "extra day"),
+            (ABSENCE, "absence"),
+            (FREE, "free"),
+        ]
+        .iter()
+        .map(|(color, text)| format!("{color}{CELL}{RESET}{text}"))
+        .collect::<Vec<String>>()
+        .join("  ");
+        output.push_str(&format!("\n    {legend}"));
+
+        let holidays = self
+            .holidays
+            .iter()
+            .filter(|(date, title)| date.year() == self.year && !title.is_empty())
+            .map(|(date, title)| format!("{}.{}. {title}", date.day(), date.month()))
+            .collect::<Vec<String>>();
+        if !holidays.is_empty() {
+            output.push_str(&format!("\n\n    {}", holidays.join("\n    ")));
+        }
+        output
+    }
+}
+
+pub(crate) async fn run(token: &Token, year: Option<i32>) -> Result<(), Error> {
+    let year = year.unwrap_or(utils::today().year());
+    let since = NaiveDate::from_ymd_opt(year, 1, 1)
+        .ok_or_else(|| Error::msg(format!("Invalid year {year}")))?;
+    if since > utils::today() {
+        return Err(Error::msg("Year cannot be in the future!"));
+    }
+
+    let (client, context) = get_context(token).await?;
+    let Items {
+        public_holidays,
+        working_days,
+        days_off,
+        ..
+    } = get_items(client, since, &context.settings).await?;
+    let calendar = Calendar::new(year, public_holidays, working_days, days_off, context);
+    println!("{}", calendar.render());
+    Ok(())
+}
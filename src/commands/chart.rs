@@ -0,0 +1,46 @@
+use crate::args::get_settings;
+use crate::calculator::daily_balances;
+use crate::clockify::Token;
+use crate::{calculation_context, get_client, get_items, get_since_date};
+use anyhow::Error;
+use chrono::Weekday;
+use std::collections::BTreeMap;
+use textplots::{Chart, Plot, Shape};
+
+const WIDTH: u32 = 160;
+const HEIGHT: u32 = 60;
+
+/// Line chart of the cumulative balance at the end of every week since the start date
+pub(crate) async fn run(token: &Token) -> Result<(), Error> {
+    let args = get_settings().await;
+    let since_date = get_since_date(&args, token).await?;
+    let (client, user_settings) = get_client(token).await?;
+    let items = get_items(client, since_date, &user_settings).await?;
+
+    let context = calculation_context(&args, user_settings);
+    let days = daily_balances(&items, &context, args.include_today);
+    let Some(first_day) = days.first() else {
+        return Err(Error::msg("No working days to chart!"));
+    };
+    let first_monday = first_day.date.week(Weekday::Mon).first_day();
+
+    let mut balance = args.start_balance.unwrap_or(0) * 60;
+    let mut weeks = BTreeMap::from([(0, balance)]);
+    for day in &days {
+        balance += day.contribution();
+        let week = (day.date - first_monday).num_days() / 7 + 1;
+        weeks.insert(week, balance);
+    }
+
+    let points = weeks
+        .iter()
+        .map(|(week, balance)| (*week as f32, *balance as f32 / 3600.0))
+        .collect::<Vec<(f32, f32)>>();
+    let last_week = weeks.keys().last().copied().unwrap_or(1).max(1);
+
+    println!("Balance in hours at the end of each week since {first_monday}:");
+    Chart::new(WIDTH, HEIGHT, 0.0, last_week as f32)
+        .lineplot(&Shape::Lines(&points))
+        .display();
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use crate::args::{validate_date, ConfigAction};
+use crate::config::{ConfigFile, CONFIG_FILE};
+use crate::extra_settings::schema::ExpectedWorkingHours;
+use crate::extra_settings::{settings_path, validation, GlobalSettings};
+use crate::utils;
+use anyhow::Error;
+use chrono::NaiveDate;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// Ask for a value on the terminal, an empty answer is None
+fn prompt<T: FromStr>(question: &str) -> Result<Option<T>, Error>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    print!("{question}: ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    match answer.trim() {
+        "" => Ok(None),
+        answer => Ok(Some(answer.parse()?)),
+    }
+}
+
+/// Write the settings of the user and the config file, the options not given are asked
+async fn init(
+    email: Option<&str>,
+    hours: Option<f32>,
+    start_date: Option<NaiveDate>,
+    start_balance: Option<i64>,
+    force: bool,
+) -> Result<(), Error> {
+    let config_path = utils::dirs::config_path(CONFIG_FILE);
+    if config_path.exists() && !force {
+        return Err(Error::msg(format!(
+            "{} already exists! Use --force to overwrite it.",
+            config_path.display()
+        )));
+    }
+    let mut global_settings = GlobalSettings::create_settings().await?;
+
+    let email = match email {
+        Some(email) => email.to_owned(),
+        None => prompt::<String>("Email of the Clockify user")?
+            .ok_or(Error::msg("Email is required!"))?,
+    };
+    if global_settings.has_user_settings(&email) && !force {
+        return Err(Error::msg(format!(
+            "Settings of {email} already exist! Use --force to replace them."
+        )));
+    }
+    let hours = match hours {
+        Some(hours) => hours,
+        None => prompt(&format!(
+            "Expected hours per weekday [{}]",
+            utils::work_day_hours()
+        ))?
+        .unwrap_or(utils::work_day_hours()),
+    };
+    if !(0.0..=24.0).contains(&hours) {
+        return Err(Error::msg("Hours must be between 0 and 24!"));
+    }
+    let start_date = match start_date {
+        Some(date) => Some(date),
+        None => prompt::<String>("Start date in the format YYYY-MM-DD [first entry]")?
+            .map(|date| validate_date(&date))
+            .transpose()?,
+    };
+    let start_balance = match (start_date, start_balance) {
+        (None, _) => None,
+        (Some(_), Some(balance)) => Some(balance),
+        (Some(_), None) => prompt("Balance on the start date in minutes [0]")?,
+    };
+
+    let mut user_settings = global_settings.default_user_settings(&email);
+    if hours != utils::work_day_hours() {
+        let from = start_date.unwrap_or(utils::default_start_date());
+        let until = NaiveDate::from_ymd_opt(9999, 12, 31).unwrap();
+        let name = format!("{hours} hours per day");
+        user_settings.set_expected_hours(ExpectedWorkingHours::new(name, from, until, hours));
+    }
+    global_settings.set_user_settings(user_settings);
+    global_settings.save().await?;
+    println!("Settings of {email} saved to {}", settings_path().display());
+
+    let config = ConfigFile {
+        start_date: start_date.map(|date| date.to_string()),
+        start_balance,
+        ..ConfigFile::default()
+    };
+    config.save(&config_path)?;
+    println!("Options saved to {}", config_path.display());
+    Ok(())
+}
+
+/// Print the problems of the extra settings, fails if there are any
+fn validate() -> Result<(), Error> {
+    let path = settings_path();
+    if !path.is_file() {
+        println!(
+            "{} doesn't exist, no extra settings are used.",
+            path.display()
+        );
+        return Ok(());
+    }
+    let json = std::fs::read_to_string(&path)?;
+    let (settings, problems) = validation::validate(&json);
+    for problem in &problems {
+        println!("{problem}");
+    }
+    match settings {
+        Some(settings) if problems.is_empty() => {
+            println!("{} is valid, {} users.", path.display(), settings.len());
+            Ok(())
+        }
+        _ => Err(Error::msg(format!(
+            "{} problems found in {}",
+            problems.len(),
+            path.display()
+        ))),
+    }
+}
+
+pub(crate) async fn run(action: &ConfigAction) -> Result<(), Error> {
+    match action {
+        ConfigAction::Init {
+            email,
+            hours,
+            start_date,
+            start_balance,
+            force,
+        } => {
+            init(
+                email.as_deref(),
+                *hours,
+                *start_date,
+                *start_balance,
+                *force,
+            )
+            .await
+        }
+        ConfigAction::Validate => validate(),
+    }
+}
@@ -0,0 +1,59 @@
+use crate::clockify::{Token, WorkspaceMember};
+use crate::commands::members;
+use crate::extra_settings::GlobalSettings;
+use crate::new_client;
+use anyhow::Error;
+use std::path::Path;
+
+const HEADER: [&str; 5] = ["Name", "Email", "Balance (h)", "Hourly rate", "Cost"];
+
+type Row = [String; 5];
+
+fn member_row(member: &WorkspaceMember, balance: Result<i64, Error>) -> Row {
+    let rate = member.hourly_rate();
+    let rate_str = rate
+        .map(|r| format!("{:.2} {}", r.amount as f64 / 100.0, r.currency))
+        .unwrap_or("-".into());
+
+    let (balance_str, cost_str) = match balance {
+        Ok(balance) => {
+            let hours = balance as f64 / 3600.0;
+            let cost = rate
+                .map(|r| format!("{:.2} {}", hours * r.amount as f64 / 100.0, r.currency))
+                .unwrap_or("-".into());
+            (format!("{hours:.2}"), cost)
+        }
+        Err(e) => (format!("Error: {e}"), "-".into()),
+    };
+
+    [
+        member.user.name.clone(),
+        member.user.email.clone(),
+        balance_str,
+        rate_str,
+        cost_str,
+    ]
+}
+
+/// Cost of the overtime balance (balance hours × hourly rate) per workspace member
+pub(crate) async fn run(token: &Token, emails: &[String], csv: Option<&Path>) -> Result<(), Error> {
+    let extra_settings = GlobalSettings::create_settings().await?;
+    let client = new_client(token).await?;
+    let members = members::select(&client, emails).await?;
+
+    let mut rows = Vec::with_capacity(members.len());
+    for member in members {
+        println!("Calculating balance of {}...", member.user.name);
+        let balance = members::results(client.for_user(&member.user), &extra_settings)
+            .await
+            .map(|r| r.balance);
+        rows.push(member_row(&member, balance));
+    }
+
+    if let Some(path) = csv {
+        members::write_csv(path, &HEADER, &rows)?;
+        println!("Cost report saved to {}", path.display());
+    }
+    members::print_table(&HEADER, rows);
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use crate::args::get_settings;
+use crate::clockify::Token;
+use crate::extra_settings::{self, settings_path, validation, SettingsSource};
+use crate::utils::{audit, dirs, holidays, http};
+use anyhow::Error;
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+/// Print the versions and the state of the local files
+pub(crate) async fn run() -> Result<(), Error> {
+    let args = get_settings().await;
+    let mut builder = Builder::default();
+    builder.push_record(["Check", "Status"]);
+    builder.push_record(["Version", env!("CARGO_PKG_VERSION")]);
+
+    let holidays_status = match holidays::load() {
+        Ok(dataset) => format!(
+            "{} {} ({}), {} holidays {} - {}",
+            holidays::country(),
+            dataset.version,
+            holidays::source(),
+            dataset.holidays.len(),
+            dataset
+                .first_date()
+                .map(|d| d.to_string())
+                .unwrap_or_default(),
+            dataset
+                .last_date()
+                .map(|d| d.to_string())
+                .unwrap_or_default(),
+        ),
+        Err(e) => format!("Error: {e}"),
+    };
+    builder.push_record(["Holidays dataset".to_string(), holidays_status]);
+
+    builder.push_record([
+        "Config directory".to_string(),
+        dirs::config_dir().display().to_string(),
+    ]);
+    builder.push_record([
+        "Cache directory".to_string(),
+        dirs::cache_dir().display().to_string(),
+    ]);
+
+    let path = settings_path();
+    let settings_status = if path.is_file() {
+        match std::fs::read_to_string(&path).map(|json| validation::validate(&json)) {
+            Ok((Some(settings), problems)) => format!(
+                "{}, {} users, {} warnings",
+                path.display(),
+                settings.len(),
+                problems.len()
+            ),
+            Ok((None, _)) => format!("{} is invalid, see `config validate`", path.display()),
+            Err(e) => format!("Error reading {}: {e}", path.display()),
+        }
+    } else {
+        format!("{} not found", path.display())
+    };
+    builder.push_record(["Extra settings".to_string(), settings_status]);
+    if let SettingsSource::Url { url, .. } = extra_settings::source() {
+        builder.push_record(["Extra settings URL".to_string(), url.to_string()]);
+    }
+
+    let token_status = if let Some(source) = args.token_source {
+        source.to_string()
+    } else {
+        match Token::from_keyring() {
+            Ok(Some(_)) => "Found in the keyring".to_string(),
+            Ok(None) => "Missing".to_string(),
+            Err(e) => format!("Error reading the keyring: {e}"),
+        }
+    };
+    builder.push_record(["Token".to_string(), token_status]);
+    if let Some(profile) = &args.profile {
+        builder.push_record(["Profile".to_string(), profile.clone()]);
+    }
+    if let Some(url) = &args.api_url {
+        builder.push_record(["API URL".to_string(), url.to_string()]);
+    }
+    if let Some(url) = &args.reports_api_url {
+        builder.push_record(["Reports API URL".to_string(), url.to_string()]);
+    }
+    if let Some(proxy) = &args.proxy {
+        // The password of the proxy is not shown
+        let mut proxy = proxy.clone();
+        if proxy.password().is_some() {
+            let _ = proxy.set_password(Some("***"));
+        }
+        builder.push_record(["Proxy".to_string(), proxy.to_string()]);
+    }
+    if let Some(path) = &args.ca_bundle {
+        let status = match http::client() {
+            Ok(_) => path.display().to_string(),
+            Err(e) => format!("Error: {e}"),
+        };
+        builder.push_record(["CA bundle".to_string(), status]);
+    }
+
+    let audit_status = match audit::read_entries() {
+        Ok(entries) => format!("{} entries", entries.len()),
+        Err(e) => format!("Error: {e}"),
+    };
+    builder.push_record(["Audit log".to_string(), audit_status]);
+
+    let mut table = builder.build();
+    table.with(Style::modern_rounded());
+    println!("{table}");
+    Ok(())
+}
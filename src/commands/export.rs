@@ -0,0 +1,166 @@
+use crate::args::{get_settings, ExportAction};
+use crate::clockify::{TimeOffQuery, Token};
+use crate::extra_settings::schema::DayType;
+use crate::models::Day;
+use crate::utils::archive::Archive;
+use crate::utils::holidays;
+use crate::{get_client, get_items, get_since_date, Items};
+use anyhow::Error;
+use chrono::{Duration, NaiveDate, Utc};
+use std::path::Path;
+
+/// All-day event of consecutive days with the same type and title
+struct Event {
+    start: NaiveDate,
+    /// Inclusive
+    end: NaiveDate,
+    type_: DayType,
+    summary: String,
+}
+
+impl Event {
+    fn new(day: &Day) -> Option<Self> {
+        let type_ = day.type_();
+        let summary = match &type_ {
+            DayType::PublicHoliday => day.title().unwrap_or("Public holiday").to_owned(),
+            DayType::Vacation => "Vacation".into(),
+            DayType::Flex => "Flex day".into(),
+            DayType::SickLeave => "Sick leave".into(),
+            DayType::ParentalLeave => "Parental leave".into(),
+            DayType::UnpaidLeave => "Unpaid leave".into(),
+            DayType::Custom(name) => name.clone(),
+            DayType::WorkingDay | DayType::Unknown => return None,
+        };
+        Some(Self {
+            start: day.date(),
+            end: day.date(),
+            type_,
+            summary,
+        })
+    }
+
+    /// Stable between exports, so that subscribed calendars update the events
+    fn uid(&self) -> String {
+        format!(
+            "{:?}-{}-{}@clockify-flex-rust",
+            self.type_,
+            self.start.format("%Y%m%d"),
+            self.end.format("%Y%m%d")
+        )
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Merge the days into events, days must be sorted by date
+fn events(days: Vec<Day>) -> Vec<Event> {
+    let mut events: Vec<Event> = Vec::new();
+    for event in days.iter().filter_map(Event::new) {
+        match events.last_mut() {
+            Some(last)
+                if last.type_ == event.type_
+                    && last.summary == event.summary
+                    && last.end + Duration::days(1) == event.start =>
+            {
+                last.end = event.end;
+            }
+            _ => events.push(event),
+        }
+    }
+    events
+}
+
+/// iCalendar document of the events
+fn build_ics(events: &[Event]) -> String {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".into(),
+        "PRODID:-//clockify-flex-rust//EN".into(),
+        "CALSCALE:GREGORIAN".into(),
+        "X-WR-CALNAME:Clockify days off".into(),
+    ];
+    for event in events {
+        lines.extend([
+            "BEGIN:VEVENT".to_owned(),
+            format!("UID:{}", event.uid()),
+            format!("DTSTAMP:{stamp}"),
+            format!("DTSTART;VALUE=DATE:{}", event.start.format("%Y%m%d")),
+            // The end of an all-day event is exclusive
+            format!(
+                "DTEND;VALUE=DATE:{}",
+                (event.end + Duration::days(1)).format("%Y%m%d")
+            ),
+            format!("SUMMARY:{}", escape(&event.summary)),
+            "TRANSP:TRANSPARENT".into(),
+            "END:VEVENT".into(),
+        ]);
+    }
+    lines.push("END:VCALENDAR".into());
+    lines.iter().map(|line| format!("{line}\r\n")).collect()
+}
+
+async fn export_ics(token: &Token, path: &Path) -> Result<(), Error> {
+    let args = get_settings().await;
+    let since_date = get_since_date(&args, token).await?;
+    let (client, user_settings) = get_client(token).await?;
+    let Items {
+        public_holidays,
+        days_off,
+        ..
+    } = get_items(client, since_date, &user_settings).await?;
+
+    let mut days = public_holidays
+        .into_iter()
+        .chain(days_off)
+        .filter(|d| !user_settings.is_ignored(d))
+        .collect::<Vec<Day>>();
+    days.sort_by_key(Day::date);
+    let events = events(days);
+    std::fs::write(path, build_ics(&events))?;
+    println!("{} events exported to {}", events.len(), path.display());
+    Ok(())
+}
+
+/// Archive of the data as fetched, without the extra settings applied
+async fn export_raw(token: &Token, dir: &Path) -> Result<(), Error> {
+    let since = get_since_date(&*get_settings().await, token).await?;
+    let (client, _) = get_client(token).await?;
+    let entries = client.get_work_items_since(&since).await?;
+    let time_off_items = client
+        .get_time_off_items(&TimeOffQuery::new().statuses(&["APPROVED", "PENDING"]))
+        .await?;
+    let holidays = holidays::load()?
+        .holidays
+        .into_iter()
+        .filter(|h| h.date >= since)
+        .collect();
+    let archive = Archive {
+        since,
+        entries,
+        time_off_items,
+        holidays,
+        exported_at: Utc::now(),
+    };
+    archive.save(dir)?;
+    println!(
+        "{} time entries, {} time off requests and {} public holidays exported to {}",
+        archive.entries.len(),
+        archive.time_off_items.len(),
+        archive.holidays.len(),
+        dir.display()
+    );
+    Ok(())
+}
+
+pub(crate) async fn run(token: &Token, action: &ExportAction) -> Result<(), Error> {
+    match action {
+        ExportAction::Ics { output } => export_ics(token, output).await,
+        ExportAction::Raw { out } => export_raw(token, out).await,
+    }
+}
@@ -0,0 +1,195 @@
+use crate::args::get_settings;
+use crate::clockify::{get_pending_days_off, ClockifyClient, Token};
+use crate::extra_settings::rules::DayEffect;
+use crate::models::Day;
+use crate::utils::resolution::resolve_pending;
+use crate::utils::table::format_duration;
+use crate::{get_context, get_items, get_since_date, utils};
+use crate::{BalanceCalculator, CalculationContext, Items, Results};
+use anyhow::Error;
+use chrono::{Duration, NaiveDate};
+use std::collections::{HashMap, HashSet};
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+pub(crate) struct Forecast {
+    pub until: NaiveDate,
+    /// Weekdays assumed to be worked as expected
+    pub working_days: Vec<NaiveDate>,
+    /// Booked flex days (and unpaid leave when it's expected) with their expected working seconds
+    pub flex_days: Vec<(NaiveDate, i64)>,
+    pub flex_sec: i64,
+    /// Vacations, parental and sick leaves
+    pub absence_day_count: usize,
+    pub public_holiday_count: usize,
+    pub balance: i64,
+}
+
+impl Forecast {
+    /// Forecast balance at the end of the given date
+    pub(crate) fn balance_on(&self, date: NaiveDate) -> i64 {
+        let later_flex_sec = self
+            .flex_days
+            .iter()
+            .filter(|(flex_date, _)| *flex_date > date)
+            .map(|(_, secs)| secs)
+            .sum::<i64>();
+        self.balance + later_flex_sec
+    }
+
+    /// Normal working days left by the end of the given date
+    pub(crate) fn working_days_by(&self, date: NaiveDate) -> usize {
+        self.working_days.iter().filter(|d| **d <= date).count()
+    }
+}
+
+/// Project the balance forward to `until`, assuming normal weekdays are worked as expected.
+/// Only the booked days off that are expected working days, e.g. flex days, change the
+/// balance.
+pub(crate) fn forecast(
+    balance: i64,
+    public_holidays: &[Day],
+    days_off: &[Day],
+    context: &CalculationContext,
+    include_today: bool,
+    until: NaiveDate,
+) -> Forecast {
+    let user_settings = &context.settings;
+    let holidays = public_holidays
+        .iter()
+        .filter(|d| !user_settings.is_ignored(d))
+        .map(Day::date)
+        .collect::<HashSet<NaiveDate>>();
+    let days_off = days_off
+        .iter()
+        .filter(|d| !user_settings.is_ignored(d))
+        .map(|d| (d.date(), user_settings.day_effect(d)))
+        .collect::<HashMap<NaiveDate, DayEffect>>();
+
+    let mut forecast = Forecast {
+        until,
+        working_days: Vec::new(),
+        flex_days: Vec::new(),
+        flex_sec: 0,
+        absence_day_count: 0,
+        public_holiday_count: 0,
+        balance,
+    };
+
+    // Today is already in the balance when it's included
+    let start = if include_today {
+        context.today + Duration::days(1)
+    } else {
+        context.today
+    };
+    for date in utils::DateRange(start, until).filter(utils::is_weekday) {
+        if holidays.contains(&date) {
+            forecast.public_holiday_count += 1;
+            continue;
+        }
+        match days_off.get(&date) {
+            // Expected days off, e.g. flex time off, reduce the balance
+            Some(DayEffect::Expected) => {
+                let secs = context.expected_day_secs(&date);
+                forecast.flex_days.push((date, secs));
+                forecast.flex_sec += secs;
+            }
+            Some(_) => forecast.absence_day_count += 1,
+            None => forecast.working_days.push(date),
+        }
+    }
+    forecast.balance -= forecast.flex_sec;
+    forecast
+}
+
+/// Fetch the items and calculate the current results together with the forecast
+pub(crate) async fn get_forecast(
+    client: ClockifyClient,
+    context: &CalculationContext,
+    since_date: NaiveDate,
+    until: NaiveDate,
+) -> Result<(Results, Forecast), Error> {
+    let args = get_settings().await;
+    let user_settings = &context.settings;
+    let pending_days_off = if args.include_pending {
+        get_pending_days_off(client.clone(), &since_date, user_settings).await?
+    } else {
+        Vec::new()
+    };
+    let Items {
+        public_holidays,
+        working_days,
+        days_off,
+        ..
+    } = get_items(client, since_date, user_settings).await?;
+
+    // Future days are filtered out in the calculations, so forecast from copies
+    let future_holidays = public_holidays
+        .iter()
+        .filter(|d| d.date() >= context.today)
+        .cloned()
+        .collect::<Vec<Day>>();
+    // Pending requests are only counted in the forecast, on the dates without booked days
+    let pending_days_off =
+        resolve_pending(pending_days_off, &public_holidays, &days_off, user_settings);
+    let future_days_off = days_off
+        .iter()
+        .chain(&pending_days_off)
+        .filter(|d| d.date() >= context.today)
+        .cloned()
+        .collect::<Vec<Day>>();
+
+    let results = BalanceCalculator::new(context.clone())
+        .include_today(args.include_today)
+        .start_balance(args.start_balance.unwrap_or(0))
+        .calculate(public_holidays, working_days, days_off)?;
+    let forecast = forecast(
+        results.balance,
+        &future_holidays,
+        &future_days_off,
+        context,
+        args.include_today,
+        until,
+    );
+    Ok((results, forecast))
+}
+
+pub(crate) async fn run(token: &Token, until: NaiveDate) -> Result<(), Error> {
+    let since_date = get_since_date(&*get_settings().await, token).await?;
+    let (client, context) = get_context(token).await?;
+    let (results, forecast) = get_forecast(client, &context, since_date, until).await?;
+
+    let mut builder = Builder::default();
+    builder.push_record(["Item", "Days", "Duration"]);
+    builder.push_record(["Current balance", "", &format_duration(results.balance)]);
+    builder.push_record([
+        "Normal working days",
+        &forecast.working_days.len().to_string(),
+        "",
+    ]);
+    builder.push_record([
+        "Public holidays (on weekdays)",
+        &forecast.public_holiday_count.to_string(),
+        "",
+    ]);
+    builder.push_record([
+        "Booked vacations and leaves",
+        &forecast.absence_day_count.to_string(),
+        "",
+    ]);
+    builder.push_record([
+        "Booked flex time off",
+        &forecast.flex_days.len().to_string(),
+        &format_duration(-forecast.flex_sec),
+    ]);
+    builder.push_record([
+        &format!("Forecast balance on {}", forecast.until),
+        "",
+        &format_duration(forecast.balance),
+    ]);
+
+    let mut table = builder.build();
+    table.with(Style::modern_rounded());
+    println!("{table}");
+    Ok(())
+}
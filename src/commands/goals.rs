@@ -0,0 +1,106 @@
+use crate::args::{get_settings, GoalsAction};
+use crate::clockify::Token;
+use crate::commands::forecast::get_forecast;
+use crate::extra_settings::schema::{BalanceGoal, ExtraSettings};
+use crate::extra_settings::GlobalSettings;
+use crate::utils::table::format_duration;
+use crate::{get_context, get_since_date, new_client, utils};
+use anyhow::Error;
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+/// Share of the target reached by the balance, in percent
+fn progress(balance: i64, target_sec: i64) -> f32 {
+    if target_sec <= 0 {
+        return if balance >= target_sec { 100.0 } else { 0.0 };
+    }
+    (balance.max(0) as f32 / target_sec as f32 * 100.0).min(100.0)
+}
+
+async fn show(token: &Token) -> Result<(), Error> {
+    let since_date = get_since_date(&*get_settings().await, token).await?;
+    let (client, context) = get_context(token).await?;
+    let goals = context.settings.goals();
+    let Some(last_until) = goals.iter().map(|g| g.until).max() else {
+        println!("No goals in the extra settings, add one with `goals add`.");
+        return Ok(());
+    };
+
+    // One forecast to the last goal covers the earlier ones too
+    let until = last_until.max(utils::today());
+    let (results, forecast) = get_forecast(client, &context, since_date, until).await?;
+
+    let mut builder = Builder::default();
+    builder.push_record([
+        "Goal",
+        "Target",
+        "By",
+        "Progress",
+        "Forecast",
+        "Required daily surplus",
+    ]);
+    for goal in goals {
+        let target_sec = (goal.hours * 3600.0) as i64;
+        let forecast_balance = forecast.balance_on(goal.until);
+        let working_day_count = forecast.working_days_by(goal.until);
+        let missing_sec = target_sec - forecast_balance;
+        let required = if missing_sec <= 0 {
+            "On track".to_string()
+        } else if working_day_count == 0 {
+            "Not reachable".to_string()
+        } else {
+            format_duration(missing_sec / working_day_count as i64)
+        };
+        let forecast_str = if goal.until < utils::today() {
+            "Passed".to_string()
+        } else {
+            format_duration(forecast_balance)
+        };
+        builder.push_record([
+            goal.name.clone(),
+            format_duration(target_sec),
+            goal.until.to_string(),
+            format!("{:.0} %", progress(results.balance, target_sec)),
+            forecast_str,
+            required,
+        ]);
+    }
+    let mut table = builder.build();
+    table.with(Style::modern_rounded());
+    println!("Current balance: {}", format_duration(results.balance));
+    println!("{table}");
+    Ok(())
+}
+
+pub(crate) async fn run(token: &Token, action: &Option<GoalsAction>) -> Result<(), Error> {
+    let action = match action {
+        None | Some(GoalsAction::Show) => return show(token).await,
+        Some(action) => action,
+    };
+
+    let client = new_client(token).await?;
+    let mut global_settings = GlobalSettings::create_settings().await?;
+    let mut user_settings = global_settings
+        .get_user_settings(&client.user.email)
+        .unwrap_or(ExtraSettings::new(&client.user.email));
+
+    match action {
+        GoalsAction::Add { name, hours, until } => {
+            user_settings.set_goal(BalanceGoal {
+                name: name.clone(),
+                hours: *hours,
+                until: *until,
+            });
+            println!("Goal \"{name}\" saved to the settings.");
+        }
+        GoalsAction::Remove { name } => {
+            if !user_settings.remove_goal(name) {
+                return Err(Error::msg(format!("No goal named \"{name}\"!")));
+            }
+            println!("Goal \"{name}\" removed from the settings.");
+        }
+        GoalsAction::Show => unreachable!(),
+    }
+    global_settings.set_user_settings(user_settings);
+    global_settings.save().await
+}
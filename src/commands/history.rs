@@ -0,0 +1,55 @@
+use crate::clockify::Token;
+use crate::utils;
+use crate::utils::cache::get_snapshots;
+use crate::utils::table::format_duration;
+use anyhow::Error;
+use chrono::Months;
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+/// Balance snapshots of the past runs with the change between them
+pub(crate) fn run(token: &Token, month_count: Option<u32>) -> Result<(), Error> {
+    let mut snapshots = get_snapshots(token)?;
+    let since = month_count.and_then(|count| utils::today().checked_sub_months(Months::new(count)));
+    if let Some(since) = since {
+        snapshots.retain(|s| s.date >= since);
+    }
+    let (Some(first), Some(last)) = (snapshots.first(), snapshots.last()) else {
+        println!("No snapshots yet. A snapshot is saved on every run without a start date.");
+        return Ok(());
+    };
+
+    let mut builder = Builder::default();
+    builder.push_record(["Date", "Balance", "Change", "Worked", "Expected"]);
+    let mut previous_balance = None;
+    for snapshot in &snapshots {
+        let change = previous_balance
+            .map(|previous| format_duration(snapshot.balance - previous))
+            .unwrap_or_default();
+        builder.push_record([
+            snapshot.date.to_string(),
+            format_duration(snapshot.balance),
+            change,
+            format_duration(snapshot.worked),
+            format_duration(snapshot.expected),
+        ]);
+        previous_balance = Some(snapshot.balance);
+    }
+
+    let mut table = builder.build();
+    table.with(Style::modern_rounded());
+    println!("{table}");
+
+    let days = (last.date - first.date).num_days();
+    if days > 0 {
+        let change = last.balance - first.balance;
+        let trend = if change < 0 { "shrunk" } else { "grown" };
+        println!(
+            "The balance has {trend} by {} since {}, {} per 30 days.",
+            format_duration(change.abs()),
+            first.date,
+            format_duration(change * 30 / days)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,18 @@
+use crate::args::HolidaysAction;
+use crate::utils::holidays;
+use anyhow::Error;
+
+pub(crate) async fn run(action: &HolidaysAction) -> Result<(), Error> {
+    match action {
+        HolidaysAction::Update => {
+            let dataset = holidays::update().await?;
+            println!(
+                "Holidays dataset {} with {} holidays saved to {}",
+                dataset.version,
+                dataset.holidays.len(),
+                holidays::dataset_path().display()
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use crate::args::get_settings;
+use crate::clockify::{ClockifyClient, Token, WorkspaceMember};
+use crate::commands::members;
+use crate::commands::monthly::breakdown;
+use crate::extra_settings::schema::DayType;
+use crate::extra_settings::GlobalSettings;
+use crate::{calculation_context, get_items, new_client, utils, CalculationContext, Items};
+use anyhow::Error;
+use chrono::{Datelike, NaiveDate};
+use rust_xlsxwriter::Workbook;
+use std::path::Path;
+
+/// Absence columns of the report, in order
+const ABSENCE_TYPES: [(DayType, &str); 5] = [
+    (DayType::Vacation, "Vacation days"),
+    (DayType::SickLeave, "Sick leave days"),
+    (DayType::ParentalLeave, "Parental leave days"),
+    (DayType::Flex, "Flex days"),
+    (DayType::UnpaidLeave, "Unpaid leave days"),
+];
+
+/// Worked and expected hours with the absence days of one member in one month
+struct Row {
+    name: String,
+    email: String,
+    month: String,
+    worked_hours: f64,
+    expected_hours: f64,
+    absence_days: [usize; ABSENCE_TYPES.len()],
+}
+
+fn header() -> Vec<&'static str> {
+    let mut header = vec![
+        "Name",
+        "Email",
+        "Month",
+        "Worked (h)",
+        "Expected (h)",
+        "Balance (h)",
+    ];
+    header.extend(ABSENCE_TYPES.iter().map(|(_, title)| *title));
+    header
+}
+
+async fn member_rows(
+    client: ClockifyClient,
+    member: &WorkspaceMember,
+    context: &CalculationContext,
+    year: i32,
+    since: NaiveDate,
+) -> Result<Vec<Row>, Error> {
+    let Items {
+        public_holidays,
+        working_days,
+        days_off,
+        ..
+    } = get_items(client, since, &context.settings).await?;
+    let user_settings = &context.settings;
+
+    let mut absences = [[0; ABSENCE_TYPES.len()]; 12];
+    for day in days_off.iter().filter(|d| {
+        d.date().year() == year
+            && d.date() <= utils::today()
+            && utils::is_weekday(&d.date())
+            && !user_settings.is_ignored(d)
+    }) {
+        if let Some(i) = ABSENCE_TYPES.iter().position(|(t, _)| *t == day.type_()) {
+            absences[day.date().month0() as usize][i] += 1;
+        }
+    }
+
+    let months = breakdown(year, public_holidays, working_days, days_off, context);
+    Ok(months
+        .into_iter()
+        .map(|(month, totals)| Row {
+            name: member.user.name.clone(),
+            email: member.user.email.clone(),
+            month: format!("{year}-{month:02}"),
+            worked_hours: totals.worked_sec as f64 / 3600.0,
+            expected_hours: totals.expected_sec as f64 / 3600.0,
+            absence_days: absences[month as usize - 1],
+        })
+        .collect())
+}
+
+fn write_csv(path: &Path, rows: &[Row]) -> Result<(), Error> {
+    let records = rows.iter().map(|row| {
+        let mut record = vec![
+            row.name.clone(),
+            row.email.clone(),
+            row.month.clone(),
+            format!("{:.2}", row.worked_hours),
+            format!("{:.2}", row.expected_hours),
+            format!("{:.2}", row.worked_hours - row.expected_hours),
+        ];
+        record.extend(row.absence_days.iter().map(|days| days.to_string()));
+        record
+    });
+    members::write_csv(path, &header(), records)
+}
+
+fn write_xlsx(path: &Path, rows: &[Row]) -> Result<(), Error> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet.write_row(0, 0, header())?;
+    for (i, row) in rows.iter().enumerate() {
+        let r = i as u32 + 1;
+        worksheet.write_string(r, 0, &row.name)?;
+        worksheet.write_string(r, 1, &row.email)?;
+        worksheet.write_string(r, 2, &row.month)?;
+        worksheet.write_number(r, 3, row.worked_hours)?;
+        worksheet.write_number(r, 4, row.expected_hours)?;
+        worksheet.write_number(r, 5, row.worked_hours - row.expected_hours)?;
+        for (col, days) in row.absence_days.iter().enumerate() {
+            worksheet.write_number(r, 6 + col as u16, *days as f64)?;
+        }
+    }
+    worksheet.autofit();
+    workbook.save(path)?;
+    Ok(())
+}
+
+/// Worked, expected and absence days per member and month of the year for the whole workspace
+pub(crate) async fn run(
+    token: &Token,
+    year: Option<i32>,
+    emails: &[String],
+    output: &Path,
+) -> Result<(), Error> {
+    let write = match output.extension().and_then(|e| e.to_str()) {
+        Some("csv") => write_csv,
+        Some("xlsx") => write_xlsx,
+        _ => return Err(Error::msg("Output must be a .csv or .xlsx file!")),
+    };
+    let year = year.unwrap_or(utils::today().year());
+    let since = NaiveDate::from_ymd_opt(year, 1, 1)
+        .ok_or_else(|| Error::msg(format!("Invalid year {year}")))?;
+    if since > utils::today() {
+        return Err(Error::msg("Year cannot be in the future!"));
+    }
+
+    let extra_settings = GlobalSettings::create_settings().await?;
+    let client = new_client(token).await?;
+    let members = members::select(&client, emails).await?;
+
+    let mut rows = Vec::new();
+    for member in &members {
+        println!("Fetching the data of {}...", member.user.name);
+        let user_settings = members::user_settings(&extra_settings, &member.user.email).await;
+        let context = calculation_context(&*get_settings().await, user_settings);
+        match member_rows(client.for_user(&member.user), member, &context, year, since).await {
+            Ok(member_rows) => rows.extend(member_rows),
+            Err(e) => println!("Skipped {}: {e}", member.user.email),
+        }
+    }
+
+    write(output, &rows)?;
+    println!(
+        "Report of {} members saved to {}",
+        members.len(),
+        output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use crate::utils::archive::{self, Archive};
+use anyhow::Error;
+use chrono::Local;
+use std::path::Path;
+
+/// Copy the archive to the cache directory after checking that it can be read
+pub(crate) fn run(dir: &Path) -> Result<(), Error> {
+    let archive = Archive::import(dir)?;
+    println!(
+        "{} time entries, {} time off requests and {} public holidays since {} imported to {}",
+        archive.entries.len(),
+        archive.time_off_items.len(),
+        archive.holidays.len(),
+        archive.since,
+        archive::imported_dir().display()
+    );
+    println!(
+        "Exported at {}, calculate from it with --offline.",
+        archive
+            .exported_at
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M")
+    );
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use crate::clockify::{TimesheetQuery, Token};
+use crate::new_client;
+use crate::utils::{self, table::format_duration};
+use anyhow::Error;
+use chrono::{Local, NaiveDate, NaiveTime, TimeDelta, TimeZone, Utc};
+
+/// Create a time entry of the given hours on the date, e.g. for a day flagged by the audit
+pub(crate) async fn run(
+    token: &Token,
+    date: NaiveDate,
+    hours: f32,
+    start: NaiveTime,
+    project: Option<&str>,
+    description: &str,
+    force: bool,
+) -> Result<(), Error> {
+    if date > utils::today() {
+        return Err(Error::msg("Date cannot be in the future!"));
+    }
+    if hours <= 0.0 || hours > 24.0 {
+        return Err(Error::msg("Hours must be between 0 and 24!"));
+    }
+
+    let client = new_client(token).await?;
+    let logged_sec = client
+        .get_time_entries(&TimesheetQuery::since(date).until(date))
+        .await?
+        .iter()
+        .map(|entry| (entry.end - entry.start).num_seconds())
+        .sum::<i64>();
+    if logged_sec > 0 && !force {
+        return Err(Error::msg(format!(
+            "{date} already has {} logged! Use --force to add the entry anyway.",
+            format_duration(logged_sec)
+        )));
+    }
+
+    let project_id = match project {
+        Some(name) => Some(client.find_project(name).await?.id),
+        None => None,
+    };
+    let start = Local
+        .from_local_datetime(&date.and_time(start))
+        .earliest()
+        .ok_or_else(|| Error::msg(format!("Invalid local time on {date}")))?
+        .with_timezone(&Utc);
+    let end = start + TimeDelta::seconds((hours * 3600.0) as i64);
+    let id = client
+        .create_time_entry(description, project_id.as_deref(), start, Some(end))
+        .await?;
+    println!(
+        "Logged {} on {date} from {} to {}. Undo with `undo {id}`.",
+        format_duration((end - start).num_seconds()),
+        start.with_timezone(&Local).format("%H:%M"),
+        end.with_timezone(&Local).format("%H:%M")
+    );
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use crate::args::get_settings;
+use crate::clockify::{ClockifyClient, WorkspaceMember};
+use crate::extra_settings::schema::ExtraSettings;
+use crate::extra_settings::GlobalSettings;
+use crate::{first_entry_date, get_results, Results};
+use anyhow::Error;
+use std::path::Path;
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+/// Workspace members with the emails, all of them without emails. Emails without an active
+/// member are warned about.
+pub(crate) async fn select(
+    client: &ClockifyClient,
+    emails: &[String],
+) -> Result<Vec<WorkspaceMember>, Error> {
+    let members = client
+        .get_workspace_members()
+        .await?
+        .into_iter()
+        .filter(|m| emails.is_empty() || emails.contains(&m.user.email))
+        .collect::<Vec<WorkspaceMember>>();
+    for email in emails {
+        if !members.iter().any(|m| &m.user.email == email) {
+            log::warn!("No active workspace member with the email {email}");
+        }
+    }
+    Ok(members)
+}
+
+/// Extra settings of the email with the exclusions of the arguments
+pub(crate) async fn user_settings(extra_settings: &GlobalSettings, email: &str) -> ExtraSettings {
+    let args = get_settings().await;
+    extra_settings
+        .get_user_settings(email)
+        .unwrap_or(ExtraSettings::empty())
+        .with_exclusions(&args.exclude_projects, &args.exclude_tags)
+}
+
+/// Results of the user of the client with the extra settings of the user, calculated the same
+/// way as the balance of the own token. A failure is warned about.
+pub(crate) async fn results(
+    client: ClockifyClient,
+    extra_settings: &GlobalSettings,
+) -> Result<Results, Error> {
+    let email = client.user.email.clone();
+    let user_settings = user_settings(extra_settings, &email).await;
+    let args = get_settings().await;
+    let since = match args.start_date {
+        Some(date) => date,
+        None => first_entry_date(&client).await,
+    };
+    let results = get_results(client, since, args.include_today, 0, user_settings).await;
+    if let Err(e) = &results {
+        log::warn!("Failed to calculate balance of {email}: {e}");
+    }
+    results
+}
+
+/// Write the header and the records to a CSV file
+pub(crate) fn write_csv<R>(
+    path: &Path,
+    header: &[&str],
+    records: impl IntoIterator<Item = R>,
+) -> Result<(), Error>
+where
+    R: IntoIterator,
+    R::Item: AsRef<[u8]>,
+{
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(header)?;
+    for record in records {
+        writer.write_record(record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+pub(crate) fn print_table<R>(header: &[&str], rows: impl IntoIterator<Item = R>)
+where
+    R: IntoIterator,
+    R::Item: Into<String>,
+{
+    let mut builder = Builder::default();
+    builder.push_record(header.iter().copied());
+    for row in rows {
+        builder.push_record(row);
+    }
+    let mut table = builder.build();
+    table.with(Style::modern_rounded());
+    println!("{table}");
+}
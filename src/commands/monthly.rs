@@ -0,0 +1,125 @@
+use crate::clockify::Token;
+use crate::models::{Day, WorkDay};
+use crate::utils::table::format_duration;
+use crate::{get_context, get_items, utils, CalculationContext, Items};
+use anyhow::Error;
+use chrono::{Datelike, NaiveDate};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+/// Share of the expected time a weekday needs to be logged to count as complete, same as
+/// on target in the calendar
+const SUFFICIENT_SHARE: f32 = 0.95;
+
+#[derive(Default)]
+pub(crate) struct Month {
+    pub worked_sec: i64,
+    pub expected_sec: i64,
+    pub weekday_count: usize,
+    pub complete_day_count: usize,
+}
+
+impl Month {
+    /// Weekdays with sufficient logged time or a recognized absence of all weekdays
+    fn completeness(&self) -> f32 {
+        if self.weekday_count == 0 {
+            return 100.0;
+        }
+        self.complete_day_count as f32 / self.weekday_count as f32 * 100.0
+    }
+}
+
+/// Worked and expected time with the timesheet completeness per month of the year, until today
+pub(crate) fn breakdown(
+    year: i32,
+    public_holidays: Vec<Day>,
+    working_days: Vec<WorkDay>,
+    days_off: Vec<Day>,
+    context: &CalculationContext,
+) -> BTreeMap<u32, Month> {
+    let user_settings = &context.settings;
+    let working_days = working_days
+        .into_iter()
+        .filter(|wd| !user_settings.is_ignored_work_day(wd))
+        .collect::<Vec<WorkDay>>();
+    let Some(first_working_day) = working_days.iter().map(|wd| wd.date).min() else {
+        return BTreeMap::new();
+    };
+
+    let mut worked = HashMap::new();
+    for wd in &working_days {
+        *worked.entry(wd.date).or_insert(0) += wd.duration();
+    }
+    let recognized = public_holidays
+        .into_iter()
+        .chain(days_off)
+        .filter(|d| !user_settings.is_ignored(d))
+        .map(Day::into_date)
+        .collect::<HashSet<NaiveDate>>();
+
+    let start = NaiveDate::from_ymd_opt(year, 1, 1)
+        .unwrap()
+        .max(first_working_day);
+    let end = NaiveDate::from_ymd_opt(year, 12, 31)
+        .unwrap()
+        .min(context.today);
+
+    let mut months: BTreeMap<u32, Month> = BTreeMap::new();
+    for date in utils::DateRange(start, end) {
+        let month = months.entry(date.month()).or_default();
+        let worked_sec = worked.get(&date).copied().unwrap_or(0);
+        month.worked_sec += worked_sec;
+        if !utils::is_weekday(&date) {
+            continue;
+        }
+
+        month.weekday_count += 1;
+        if recognized.contains(&date) {
+            month.complete_day_count += 1;
+            continue;
+        }
+        let expected_sec = context.expected_day_secs(&date);
+        month.expected_sec += expected_sec;
+        if worked_sec as f32 >= expected_sec as f32 * SUFFICIENT_SHARE {
+            month.complete_day_count += 1;
+        }
+    }
+    months
+}
+
+pub(crate) async fn run(token: &Token, year: Option<i32>) -> Result<(), Error> {
+    let year = year.unwrap_or(utils::today().year());
+    let since = NaiveDate::from_ymd_opt(year, 1, 1)
+        .ok_or_else(|| Error::msg(format!("Invalid year {year}")))?;
+    if since > utils::today() {
+        return Err(Error::msg("Year cannot be in the future!"));
+    }
+
+    let (client, context) = get_context(token).await?;
+    let Items {
+        public_holidays,
+        working_days,
+        days_off,
+        ..
+    } = get_items(client, since, &context.settings).await?;
+    let months = breakdown(year, public_holidays, working_days, days_off, &context);
+
+    let mut builder = Builder::default();
+    builder.push_record(["Month", "Worked", "Expected", "Balance", "Complete"]);
+    for (month, totals) in months {
+        let first_day = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+        builder.push_record([
+            first_day.format("%Y-%m").to_string(),
+            format_duration(totals.worked_sec),
+            format_duration(totals.expected_sec),
+            format_duration(totals.worked_sec - totals.expected_sec),
+            format!("{:.0} %", totals.completeness()),
+        ]);
+    }
+
+    let mut table = builder.build();
+    table.with(Style::modern_rounded());
+    println!("{table}");
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use crate::args::get_settings;
+use crate::clockify::Token;
+use crate::commands::forecast::get_forecast;
+use crate::extra_settings::schema::PayoutRule;
+use crate::utils::table::format_duration;
+use crate::{get_context, get_since_date, utils};
+use anyhow::Error;
+use chrono::NaiveDate;
+use itertools::Itertools;
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+struct PayoutTier {
+    above_sec: i64,
+    multiplier: f32,
+    paid_sec: i64,
+}
+
+struct Payout {
+    carried_over_sec: i64,
+    tiers: Vec<PayoutTier>,
+}
+
+impl Payout {
+    /// Split the balance into carried over and paid out hours. Each rule pays the hours
+    /// between its threshold and the next rule's threshold with its multiplier.
+    fn new(balance: i64, rules: &[PayoutRule]) -> Self {
+        let rules = rules
+            .iter()
+            .sorted_by(|a, b| a.above_hours.total_cmp(&b.above_hours))
+            .collect_vec();
+        let thresholds = rules
+            .iter()
+            .map(|r| (r.above_hours * 3600.0) as i64)
+            .collect_vec();
+
+        let tiers = rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                let above_sec = thresholds[i];
+                let upper = thresholds.get(i + 1).copied().unwrap_or(i64::MAX);
+                let paid_sec = balance.clamp(above_sec, upper) - above_sec;
+                PayoutTier {
+                    above_sec,
+                    multiplier: rule.multiplier,
+                    paid_sec,
+                }
+            })
+            .collect_vec();
+        let carried_over_sec = balance.min(thresholds.first().copied().unwrap_or(balance));
+
+        Self {
+            carried_over_sec,
+            tiers,
+        }
+    }
+
+    fn paid_sec(&self) -> i64 {
+        self.tiers.iter().map(|t| t.paid_sec).sum()
+    }
+
+    fn weighted_paid_sec(&self) -> i64 {
+        self.tiers
+            .iter()
+            .map(|t| (t.paid_sec as f32 * t.multiplier) as i64)
+            .sum()
+    }
+}
+
+pub(crate) async fn run(
+    token: &Token,
+    cutoff: NaiveDate,
+    rule: Option<PayoutRule>,
+) -> Result<(), Error> {
+    if cutoff < utils::today() {
+        return Err(Error::msg("Cutoff date cannot be in the past!"));
+    }
+
+    let since_date = get_since_date(&*get_settings().await, token).await?;
+    let (client, context) = get_context(token).await?;
+    let rules = match rule {
+        Some(rule) => vec![rule],
+        None => context.settings.payout_rules().to_vec(),
+    };
+    if rules.is_empty() {
+        return Err(Error::msg(
+            "No payout rules! Add payoutRules to the extra settings or use --above and --multiplier.",
+        ));
+    }
+
+    let (_, forecast) = get_forecast(client, &context, since_date, cutoff).await?;
+    let payout = Payout::new(forecast.balance, &rules);
+
+    let mut builder = Builder::default();
+    builder.push_record(["Item", "Duration"]);
+    builder.push_record([
+        format!("Balance on {cutoff}"),
+        format_duration(forecast.balance),
+    ]);
+    for tier in &payout.tiers {
+        let (hours, minutes) = utils::seconds_to_hours_and_minutes(tier.above_sec);
+        builder.push_record([
+            format!("Paid at {}x (above {hours}:{minutes:02})", tier.multiplier),
+            format_duration(tier.paid_sec),
+        ]);
+    }
+    builder.push_record(["Paid out".into(), format_duration(payout.paid_sec())]);
+    builder.push_record([
+        "Paid out with multipliers".into(),
+        format_duration(payout.weighted_paid_sec()),
+    ]);
+    builder.push_record([
+        "Carried over".into(),
+        format_duration(payout.carried_over_sec),
+    ]);
+
+    let mut table = builder.build();
+    table.with(Style::modern_rounded());
+    println!("{table}");
+    Ok(())
+}
@@ -0,0 +1,146 @@
+use crate::args::{get_settings, PlanStrategy};
+use crate::clockify::Token;
+use crate::commands::forecast::get_forecast;
+use crate::utils::table::format_duration;
+use crate::{get_context, get_since_date};
+use anyhow::Error;
+use chrono::{Datelike, Duration, NaiveDate};
+use itertools::Itertools;
+use std::collections::BTreeMap;
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+/// Used when the max daily hours are not set in the arguments or settings
+const DEFAULT_MAX_DAILY_HOURS: f32 = 10.0;
+/// Extra time is planned in steps of five minutes
+const STEP_SEC: i64 = 300;
+
+#[derive(Default)]
+struct WeekPlan {
+    working_day_count: usize,
+    extra_days: Vec<i64>,
+    flex_sec: i64,
+}
+
+/// Spread the deficit over the days, filling the days with the least capacity first so
+/// that the rest goes evenly to the others.
+fn distribute(deficit: i64, capacities: &[i64]) -> Vec<i64> {
+    let mut extra = vec![0; capacities.len()];
+    let mut remaining = deficit;
+    let mut days_left = capacities.len() as i64;
+    for i in (0..capacities.len()).sorted_by_key(|i| capacities[*i]) {
+        let share = (remaining + days_left - 1) / days_left;
+        let share = (share + STEP_SEC - 1) / STEP_SEC * STEP_SEC;
+        extra[i] = share.min(capacities[i]).min(remaining).max(0);
+        remaining -= extra[i];
+        days_left -= 1;
+    }
+    extra
+}
+
+fn week_start(date: &NaiveDate) -> NaiveDate {
+    *date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+pub(crate) async fn run(
+    token: &Token,
+    until: NaiveDate,
+    strategy: &PlanStrategy,
+    long_days_per_week: usize,
+    max_daily_hours: Option<f32>,
+) -> Result<(), Error> {
+    let since_date = get_since_date(&*get_settings().await, token).await?;
+    let (client, context) = get_context(token).await?;
+    let (results, forecast) = get_forecast(client, &context, since_date, until).await?;
+
+    if forecast.balance >= 0 {
+        println!(
+            "Balance on {until} will be {}, no need to recover.",
+            format_duration(forecast.balance)
+        );
+        return Ok(());
+    }
+
+    let max_daily_hours = max_daily_hours
+        .or(context.settings.max_daily_hours())
+        .unwrap_or(DEFAULT_MAX_DAILY_HOURS);
+    let max_daily_sec = (max_daily_hours * 3600.0) as i64;
+
+    let candidates = match strategy {
+        PlanStrategy::Even => forecast.working_days.clone(),
+        PlanStrategy::LongDays => forecast
+            .working_days
+            .iter()
+            .chunk_by(|d| week_start(d))
+            .into_iter()
+            .flat_map(|(_, days)| days.take(long_days_per_week).copied().collect_vec())
+            .collect_vec(),
+    };
+    let capacities = candidates
+        .iter()
+        .map(|d| (max_daily_sec - context.expected_day_secs(d)).max(0))
+        .collect_vec();
+    let deficit = -forecast.balance;
+    let extra = distribute(deficit, &capacities);
+
+    let mut weeks: BTreeMap<NaiveDate, WeekPlan> = BTreeMap::new();
+    for date in &forecast.working_days {
+        weeks.entry(week_start(date)).or_default().working_day_count += 1;
+    }
+    for (date, secs) in &forecast.flex_days {
+        weeks.entry(week_start(date)).or_default().flex_sec += secs;
+    }
+    for (date, secs) in candidates.iter().zip(&extra) {
+        if *secs > 0 {
+            weeks
+                .entry(week_start(date))
+                .or_default()
+                .extra_days
+                .push(*secs);
+        }
+    }
+
+    let mut builder = Builder::default();
+    builder.push_record([
+        "Week",
+        "Working days",
+        "Longer days",
+        "Extra per day",
+        "Extra in week",
+        "Balance at week end",
+    ]);
+    let mut balance = results.balance;
+    for (monday, week) in weeks {
+        let week_extra = week.extra_days.iter().sum::<i64>();
+        balance += week_extra - week.flex_sec;
+        let per_day = match week.extra_days.iter().minmax() {
+            itertools::MinMaxResult::NoElements => String::new(),
+            itertools::MinMaxResult::OneElement(secs) => format_duration(*secs),
+            itertools::MinMaxResult::MinMax(min, max) if min == max => format_duration(*min),
+            itertools::MinMaxResult::MinMax(min, max) => {
+                format!("{} - {}", format_duration(*min), format_duration(*max))
+            }
+        };
+        builder.push_record([
+            format!("{} ({monday})", monday.format("%G-W%V")),
+            week.working_day_count.to_string(),
+            week.extra_days.len().to_string(),
+            per_day,
+            format_duration(week_extra),
+            format_duration(balance),
+        ]);
+    }
+
+    let mut table = builder.build();
+    table.with(Style::modern_rounded());
+    println!("{table}");
+
+    let remaining = deficit - extra.iter().sum::<i64>();
+    if remaining > 0 {
+        println!(
+            "The balance cannot be fully recovered by {until} within {max_daily_hours} hours per day, {} remains.",
+            format_duration(-remaining)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use crate::clockify::Token;
+use crate::utils;
+use crate::utils::cache::get_snapshots;
+use crate::utils::table::{format_balance, ColorChoice};
+use anyhow::Error;
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+/// Balance in work days, hours and minutes without spaces, e.g. "+6d2h" or "-45m"
+fn compact_balance(seconds: i64) -> String {
+    let sign = if seconds < 0 { "-" } else { "+" };
+    let (days, hours, minutes) =
+        utils::seconds_to_days_hours_and_minutes(seconds.abs(), utils::work_day_hours());
+    let mut text = String::from(sign);
+    if days != 0 {
+        text.push_str(&format!("{days}d"));
+    }
+    if hours != 0 || (days == 0 && minutes == 0) {
+        text.push_str(&format!("{hours}h"));
+    }
+    // Minutes only matter on short balances
+    if days == 0 && minutes != 0 {
+        text.push_str(&format!("{minutes}m"));
+    }
+    text
+}
+
+/// Print the balance of the latest snapshot as one segment of a shell prompt. Nothing is
+/// fetched, so that the prompt stays fast, and nothing is printed without snapshots. The
+/// prompt is rarely a terminal, so `auto` colors it unless `NO_COLOR` is set.
+pub(crate) fn run(token: &Token, format: &str, color: ColorChoice) -> Result<(), Error> {
+    let Some(snapshot) = get_snapshots(token)?.pop() else {
+        return Ok(());
+    };
+    let segment = format
+        .replace("{balance}", &compact_balance(snapshot.balance))
+        .replace("{hours}", &format_balance(snapshot.balance))
+        .replace("{date}", &snapshot.date.to_string());
+    let plain = match color {
+        ColorChoice::Auto => std::env::var_os("NO_COLOR").is_some(),
+        ColorChoice::Always => false,
+        ColorChoice::Never => true,
+    };
+    if plain {
+        println!("{segment}");
+        return Ok(());
+    }
+    let color = if snapshot.balance < 0 { RED } else { GREEN };
+    println!("{color}{segment}{RESET}");
+    Ok(())
+}
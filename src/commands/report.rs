@@ -0,0 +1,418 @@
+use crate::args::{get_settings, ReportAction};
+use crate::calculator::{daily_balances, CalculationContext};
+use crate::clockify::Token;
+use crate::extra_settings::schema::{DayType, ExtraSettings, ProjectBudget};
+use crate::utils::table::{format_duration, format_hours};
+use crate::{
+    calculation_context, get_client, get_context, get_items, get_since_date, utils, Items,
+};
+use anyhow::Error;
+use chrono::{Datelike, Days, Months, NaiveDate};
+use regex::{Regex, RegexBuilder};
+use std::collections::BTreeMap;
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+/// Totals of a period, until the end of the calculation
+#[derive(Default)]
+struct Totals {
+    worked_sec: i64,
+    expected_sec: i64,
+    vacation_day_count: usize,
+    sick_day_count: usize,
+}
+
+impl Totals {
+    fn balance_delta(&self) -> i64 {
+        self.worked_sec - self.expected_sec
+    }
+}
+
+/// Name of a row of the comparison and its value of the year
+type Metric = (&'static str, fn(&Totals) -> i64);
+
+/// Quarter of the fiscal year starting in the month, named after the year when the fiscal
+/// year starts
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Quarter {
+    fiscal_year: i32,
+    number: u32,
+}
+
+impl Quarter {
+    fn new(date: NaiveDate, fiscal_year_start: u32) -> Self {
+        let months = date.month0() + 12 - (fiscal_year_start - 1);
+        Self {
+            fiscal_year: date.year() - i32::from(months < 12),
+            number: months % 12 / 3 + 1,
+        }
+    }
+
+    fn first_day(&self, fiscal_year_start: u32) -> NaiveDate {
+        let month0 = fiscal_year_start - 1 + (self.number - 1) * 3;
+        NaiveDate::from_ymd_opt(self.fiscal_year + (month0 / 12) as i32, month0 % 12 + 1, 1)
+            .unwrap()
+    }
+
+    fn last_day(&self, fiscal_year_start: u32) -> NaiveDate {
+        self.first_day(fiscal_year_start)
+            .checked_add_months(Months::new(3))
+            .and_then(|next| next.pred_opt())
+            .unwrap()
+    }
+}
+
+/// Counted and expected time per period from the daily balances, with the vacation and sick
+/// leave weekdays
+fn totals<K: Ord>(
+    items: &Items,
+    context: &CalculationContext,
+    include_today: bool,
+    period: impl Fn(NaiveDate) -> K,
+) -> BTreeMap<K, Totals> {
+    let user_settings = &context.settings;
+    let until = context.last_counted_date(include_today);
+    let mut totals: BTreeMap<K, Totals> = BTreeMap::new();
+    for balance in daily_balances(items, context, include_today) {
+        let period = totals.entry(period(balance.date)).or_default();
+        period.worked_sec += balance.counted;
+        period.expected_sec += balance.expected;
+    }
+    for day in items
+        .days_off
+        .iter()
+        .filter(|d| d.date() <= until && utils::is_weekday(&d.date()))
+        .filter(|d| !user_settings.is_ignored(d))
+    {
+        // Absences before the first working day have no balance
+        let Some(period) = totals.get_mut(&period(day.date())) else {
+            continue;
+        };
+        match day.type_() {
+            DayType::Vacation => period.vacation_day_count += 1,
+            DayType::SickLeave => period.sick_day_count += 1,
+            _ => {}
+        }
+    }
+    totals
+}
+
+/// One row per year, or with `compare` the years side by side with the change of the latest
+/// year from the previous one
+fn yearly_table(years: &BTreeMap<i32, Totals>, compare: bool) -> tabled::Table {
+    let mut builder = Builder::default();
+    if !compare {
+        builder.push_record([
+            "Year",
+            "Worked",
+            "Expected",
+            "Vacation days",
+            "Sick days",
+            "Balance change",
+        ]);
+        for (year, totals) in years {
+            builder.push_record([
+                year.to_string(),
+                format_duration(totals.worked_sec),
+                format_duration(totals.expected_sec),
+                totals.vacation_day_count.to_string(),
+                totals.sick_day_count.to_string(),
+                format_duration(totals.balance_delta()),
+            ]);
+        }
+        return builder.build();
+    }
+
+    let latest = years.values().rev().take(2).collect::<Vec<&Totals>>();
+    let change = |value: fn(&Totals) -> i64| match latest[..] {
+        [last, previous] => Some(value(last) - value(previous)),
+        _ => None,
+    };
+    let mut header = vec!["".to_string()];
+    header.extend(years.keys().map(i32::to_string));
+    if years.len() > 1 {
+        header.push("Change".into());
+    }
+    builder.push_record(header);
+
+    let durations: [Metric; 3] = [
+        ("Worked", |y| y.worked_sec),
+        ("Expected", |y| y.expected_sec),
+        ("Balance change", Totals::balance_delta),
+    ];
+    for (name, value) in durations {
+        let mut row = vec![name.to_string()];
+        row.extend(years.values().map(|y| format_duration(value(y))));
+        row.extend(change(value).map(format_duration));
+        builder.push_record(row);
+    }
+    let counts: [Metric; 2] = [
+        ("Vacation days", |y| y.vacation_day_count as i64),
+        ("Sick days", |y| y.sick_day_count as i64),
+    ];
+    for (name, value) in counts {
+        let mut row = vec![name.to_string()];
+        row.extend(years.values().map(|y| value(y).to_string()));
+        row.extend(change(value).map(|change| format!("{change:+}")));
+        builder.push_record(row);
+    }
+    builder.build()
+}
+
+/// The items since the start date, the context of the calculation and whether today is
+/// included
+async fn fetch(token: &Token) -> Result<(Items, CalculationContext, bool), Error> {
+    let args = get_settings().await;
+    let since_date = get_since_date(&args, token).await?;
+    let (client, user_settings) = get_client(token).await?;
+    let items = get_items(client, since_date, &user_settings).await?;
+    Ok((
+        items,
+        calculation_context(&args, user_settings),
+        args.include_today,
+    ))
+}
+
+async fn yearly(token: &Token, compare: bool) -> Result<(), Error> {
+    let (items, context, include_today) = fetch(token).await?;
+    let years = totals(&items, &context, include_today, |date| date.year());
+    if years.is_empty() {
+        println!("No working days to report.");
+        return Ok(());
+    }
+    let mut table = yearly_table(&years, compare);
+    table.with(Style::modern_rounded());
+    println!("{table}");
+    Ok(())
+}
+
+fn quarterly_table(quarters: &BTreeMap<Quarter, Totals>, fiscal_year_start: u32) -> tabled::Table {
+    let mut builder = Builder::default();
+    builder.push_record([
+        "Quarter",
+        "Period",
+        "Worked",
+        "Expected",
+        "Vacation days",
+        "Sick days",
+        "Balance change",
+    ]);
+    for (quarter, totals) in quarters {
+        // Fiscal years over the turn of the year are named after both years, e.g. 2024/25
+        let fiscal_year = if fiscal_year_start == 1 {
+            quarter.fiscal_year.to_string()
+        } else {
+            format!(
+                "{}/{:02}",
+                quarter.fiscal_year,
+                (quarter.fiscal_year + 1) % 100
+            )
+        };
+        builder.push_record([
+            format!("{fiscal_year} Q{}", quarter.number),
+            format!(
+                "{} – {}",
+                quarter.first_day(fiscal_year_start),
+                quarter.last_day(fiscal_year_start)
+            ),
+            format_duration(totals.worked_sec),
+            format_duration(totals.expected_sec),
+            totals.vacation_day_count.to_string(),
+            totals.sick_day_count.to_string(),
+            format_duration(totals.balance_delta()),
+        ]);
+    }
+    builder.build()
+}
+
+async fn quarterly(token: &Token, fiscal_year_start: u32) -> Result<(), Error> {
+    let (items, context, include_today) = fetch(token).await?;
+    let quarters = totals(&items, &context, include_today, |date| {
+        Quarter::new(date, fiscal_year_start)
+    });
+    if quarters.is_empty() {
+        println!("No working days to report.");
+        return Ok(());
+    }
+    let mut table = quarterly_table(&quarters, fiscal_year_start);
+    table.with(Style::modern_rounded());
+    println!("{table}");
+    Ok(())
+}
+
+/// Matching and all entries of a week, the entry counts and the seconds
+#[derive(Default)]
+struct Week {
+    match_count: usize,
+    matched_sec: i64,
+    logged_sec: i64,
+}
+
+/// Logged and matching time per ISO week. Weeks without matching entries are included, so
+/// that the load can be compared.
+fn matching_weeks(
+    items: &Items,
+    user_settings: &ExtraSettings,
+    until: NaiveDate,
+    regex: &Regex,
+) -> BTreeMap<(i32, u32), Week> {
+    let mut weeks: BTreeMap<(i32, u32), Week> = BTreeMap::new();
+    for wd in items
+        .working_days
+        .iter()
+        .filter(|wd| wd.date <= until && !user_settings.is_ignored_work_day(wd))
+    {
+        let iso_week = wd.date.iso_week();
+        let week = weeks.entry((iso_week.year(), iso_week.week())).or_default();
+        for item in &wd.items {
+            week.logged_sec += item.duration();
+            if regex.is_match(item.description()) {
+                week.match_count += 1;
+                week.matched_sec += item.duration();
+            }
+        }
+    }
+    weeks
+}
+
+async fn grep(token: &Token, pattern: &str, ignore_case: bool) -> Result<(), Error> {
+    let regex = RegexBuilder::new(pattern)
+        .case_insensitive(ignore_case)
+        .build()
+        .map_err(|e| Error::msg(format!("Invalid pattern: {e}")))?;
+    let (items, context, include_today) = fetch(token).await?;
+    let until = context.last_counted_date(include_today);
+    let weeks = matching_weeks(&items, &context.settings, until, &regex);
+    let matched = weeks.values().map(|w| w.match_count).sum::<usize>();
+    if matched == 0 {
+        println!("No entries match '{pattern}'.");
+        return Ok(());
+    }
+
+    let share = |matched_sec: i64, logged_sec: i64| {
+        format!(
+            "{:.0} %",
+            matched_sec as f32 / logged_sec.max(1) as f32 * 100.0
+        )
+    };
+    let mut builder = Builder::default();
+    builder.push_record(["ISO week", "Entries", "Matched", "Logged", "Share"]);
+    for ((year, number), week) in &weeks {
+        builder.push_record([
+            format!("{year}-W{number:02}"),
+            week.match_count.to_string(),
+            format_duration(week.matched_sec),
+            format_duration(week.logged_sec),
+            share(week.matched_sec, week.logged_sec),
+        ]);
+    }
+    let matched_sec = weeks.values().map(|w| w.matched_sec).sum::<i64>();
+    let logged_sec = weeks.values().map(|w| w.logged_sec).sum::<i64>();
+    builder.push_record([
+        "Total".to_string(),
+        matched.to_string(),
+        format_duration(matched_sec),
+        format_duration(logged_sec),
+        share(matched_sec, logged_sec),
+    ]);
+    let mut table = builder.build();
+    table.with(Style::modern_rounded());
+    println!("{table}");
+    println!(
+        "Average per week: {}",
+        format_duration(matched_sec / weeks.len() as i64)
+    );
+    Ok(())
+}
+
+/// Budgets in their current period until the date
+fn budgets_table(
+    items: &Items,
+    user_settings: &ExtraSettings,
+    budgets: &[(&ProjectBudget, (NaiveDate, NaiveDate))],
+    until: NaiveDate,
+) -> tabled::Table {
+    let mut builder = Builder::default();
+    builder.push_record([
+        "Project",
+        "Period",
+        "Budget",
+        "Consumed",
+        "Remaining",
+        "Used",
+        "Projected exhaustion",
+    ]);
+    for &(budget, (start, end)) in budgets {
+        let consumed_sec = items
+            .working_days
+            .iter()
+            .filter(|wd| (start..=until).contains(&wd.date))
+            .filter(|wd| !user_settings.is_ignored_work_day(wd))
+            .flat_map(|wd| &wd.items)
+            .filter(|item| item.project() == budget.project)
+            .map(|item| item.duration())
+            .sum::<i64>();
+        let budget_sec = (budget.hours * 3600.0).round() as i64;
+        let remaining_sec = budget_sec - consumed_sec;
+        // Burn rate per calendar day of the period so far
+        let elapsed_days = (until - start).num_days() + 1;
+        let exhaustion = if remaining_sec <= 0 {
+            "Exhausted".to_string()
+        } else if consumed_sec == 0 {
+            "-".to_string()
+        } else {
+            let days_left = ((remaining_sec * elapsed_days) as u64).div_ceil(consumed_sec as u64);
+            match until.checked_add_days(Days::new(days_left)) {
+                Some(date) if date <= end => date.to_string(),
+                _ => "Lasts the period".to_string(),
+            }
+        };
+        builder.push_record([
+            budget.project.clone(),
+            format!("{start} – {end}"),
+            format_hours(budget_sec),
+            format_hours(consumed_sec),
+            format_hours(remaining_sec),
+            format!(
+                "{:.0} %",
+                consumed_sec as f32 / budget_sec.max(1) as f32 * 100.0
+            ),
+            exhaustion,
+        ]);
+    }
+    builder.build()
+}
+
+async fn budgets(token: &Token) -> Result<(), Error> {
+    let include_today = get_settings().await.include_today;
+    let (client, context) = get_context(token).await?;
+    let user_settings = &context.settings;
+    let until = context.last_counted_date(include_today);
+    let budgets = user_settings
+        .project_budgets()
+        .iter()
+        .map(|budget| (budget, budget.period.range(until)))
+        .collect::<Vec<_>>();
+    // The periods may start before the start date of the balance
+    let Some(since_date) = budgets.iter().map(|(_, (start, _))| *start).min() else {
+        println!("No project budgets in the extra settings.");
+        return Ok(());
+    };
+    let items = get_items(client, since_date, user_settings).await?;
+    let mut table = budgets_table(&items, user_settings, &budgets, until);
+    table.with(Style::modern_rounded());
+    println!("{table}");
+    Ok(())
+}
+
+pub(crate) async fn run(token: &Token, action: &ReportAction) -> Result<(), Error> {
+    match action {
+        ReportAction::Yearly { compare } => yearly(token, *compare).await,
+        ReportAction::Quarterly { fiscal_year_start } => quarterly(token, *fiscal_year_start).await,
+        ReportAction::Grep {
+            pattern,
+            ignore_case,
+        } => grep(token, pattern, *ignore_case).await,
+        ReportAction::Budgets => budgets(token).await,
+    }
+}
@@ -0,0 +1,84 @@
+use crate::args::ScheduleAction;
+use crate::clockify::Token;
+use crate::extra_settings::schema::{ExpectedWorkingHours, ExtraSettings, SchedulePeriod};
+use crate::extra_settings::GlobalSettings;
+use crate::{new_client, utils, CalculationContext};
+use anyhow::Error;
+use chrono::{Datelike, Duration, NaiveDate};
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+/// Expected hours are set in half-hour steps
+const STEP_HOURS: f32 = 0.5;
+
+/// Effective schedule from the first configured range until the end of the current year
+fn periods(user_settings: &ExtraSettings) -> Vec<SchedulePeriod> {
+    let year_start = NaiveDate::from_ymd_opt(utils::today().year(), 1, 1).unwrap();
+    let start = user_settings
+        .first_expected_hours_date()
+        .unwrap_or(year_start)
+        .min(year_start);
+    let start = start - Duration::days(start.weekday().num_days_from_monday() as i64);
+    let end = NaiveDate::from_ymd_opt(utils::today().year(), 12, 31).unwrap();
+    CalculationContext::new(user_settings.clone()).schedule_periods(start, end)
+}
+
+fn show(user_settings: &ExtraSettings) {
+    let mut builder = Builder::default();
+    builder.push_record(["From", "To", "Mon", "Tue", "Wed", "Thu", "Fri"]);
+    for period in periods(user_settings) {
+        let mut record = vec![period.start.to_string(), period.end.to_string()];
+        record.extend(period.hours.iter().map(|h| {
+            let (hours, minutes) = utils::hours_to_hours_and_minutes(*h);
+            format!("{hours}:{minutes:02}")
+        }));
+        builder.push_record(record);
+    }
+    let mut table = builder.build();
+    table.with(Style::modern_rounded());
+    println!("{table}");
+
+    let overlapping = user_settings.overlapping_expected_hours();
+    if !overlapping.is_empty() {
+        println!("Overlapping expectedWorkingHours ranges, the shorter one wins:");
+        for range in overlapping {
+            println!("- {range}");
+        }
+    }
+}
+
+pub(crate) async fn run(token: &Token, action: &ScheduleAction) -> Result<(), Error> {
+    let client = new_client(token).await?;
+    let mut global_settings = GlobalSettings::create_settings().await?;
+    let mut user_settings = global_settings
+        .get_user_settings(&client.user.email)
+        .unwrap_or(ExtraSettings::new(&client.user.email));
+
+    match action {
+        ScheduleAction::Show => show(&user_settings),
+        ScheduleAction::Set {
+            from,
+            until,
+            hours,
+            name,
+        } => {
+            if until < from {
+                return Err(Error::msg("End date cannot be before the start date!"));
+            }
+            if *hours < 0.0 || (hours / STEP_HOURS).fract() != 0.0 {
+                return Err(Error::msg(format!(
+                    "Hours must be given in steps of {STEP_HOURS} hours!"
+                )));
+            }
+
+            let name = name.clone().unwrap_or(format!("{hours} hours per day"));
+            user_settings
+                .set_expected_hours(ExpectedWorkingHours::new(name, *from, *until, *hours));
+            global_settings.set_user_settings(user_settings.clone());
+            global_settings.save().await?;
+            println!("Expected hours saved to the settings.");
+            show(&user_settings);
+        }
+    }
+    Ok(())
+}
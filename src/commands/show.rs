@@ -0,0 +1,78 @@
+use crate::calculator::DayBalance;
+use crate::clockify::Token;
+use crate::utils::table::format_duration;
+use crate::{get_context, get_items, utils, Items};
+use anyhow::Error;
+use chrono::{Datelike, Local, NaiveDate};
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+/// Print the work items of a day with its expected time and contribution to the balance
+pub(crate) async fn run(token: &Token, date: NaiveDate) -> Result<(), Error> {
+    if date > utils::today() {
+        return Err(Error::msg("Date cannot be in the future!"));
+    }
+
+    let (client, context) = get_context(token).await?;
+    let user_settings = &context.settings;
+    let Items {
+        public_holidays,
+        working_days,
+        days_off,
+        ..
+    } = get_items(client, date, user_settings).await?;
+
+    let working_days = working_days
+        .into_iter()
+        .filter(|wd| wd.date == date && !user_settings.is_ignored_work_day(wd))
+        .collect::<Vec<_>>();
+    let worked_sec = working_days
+        .iter()
+        .map(|wd| user_settings.logged_secs(wd))
+        .sum::<i64>();
+    let items = working_days
+        .into_iter()
+        .flat_map(|wd| wd.items)
+        .collect::<Vec<_>>();
+    let day = public_holidays
+        .iter()
+        .chain(&days_off)
+        .find(|d| d.date() == date);
+    let day_type = day.map(|d| d.type_());
+    let balance = DayBalance::new(date, worked_sec, day, &context);
+
+    let mut builder = Builder::default();
+    builder.push_record(["Description", "Project", "Start", "Stop", "Duration"]);
+    for item in &items {
+        builder.push_record([
+            item.description().to_string(),
+            item.project().to_string(),
+            item.start()
+                .with_timezone(&Local)
+                .format("%H:%M")
+                .to_string(),
+            item.stop()
+                .with_timezone(&Local)
+                .format("%H:%M")
+                .to_string(),
+            format_duration(item.duration()),
+        ]);
+    }
+    let mut table = builder.build();
+    table.with(Style::modern_rounded());
+
+    let type_str = match (&day_type, day.and_then(|d| d.title())) {
+        (Some(t), Some(title)) => format!(" ({t:?}: {title})"),
+        (Some(t), None) => format!(" ({t:?})"),
+        _ => String::new(),
+    };
+    println!("{date}, {}{type_str}", date.weekday());
+    println!("{table}");
+    println!("Worked: {}", format_duration(worked_sec));
+    println!("Expected: {}", format_duration(balance.expected));
+    println!(
+        "Contribution to the balance: {}",
+        format_duration(balance.contribution())
+    );
+    Ok(())
+}
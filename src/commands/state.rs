@@ -0,0 +1,147 @@
+use crate::args::StateAction;
+use crate::clockify::Token;
+use crate::extra_settings::settings_path;
+use crate::utils::cache::Snapshot;
+use crate::utils::cache::{
+    add_snapshot, get_cache_first_date, get_snapshots, set_cache_first_date,
+};
+use crate::utils::dirs;
+use anyhow::Error;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+const STATE_VERSION: u32 = 1;
+const MANIFEST_ENTRY: &str = "manifest.json";
+const CACHE_ENTRY: &str = "cache.json";
+const SETTINGS_ENTRY: &str = "settings.json";
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Manifest {
+    version: u32,
+    created: DateTime<Utc>,
+}
+
+/// Cache of the current user. Cache keys are tokens, so they are left out.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheState {
+    first_date: Option<NaiveDate>,
+    #[serde(default)]
+    history: Vec<Snapshot>,
+}
+
+fn append_json<W: Write, T: Serialize>(
+    archive: &mut tar::Builder<W>,
+    name: &str,
+    value: &T,
+) -> Result<(), Error> {
+    let bytes = serde_json::to_vec_pretty(value)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp() as u64);
+    header.set_cksum();
+    archive.append_data(&mut header, name, bytes.as_slice())?;
+    Ok(())
+}
+
+fn export(token: &Token, path: &Path) -> Result<(), Error> {
+    let encoder = zstd::Encoder::new(File::create(path)?, 0)?;
+    let mut archive = tar::Builder::new(encoder);
+
+    let manifest = Manifest {
+        version: STATE_VERSION,
+        created: Utc::now(),
+    };
+    append_json(&mut archive, MANIFEST_ENTRY, &manifest)?;
+
+    let cache = CacheState {
+        first_date: get_cache_first_date(token)?,
+        history: get_snapshots(token)?,
+    };
+    append_json(&mut archive, CACHE_ENTRY, &cache)?;
+
+    let settings_path = settings_path();
+    if settings_path.is_file() {
+        archive.append_path_with_name(settings_path, SETTINGS_ENTRY)?;
+    }
+
+    archive.into_inner()?.finish()?;
+    Ok(())
+}
+
+fn read_entries(path: &Path) -> Result<HashMap<String, Vec<u8>>, Error> {
+    let decoder = zstd::Decoder::new(File::open(path)?)?;
+    let mut archive = tar::Archive::new(decoder);
+    let mut entries = HashMap::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes)?;
+        entries.insert(name, bytes);
+    }
+    Ok(entries)
+}
+
+fn import(token: &Token, path: &Path, force: bool) -> Result<(), Error> {
+    let mut entries = read_entries(path)?;
+
+    let manifest = entries
+        .remove(MANIFEST_ENTRY)
+        .ok_or(Error::msg("State archive is missing the manifest!"))?;
+    let manifest: Manifest = serde_json::from_slice(&manifest)?;
+    if manifest.version != STATE_VERSION {
+        return Err(Error::msg(format!(
+            "Unsupported state version {}, expected {STATE_VERSION}!",
+            manifest.version
+        )));
+    }
+
+    let settings = entries.remove(SETTINGS_ENTRY);
+    let settings_path = settings_path();
+    if settings.is_some() && settings_path.exists() && !force {
+        return Err(Error::msg(format!(
+            "{} already exists! Use --force to overwrite it.",
+            settings_path.display()
+        )));
+    }
+
+    if let Some(cache) = entries.remove(CACHE_ENTRY) {
+        let cache: CacheState = serde_json::from_slice(&cache)?;
+        if let Some(first_date) = cache.first_date {
+            set_cache_first_date(token, &first_date)?;
+        }
+        for snapshot in cache.history {
+            add_snapshot(token, snapshot)?;
+        }
+    }
+
+    if let Some(settings) = settings {
+        dirs::create_parent(&settings_path)?;
+        std::fs::write(settings_path, settings)?;
+    }
+
+    for name in entries.keys() {
+        log::warn!("Unknown entry in the state archive: {name}");
+    }
+    Ok(())
+}
+
+pub(crate) fn run(token: &Token, action: &StateAction) -> Result<(), Error> {
+    match action {
+        StateAction::Export { path } => {
+            export(token, path)?;
+            println!("State exported to {}", path.display());
+        }
+        StateAction::Import { path, force } => {
+            import(token, path, *force)?;
+            println!("State imported from {}", path.display());
+        }
+    }
+    Ok(())
+}
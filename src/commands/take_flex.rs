@@ -0,0 +1,101 @@
+use crate::args::get_settings;
+use crate::clockify::{ClockifyClient, TimeOffType, Token};
+use crate::commands::forecast::get_forecast;
+use crate::extra_settings::schema::ExtraSettings;
+use crate::utils::table::format_duration;
+use crate::{get_context, get_since_date, utils};
+use anyhow::Error;
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeZone, Utc};
+use std::io::{self, Write};
+
+/// Ask a yes or no question on the terminal, no is the default
+fn confirm(question: &str) -> Result<bool, Error> {
+    print!("{question} [y/N] ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// Local day as UTC, the same way Clockify stores full-day time off
+fn local_day(date: NaiveDate) -> Result<(DateTime<Utc>, DateTime<Utc>), Error> {
+    let local = |time: NaiveTime| {
+        Local
+            .from_local_datetime(&date.and_time(time))
+            .earliest()
+            .map(|datetime| datetime.with_timezone(&Utc))
+            .ok_or_else(|| Error::msg(format!("Invalid local time on {date}")))
+    };
+    let end = NaiveTime::from_hms_milli_opt(23, 59, 59, 999).unwrap();
+    Ok((local(NaiveTime::MIN)?, local(end)?))
+}
+
+/// Active policy of the workspace mapped to flex time off
+async fn flex_policy_id(
+    client: &ClockifyClient,
+    user_settings: &ExtraSettings,
+) -> Result<String, Error> {
+    client
+        .get_policies()
+        .await?
+        .into_iter()
+        .find(|policy| {
+            !policy.archived
+                && matches!(
+                    user_settings.time_off_type(&policy.id, &policy.name),
+                    Some(TimeOffType::DayOff)
+                )
+        })
+        .map(|policy| policy.id)
+        .ok_or(Error::msg(
+            "No active \"Day off\" policy found, map one to DayOff in policyMapping of the extra settings",
+        ))
+}
+
+/// Request a flex day off on the date, after checking the balance left after it
+pub(crate) async fn run(
+    token: &Token,
+    date: NaiveDate,
+    note: Option<&str>,
+    yes: bool,
+    force: bool,
+) -> Result<(), Error> {
+    if date < utils::today() {
+        return Err(Error::msg("Date cannot be in the past!"));
+    }
+    if !utils::is_weekday(&date) {
+        return Err(Error::msg(format!("{date} is not a weekday!")));
+    }
+
+    let since_date = get_since_date(&*get_settings().await, token).await?;
+    let (client, context) = get_context(token).await?;
+    let (_, forecast) = get_forecast(client.clone(), &context, since_date, date).await?;
+    if !forecast.working_days.contains(&date) {
+        return Err(Error::msg(format!(
+            "{date} is already a public holiday or booked time off!"
+        )));
+    }
+
+    let balance_after = forecast.balance_on(date) - context.expected_day_secs(&date);
+    println!(
+        "The balance at the end of {date} would be {} with the flex day.",
+        format_duration(balance_after)
+    );
+    if balance_after < 0 && !force {
+        return Err(Error::msg(
+            "The flex day would make the balance negative! Use --force to request it anyway.",
+        ));
+    }
+    if !yes && !confirm(&format!("Request a flex day off on {date}?"))? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let policy_id = flex_policy_id(&client, &context.settings).await?;
+    let (start, end) = local_day(date)?;
+    let id = client
+        .request_time_off(&policy_id, note.unwrap_or_default(), start, end)
+        .await?;
+    println!("Flex day requested on {date}. Undo with `undo {id}`.");
+    Ok(())
+}
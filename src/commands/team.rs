@@ -0,0 +1,78 @@
+use crate::clockify::Token;
+use crate::commands::members;
+use crate::extra_settings::GlobalSettings;
+use crate::utils::table::{format_balance, format_hours};
+use crate::{new_client, Results};
+use anyhow::Error;
+use std::path::Path;
+
+const HEADER: [&str; 6] = ["Name", "Email", "Since", "Worked", "Expected", "Balance"];
+
+type Row = [String; 6];
+
+fn member_row(name: &str, email: &str, results: Result<Results, Error>) -> Row {
+    match results {
+        Ok(r) => [
+            name.into(),
+            email.into(),
+            r.first_working_day.to_string(),
+            format_hours(r.worked_time),
+            format_hours(r.expected_working_time_sec),
+            format_balance(r.balance),
+        ],
+        Err(e) => [
+            name.into(),
+            email.into(),
+            "-".into(),
+            "-".into(),
+            "-".into(),
+            format!("Error: {e}"),
+        ],
+    }
+}
+
+fn print_rows(rows: Vec<Row>, csv: Option<&Path>) -> Result<(), Error> {
+    if let Some(path) = csv {
+        members::write_csv(path, &HEADER, &rows)?;
+        println!("Team balances saved to {}", path.display());
+    }
+    members::print_table(&HEADER, rows);
+    Ok(())
+}
+
+/// Balance per workspace member with an admin token, of all the members without emails
+pub(crate) async fn run(token: &Token, emails: &[String], csv: Option<&Path>) -> Result<(), Error> {
+    let extra_settings = GlobalSettings::create_settings().await?;
+    let client = new_client(token).await?;
+    let members = members::select(&client, emails).await?;
+
+    let mut rows = Vec::with_capacity(members.len());
+    for member in members {
+        println!("Calculating balance of {}...", member.user.name);
+        let results = members::results(client.for_user(&member.user), &extra_settings).await;
+        rows.push(member_row(&member.user.name, &member.user.email, results));
+    }
+    print_rows(rows, csv)
+}
+
+/// Balance per token of the teammates. The tokens are never printed, a failing one is shown
+/// by its position.
+pub(crate) async fn run_tokens(tokens: &[Token], csv: Option<&Path>) -> Result<(), Error> {
+    let extra_settings = GlobalSettings::create_settings().await?;
+    let mut rows = Vec::with_capacity(tokens.len());
+    for (i, token) in tokens.iter().enumerate() {
+        let client = match new_client(token).await {
+            Ok(client) => client,
+            Err(e) => {
+                log::warn!("Failed to fetch the user of token {}: {e}", i + 1);
+                rows.push(member_row(&format!("Token {}", i + 1), "-", Err(e)));
+                continue;
+            }
+        };
+        println!("Calculating balance of {}...", client.user.name);
+        let (name, email) = (client.user.name.clone(), client.user.email.clone());
+        let results = members::results(client, &extra_settings).await;
+        rows.push(member_row(&name, &email, results));
+    }
+    print_rows(rows, csv)
+}
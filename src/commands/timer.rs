@@ -0,0 +1,38 @@
+use crate::args::TimerAction;
+use crate::clockify::Token;
+use crate::new_client;
+use crate::utils::table::format_duration;
+use anyhow::Error;
+use chrono::Local;
+
+pub(crate) async fn run(token: &Token, action: &TimerAction) -> Result<(), Error> {
+    let client = new_client(token).await?;
+    match action {
+        TimerAction::Start {
+            project,
+            description,
+        } => {
+            let project_id = match project {
+                Some(name) => Some(client.find_project(name).await?.id),
+                None => None,
+            };
+            let id = client
+                .start_timer(description, project_id.as_deref())
+                .await?;
+            println!(
+                "Timer started at {}. Undo with `undo {id}`.",
+                Local::now().format("%H:%M")
+            );
+        }
+        TimerAction::Stop => match client.stop_timer().await? {
+            Some(timer) => println!(
+                "Timer \"{}\" stopped at {} after {}.",
+                timer.description,
+                timer.end.with_timezone(&Local).format("%H:%M"),
+                format_duration((timer.end - timer.start).num_seconds())
+            ),
+            None => println!("No timer is running."),
+        },
+    }
+    Ok(())
+}
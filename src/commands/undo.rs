@@ -0,0 +1,44 @@
+use crate::clockify::Token;
+use crate::new_client;
+use crate::utils::audit;
+use anyhow::Error;
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+/// Delete a resource created by this tool, the latest one by default
+pub(crate) async fn run(token: &Token, id: Option<&str>, list: bool) -> Result<(), Error> {
+    let entries = audit::undoable_entries()?;
+
+    if list {
+        if entries.is_empty() {
+            println!("Nothing to undo.");
+            return Ok(());
+        }
+        let mut builder = Builder::default();
+        builder.push_record(["Created", "ID", "Resource"]);
+        for entry in &entries {
+            builder.push_record([
+                entry.timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+                entry.response_id.clone(),
+                entry.resource_path.clone(),
+            ]);
+        }
+        let mut table = builder.build();
+        table.with(Style::modern_rounded());
+        println!("{table}");
+        return Ok(());
+    }
+
+    let entry = match id {
+        Some(id) => entries
+            .iter()
+            .find(|e| e.response_id == id)
+            .ok_or_else(|| Error::msg(format!("Nothing to undo with id {id}!")))?,
+        None => entries.last().ok_or(Error::msg("Nothing to undo!"))?,
+    };
+
+    let client = new_client(token).await?;
+    client.undo(entry).await?;
+    println!("Deleted {}", entry.resource_path);
+    Ok(())
+}
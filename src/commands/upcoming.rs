@@ -0,0 +1,115 @@
+use crate::args::get_settings;
+use crate::clockify::{get_pending_days_off, Token};
+use crate::extra_settings::rules::DayEffect;
+use crate::extra_settings::schema::DayType;
+use crate::models::Day;
+use crate::utils::resolution::resolve_pending;
+use crate::utils::table::format_duration;
+use crate::{get_context, get_items, utils, Items};
+use anyhow::Error;
+use chrono::{Datelike, Months, NaiveDate};
+use std::collections::{BTreeMap, HashSet};
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+#[derive(Default)]
+struct Month {
+    vacation_day_count: usize,
+    flex_day_count: usize,
+    parental_leave_day_count: usize,
+    unpaid_leave_day_count: usize,
+    /// Change of the balance caused by the absences
+    balance_impact_sec: i64,
+    /// Change of the balance if the vacations were taken as flex days
+    vacation_as_flex_sec: i64,
+}
+
+/// Future booked absences by type per month for the coming months
+pub(crate) async fn run(token: &Token, month_count: u32) -> Result<(), Error> {
+    let args = get_settings().await;
+    let today = utils::today();
+    let until = today
+        .with_day(1)
+        .and_then(|first| first.checked_add_months(Months::new(month_count)))
+        .and_then(|next| next.pred_opt())
+        .ok_or(Error::msg("Too many months!"))?;
+
+    let (client, context) = get_context(token).await?;
+    let user_settings = &context.settings;
+    let pending_days_off = if args.include_pending {
+        get_pending_days_off(client.clone(), &today, user_settings).await?
+    } else {
+        Vec::new()
+    };
+    let Items {
+        public_holidays,
+        days_off,
+        ..
+    } = get_items(client, today, user_settings).await?;
+    let pending_days_off =
+        resolve_pending(pending_days_off, &public_holidays, &days_off, user_settings);
+
+    let holidays = public_holidays
+        .iter()
+        .filter(|d| !user_settings.is_ignored(d))
+        .map(Day::date)
+        .collect::<HashSet<NaiveDate>>();
+
+    let mut months: BTreeMap<NaiveDate, Month> = BTreeMap::new();
+    for date in utils::DateRange(today, until) {
+        months.entry(date.with_day(1).unwrap()).or_default();
+    }
+    for day in days_off.iter().chain(&pending_days_off) {
+        let date = day.date();
+        if date < today
+            || date > until
+            || !utils::is_weekday(&date)
+            || holidays.contains(&date)
+            || user_settings.is_ignored(day)
+        {
+            continue;
+        }
+        let month = months.entry(date.with_day(1).unwrap()).or_default();
+        let expected_sec = context.expected_day_secs(&date);
+        match day.type_() {
+            DayType::Vacation => {
+                month.vacation_day_count += 1;
+                month.vacation_as_flex_sec -= expected_sec;
+            }
+            DayType::Flex => month.flex_day_count += 1,
+            DayType::ParentalLeave => month.parental_leave_day_count += 1,
+            DayType::UnpaidLeave => month.unpaid_leave_day_count += 1,
+            _ => {}
+        }
+        if user_settings.day_effect(day) == DayEffect::Expected {
+            month.balance_impact_sec -= expected_sec;
+        }
+    }
+
+    let mut builder = Builder::default();
+    builder.push_record([
+        "Month",
+        "Vacations",
+        "Flex days",
+        "Parental leaves",
+        "Unpaid leaves",
+        "Balance impact",
+        "Vacations as flex",
+    ]);
+    for (first_day, month) in months {
+        builder.push_record([
+            first_day.format("%Y-%m").to_string(),
+            month.vacation_day_count.to_string(),
+            month.flex_day_count.to_string(),
+            month.parental_leave_day_count.to_string(),
+            month.unpaid_leave_day_count.to_string(),
+            format_duration(month.balance_impact_sec),
+            format_duration(month.vacation_as_flex_sec),
+        ]);
+    }
+
+    let mut table = builder.build();
+    table.with(Style::modern_rounded());
+    println!("{table}");
+    Ok(())
+}
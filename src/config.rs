@@ -0,0 +1,249 @@
+use crate::args::{validate_country, validate_date, validate_work_day_hours, Args};
+use crate::clockify::Token;
+use crate::extra_settings::{self, SettingsSource};
+use crate::utils;
+use anyhow::Error;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use url::Url;
+
+pub(crate) const CONFIG_FILE: &str = "config.toml";
+
+/// Options read from config.toml in the config directory, named after the long flags. They
+/// are used only when the flag is not given on the command line or in the environment.
+#[derive(Deserialize, Serialize, Default, Debug)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct ConfigFile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_balance: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub work_day_hours: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_today: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_pending: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exclude_projects: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exclude_tags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub concurrency: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hours_format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<Url>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reports_api_url: Option<Url>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<Url>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_timeout: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Extra settings file outside of the config directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settings_path: Option<PathBuf>,
+    /// Extra settings fetched on every run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settings_url: Option<Url>,
+    /// Header of the settings request in the format `Name: value`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settings_header: Option<String>,
+}
+
+fn value_enum<T: ValueEnum>(key: &str, value: &str) -> Result<T, Error> {
+    T::from_str(value, true)
+        .map_err(|_| Error::msg(format!("Invalid {key} '{value}' in {CONFIG_FILE}!")))
+}
+
+impl ConfigFile {
+    /// The file if it exists, otherwise no options. The options of the profile, a table under
+    /// `[profiles]`, replace the ones at the top of the file.
+    pub(crate) fn read(path: &Path, profile: Option<&str>) -> Result<Self, Error> {
+        let invalid =
+            |e: &dyn std::fmt::Display| Error::msg(format!("Invalid {}: {e}", path.display()));
+        let mut table = if path.is_file() {
+            let text = std::fs::read_to_string(path)?;
+            text.parse::<toml::Table>().map_err(|e| invalid(&e))?
+        } else {
+            toml::Table::new()
+        };
+        let profiles = table.remove("profiles");
+        if let Some(name) = profile {
+            let options = profiles
+                .as_ref()
+                .and_then(|profiles| profiles.get(name))
+                .and_then(toml::Value::as_table)
+                .ok_or(Error::msg(format!(
+                    "No profile '{name}' in {}!",
+                    path.display()
+                )))?;
+            table.extend(options.clone());
+        }
+        table.try_into().map_err(|e| invalid(&e))
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<(), Error> {
+        utils::dirs::create_parent(path)?;
+        std::fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Where the extra settings are read from
+    fn settings_source(&self) -> Result<SettingsSource, Error> {
+        match (&self.settings_path, &self.settings_url) {
+            (Some(_), Some(_)) => Err(Error::msg(format!(
+                "Only one of settings-path and settings-url can be set in {CONFIG_FILE}!"
+            ))),
+            (Some(path), None) => Ok(SettingsSource::Path(path.clone())),
+            (None, Some(url)) => {
+                let header = match &self.settings_header {
+                    Some(header) => Some(
+                        header
+                            .split_once(':')
+                            .map(|(name, value)| (name.trim().to_owned(), value.trim().to_owned()))
+                            .ok_or(Error::msg(format!(
+                                "Invalid settings-header in {CONFIG_FILE}, expected 'Name: value'!"
+                            )))?,
+                    ),
+                    None => None,
+                };
+                Ok(SettingsSource::Url {
+                    url: url.clone(),
+                    header,
+                })
+            }
+            (None, None) => Ok(SettingsSource::Local),
+        }
+    }
+
+    /// Fill in the options of the arguments that are not given on the command line or in the
+    /// environment, and set the source of the extra settings
+    pub(crate) fn apply(self, args: &mut Args, matches: &ArgMatches) -> Result<(), Error> {
+        extra_settings::set_source(self.settings_source()?)?;
+
+        let unset = |id: &str| {
+            !matches!(
+                matches.value_source(id),
+                Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+            )
+        };
+
+        match matches.value_source("token") {
+            Some(ValueSource::CommandLine) => args.token_source = Some("Given as argument"),
+            Some(ValueSource::EnvVariable) => args.token_source = Some("Found in the environment"),
+            _ => {
+                if let Some(token) = self.token {
+                    args.token = Some(Token::new(&token));
+                    args.token_source = Some("Found in config.toml");
+                }
+            }
+        }
+        if let Some(date) = self.start_date.filter(|_| unset("start_date")) {
+            let date = validate_date(&date)
+                .map_err(|e| Error::msg(format!("Invalid start-date in {CONFIG_FILE}: {e}")))?;
+            args.start_date = Some(date);
+        }
+        if let Some(balance) = self.start_balance.filter(|_| unset("start_balance")) {
+            args.start_balance = Some(balance);
+        }
+        if let Some(hours) = self.work_day_hours.filter(|_| unset("work_day_hours")) {
+            args.work_day_hours = validate_work_day_hours(&hours.to_string())
+                .map_err(|e| Error::msg(format!("Invalid work-day-hours in {CONFIG_FILE}: {e}")))?;
+        }
+        if let Some(country) = self.country.filter(|_| unset("country")) {
+            args.country = validate_country(&country)
+                .map_err(|e| Error::msg(format!("Invalid country in {CONFIG_FILE}: {e}")))?;
+        }
+        if let Some(include_today) = self.include_today.filter(|_| unset("include_today")) {
+            args.include_today = include_today;
+        }
+        if let Some(include_pending) = self.include_pending.filter(|_| unset("include_pending")) {
+            args.include_pending = include_pending;
+        }
+        if let Some(projects) = self.exclude_projects.filter(|_| unset("exclude_projects")) {
+            args.exclude_projects = projects;
+        }
+        if let Some(tags) = self.exclude_tags.filter(|_| unset("exclude_tags")) {
+            args.exclude_tags = tags;
+        }
+        if let Some(source) = self.source.filter(|_| unset("source")) {
+            args.source = value_enum("source", &source)?;
+        }
+        if let Some(concurrency) = self.concurrency.filter(|_| unset("concurrency")) {
+            args.concurrency = concurrency;
+        }
+        if let Some(strict) = self.strict.filter(|_| unset("strict")) {
+            args.strict = strict;
+        }
+        if let Some(output) = self.output.filter(|_| unset("output")) {
+            args.output = value_enum("output", &output)?;
+        }
+        if let Some(locale) = self.locale.filter(|_| unset("locale")) {
+            args.locale = value_enum("locale", &locale)?;
+        }
+        if let Some(format) = self.hours_format.filter(|_| unset("hours_format")) {
+            args.hours_format = value_enum("hours-format", &format)?;
+        }
+        if let Some(color) = self.color.filter(|_| unset("color")) {
+            args.color = value_enum("color", &color)?;
+        }
+        if let Some(rows) = self.rows.filter(|_| unset("rows")) {
+            args.rows = rows
+                .iter()
+                .map(|row| value_enum("rows", row))
+                .collect::<Result<_, _>>()?;
+        }
+        if let Some(url) = self.api_url.filter(|_| unset("api_url")) {
+            args.api_url = Some(url);
+        }
+        if let Some(url) = self.reports_api_url.filter(|_| unset("reports_api_url")) {
+            args.reports_api_url = Some(url);
+        }
+        if let Some(proxy) = self.proxy.filter(|_| unset("proxy")) {
+            args.proxy = Some(proxy);
+        }
+        if let Some(path) = self.ca_bundle.filter(|_| unset("ca_bundle")) {
+            args.ca_bundle = Some(path);
+        }
+        if let Some(seconds) = self.request_timeout.filter(|_| unset("request_timeout")) {
+            args.request_timeout = seconds.max(1);
+        }
+        if let Some(seconds) = self.timeout.filter(|_| unset("timeout")) {
+            args.timeout = Some(seconds.max(1));
+        }
+        if let Some(level) = self.log_level.filter(|_| unset("log_level")) {
+            args.log_level = value_enum("log-level", &level)?;
+        }
+        if let Some(output) = self.log_output.filter(|_| unset("log_output")) {
+            args.log_output = value_enum("log-output", &output)?;
+        }
+        Ok(())
+    }
+}
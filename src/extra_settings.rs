@@ -1,39 +1,266 @@
-pub(crate) mod schema;
+pub mod rules;
+pub mod schema;
+pub mod validation;
 
+use crate::utils::{self, dirs};
 use anyhow::Error;
 use schema::ExtraSettings;
+use serde_json::{json, Map, Value};
+use std::path::PathBuf;
+use std::sync::OnceLock;
 use tokio::{fs::metadata, fs::File, io::AsyncReadExt};
+use url::Url;
+use validation::Severity;
 
-pub(crate) struct GlobalSettings {
+/// Where the extra settings are read from
+#[derive(Clone, Debug)]
+pub enum SettingsSource {
+    /// settings.json in the config directory
+    Local,
+    /// File outside of the config directory, e.g. in a git repository shared by a team
+    Path(PathBuf),
+    /// Fetched on every run. The last fetched copy is used when fetching fails.
+    Url {
+        url: Url,
+        /// Header name and value, e.g. for authorization
+        header: Option<(String, String)>,
+    },
+}
+
+static SOURCE: OnceLock<SettingsSource> = OnceLock::new();
+
+/// Read the extra settings from the source instead of the config directory. Must be called
+/// before the settings are used.
+pub fn set_source(source: SettingsSource) -> Result<(), Error> {
+    SOURCE
+        .set(source)
+        .map_err(|_| Error::msg("Extra settings source is already in use!"))
+}
+
+pub fn source() -> &'static SettingsSource {
+    SOURCE.get_or_init(|| SettingsSource::Local)
+}
+
+/// Local file of the extra settings, the last fetched copy for a URL
+pub fn settings_path() -> PathBuf {
+    match source() {
+        SettingsSource::Local => dirs::config_path("settings.json"),
+        SettingsSource::Path(path) => path.clone(),
+        SettingsSource::Url { .. } => dirs::cache_path("remote-settings.json"),
+    }
+}
+
+async fn fetch(url: &Url, header: Option<&(String, String)>) -> Result<String, Error> {
+    let mut request = utils::http::client()?.get(url.clone());
+    if let Some((name, value)) = header {
+        request = request.header(name, value);
+    }
+    Ok(request.send().await?.error_for_status()?.text().await?)
+}
+
+/// Email of the settings block used for the users without their own settings, and as the base
+/// of the other blocks
+pub const DEFAULT_EMAIL: &str = "default";
+
+type Fields = Map<String, Value>;
+
+/// Fields of the user block over the default fields
+fn with_defaults(defaults: &Fields, user: Value) -> Value {
+    match user {
+        Value::Object(fields) => {
+            let mut merged = defaults.clone();
+            merged.extend(fields);
+            Value::Object(merged)
+        }
+        other => other,
+    }
+}
+
+/// Value of an empty field of the same type as the default, a list or the policy mapping,
+/// otherwise null
+fn empty_like(key: &str, default: &Value) -> Value {
+    match default {
+        Value::Array(_) => json!([]),
+        Value::Object(_) if key == "policyMapping" => json!({}),
+        _ => Value::Null,
+    }
+}
+
+pub struct GlobalSettings {
+    /// Settings of the users with the defaults applied
     settings: Vec<ExtraSettings>,
+    /// Fields of the default block without the email, as written in the file
+    defaults: Option<Fields>,
+    default_settings: Option<ExtraSettings>,
 }
 
 impl GlobalSettings {
-    async fn read_extra_settings() -> Result<Option<Vec<ExtraSettings>>, Error> {
-        let path = ".settings.json";
-        if metadata(path).await.is_err() {
-            println!("Extra settings file doesn't exist.");
+    async fn read_extra_settings() -> Result<Option<String>, Error> {
+        let path = settings_path();
+        if let SettingsSource::Url { url, header } = source() {
+            match fetch(url, header.as_ref()).await {
+                Ok(json) => dirs::write_atomic(&path, json.as_bytes())?,
+                Err(e) => eprintln!(
+                    "Fetching the extra settings from {url} failed, using the last fetched copy: {e}"
+                ),
+            }
+        }
+        if metadata(&path).await.is_err() {
+            eprintln!("Extra settings file doesn't exist.");
             return Ok(None);
         }
-        let mut settings = File::open(path).await?;
+        let mut settings = File::open(&path).await?;
         let mut json = String::new();
         settings.read_to_string(&mut json).await?;
-        let data: Vec<ExtraSettings> = serde_json::from_str(&json)?;
-        Ok(Some(data))
+        let (data, problems) = validation::validate(&json);
+        for problem in problems.iter().filter(|p| p.severity == Severity::Warning) {
+            eprintln!("{}: {problem}", path.display());
+        }
+        if data.is_some() {
+            return Ok(Some(json));
+        }
+        let errors = problems
+            .iter()
+            .filter(|p| p.severity == Severity::Error)
+            .map(|p| p.to_string())
+            .collect::<Vec<String>>()
+            .join("\n");
+        Err(Error::msg(format!(
+            "Invalid extra settings in {}:\n{errors}\nCheck the file with `config validate`.",
+            path.display()
+        )))
+    }
+
+    fn from_json(json: &str) -> Result<Self, Error> {
+        let mut blocks: Vec<Value> = serde_json::from_str(json)?;
+        let defaults = blocks
+            .iter()
+            .position(|block| block["email"] == DEFAULT_EMAIL)
+            .map(|i| match blocks.remove(i) {
+                Value::Object(mut fields) => {
+                    fields.remove("email");
+                    fields
+                }
+                _ => Fields::new(),
+            });
+        let default_settings = defaults
+            .as_ref()
+            .map(|defaults| {
+                serde_json::from_value(with_defaults(defaults, json!({ "email": DEFAULT_EMAIL })))
+            })
+            .transpose()?;
+        let settings = blocks
+            .into_iter()
+            .map(|block| {
+                let block = match &defaults {
+                    Some(defaults) => with_defaults(defaults, block),
+                    None => block,
+                };
+                serde_json::from_value(block)
+            })
+            .collect::<Result<Vec<ExtraSettings>, _>>()?;
+        Ok(Self {
+            settings,
+            defaults,
+            default_settings,
+        })
+    }
+
+    pub async fn create_settings() -> Result<GlobalSettings, Error> {
+        match Self::read_extra_settings().await? {
+            Some(json) => Self::from_json(&json),
+            None => Ok(Self {
+                settings: Vec::with_capacity(0),
+                defaults: None,
+                default_settings: None,
+            }),
+        }
+    }
+
+    /// Replace the settings of the user, or add them if missing
+    pub fn set_user_settings(&mut self, user_settings: ExtraSettings) {
+        match self
+            .settings
+            .iter_mut()
+            .find(|single_settings| single_settings.email == user_settings.email)
+        {
+            Some(existing) => *existing = user_settings,
+            None => self.settings.push(user_settings),
+        }
+    }
+
+    pub async fn save(&self) -> Result<(), Error> {
+        if let SettingsSource::Url { url, .. } = source() {
+            return Err(Error::msg(format!(
+                "The extra settings are fetched from {url} and can't be saved, edit the shared file instead!"
+            )));
+        }
+        let mut blocks = Vec::with_capacity(self.settings.len() + 1);
+        if let Some(defaults) = &self.defaults {
+            let mut block = Fields::new();
+            block.insert("email".into(), DEFAULT_EMAIL.into());
+            block.extend(defaults.clone());
+            blocks.push(Value::Object(block));
+        }
+        for user_settings in &self.settings {
+            let mut block = serde_json::to_value(user_settings)?;
+            // Fields equal to the defaults are left out, so that later changes of the defaults
+            // still apply to the user
+            if let (Some(defaults), Value::Object(fields)) = (&self.defaults, &mut block) {
+                // Empty fields are not serialized, but must stay empty over the defaults
+                let emptied = defaults
+                    .iter()
+                    .filter(|(key, _)| !fields.contains_key(*key))
+                    .map(|(key, default)| (key.clone(), empty_like(key, default)))
+                    .collect::<Vec<_>>();
+                fields.extend(emptied);
+                fields.retain(|key, value| key == "email" || defaults.get(key) != Some(value));
+            }
+            blocks.push(block);
+        }
+
+        let json = serde_json::to_string_pretty(&blocks)?;
+        dirs::write_atomic(&settings_path(), json.as_bytes())
+    }
+
+    /// Settings of the only user in the file, for when the email is not known
+    pub fn only_user_settings(&self) -> Option<ExtraSettings> {
+        match self.settings.as_slice() {
+            [user_settings] => Some(user_settings.clone()),
+            [] => self.default_settings.clone(),
+            _ => None,
+        }
+    }
+
+    /// Whether the user has an own settings block
+    pub fn has_user_settings(&self, email: &str) -> bool {
+        self.settings
+            .iter()
+            .any(|single_settings| single_settings.email == email)
     }
 
-    pub(crate) async fn create_settings() -> Result<GlobalSettings, Error> {
-        let settings = match Self::read_extra_settings().await? {
-            Some(settings) => settings,
-            None => Vec::with_capacity(0),
-        };
-        Ok(Self { settings })
+    /// Settings of the default block for the user, or no options without the block
+    pub fn default_user_settings(&self, email: &str) -> ExtraSettings {
+        match &self.default_settings {
+            Some(default_settings) => {
+                let mut user_settings = default_settings.clone();
+                user_settings.email = email.to_owned();
+                user_settings
+            }
+            None => ExtraSettings::new(email),
+        }
     }
 
-    pub(crate) fn get_user_settings(&self, email: &str) -> Option<ExtraSettings> {
+    /// Settings of the user, or the default settings if the user has none
+    pub fn get_user_settings(&self, email: &str) -> Option<ExtraSettings> {
         self.settings
             .iter()
             .find(|single_settings| single_settings.email == email)
             .cloned()
+            .or_else(|| {
+                self.default_settings
+                    .as_ref()
+                    .map(|_| self.default_user_settings(email))
+            })
     }
 }
@@ -0,0 +1,90 @@
+use super::schema::{
+    CustomDayCounting, DayType, ExtraSettings, Pattern, SickLeavePolicy, UnpaidLeavePolicy,
+};
+use crate::models::Day;
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// How a classified day counts in the balance
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum DayEffect {
+    /// Left out of the expected time, e.g. vacations
+    NotExpected,
+    /// An expected working day, e.g. flex time off reduces the balance
+    Expected,
+    /// An expected working day credited with the expected time as worked
+    Credited,
+    /// Left out of the expected time, logged time only counts beyond the expected time
+    PartialCredit,
+}
+
+/// Rule of the day classification pipeline, the first matching rule decides the effect
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DayRule {
+    name: String,
+    #[serde(rename = "type")]
+    type_: DayType,
+    /// Only the days with a matching note of the time off request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pattern: Option<Pattern>,
+    /// Open-ended without a start or an end
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    date_start: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    date_end: Option<NaiveDate>,
+    /// Only these weekdays, every day when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    weekdays: Vec<Weekday>,
+    effect: DayEffect,
+}
+
+impl DayRule {
+    pub fn type_(&self) -> &DayType {
+        &self.type_
+    }
+
+    pub fn range(&self) -> Option<(NaiveDate, NaiveDate)> {
+        self.date_start.zip(self.date_end)
+    }
+
+    pub fn effect(&self) -> DayEffect {
+        self.effect
+    }
+
+    pub fn matches(&self, day: &Day) -> bool {
+        let date = day.date();
+        self.type_ == day.type_()
+            && self.date_start.is_none_or(|start| start <= date)
+            && self.date_end.is_none_or(|end| date <= end)
+            && (self.weekdays.is_empty() || self.weekdays.contains(&date.weekday()))
+            && match &self.pattern {
+                Some(pattern) => day.note().is_some_and(|note| pattern.is_match(note)),
+                None => true,
+            }
+    }
+}
+
+/// Effect of the day type when no rule matches, from the policies of the settings
+pub fn default_effect(settings: &ExtraSettings, type_: &DayType) -> DayEffect {
+    match type_ {
+        DayType::PublicHoliday | DayType::ParentalLeave | DayType::Vacation => {
+            DayEffect::NotExpected
+        }
+        DayType::SickLeave => match settings.sick_leave_policy() {
+            SickLeavePolicy::Worked => DayEffect::NotExpected,
+            SickLeavePolicy::NotWorked => DayEffect::Expected,
+            SickLeavePolicy::Partial => DayEffect::PartialCredit,
+        },
+        DayType::UnpaidLeave => match settings.unpaid_leave_policy() {
+            UnpaidLeavePolicy::NotExpected => DayEffect::NotExpected,
+            UnpaidLeavePolicy::Expected => DayEffect::Expected,
+        },
+        DayType::Custom(name) => match settings.custom_day_type(name).map(|t| t.counting) {
+            Some(CustomDayCounting::Worked) | None => DayEffect::Credited,
+            Some(CustomDayCounting::ReducesExpected) => DayEffect::NotExpected,
+            Some(CustomDayCounting::Ignored) => DayEffect::Expected,
+        },
+        DayType::WorkingDay | DayType::Flex | DayType::Unknown => DayEffect::Expected,
+    }
+}
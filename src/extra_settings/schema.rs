@@ -1,78 +1,1083 @@
-use crate::models::Day;
-use chrono::NaiveDate;
-use serde::{Deserialize, Serialize};
+use super::rules::{self, DayEffect, DayRule};
+use crate::clockify::TimeOffType;
+use crate::models::{Day, WorkDay, WorkItem};
+use crate::utils;
+use chrono::{Datelike, Months, NaiveDate, Weekday};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+use url::Url;
 
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
-pub(crate) enum DayType {
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub enum DayType {
     WorkingDay,
     SickLeave,
     ParentalLeave,
     PublicHoliday,
     Vacation,
     Flex,
+    UnpaidLeave,
     Unknown,
+    /// Name of a custom day type, e.g. `"SelfImprovement"`
+    #[serde(untagged)]
+    Custom(String),
+}
+
+/// Regular expression matched anywhere in the text
+#[derive(Clone, Debug)]
+pub struct Pattern(Regex);
+
+impl Serialize for Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+impl Pattern {
+    pub fn is_match(&self, text: &str) -> bool {
+        self.0.is_match(text)
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Regex::new(&pattern)
+            .map(Pattern)
+            .map_err(serde::de::Error::custom)
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub(crate) struct IgnoreItem {
+pub struct IgnoreItem {
     name: String,
+    #[serde(default)]
     description: String,
-    date_start: NaiveDate,
-    date_end: NaiveDate,
+    /// Open-ended without a start or an end
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    date_start: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    date_end: Option<NaiveDate>,
     #[serde(rename = "type")]
     type_: DayType,
+    /// Only the time off with a matching note, or the entries with a matching description for
+    /// the WorkingDay type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pattern: Option<Pattern>,
+}
+
+impl IgnoreItem {
+    fn contains(&self, date: NaiveDate) -> bool {
+        self.date_start.is_none_or(|start| start <= date)
+            && self.date_end.is_none_or(|end| date <= end)
+    }
+
+    /// Items without a pattern match any text
+    fn matches(&self, date: NaiveDate, type_: &DayType, text: Option<&str>) -> bool {
+        self.contains(date)
+            && self.type_ == *type_
+            && match &self.pattern {
+                Some(pattern) => text.is_some_and(|text| pattern.is_match(text)),
+                None => true,
+            }
+    }
+}
+
+/// Expected hours of a date range, or of single dates. Both can be limited to weekdays.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpectedWorkingHours {
+    name: String,
+    #[serde(default)]
+    description: String,
+    /// Open-ended without a start or an end
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    date_start: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    date_end: Option<NaiveDate>,
+    /// Single dates instead of the range, e.g. a shortened Christmas Eve
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    dates: Vec<NaiveDate>,
+    /// Only these weekdays, e.g. `["Fri"]`, every day when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    weekdays: Vec<Weekday>,
+    hours_per_day: f32,
+}
+
+impl ExpectedWorkingHours {
+    pub fn new(
+        name: String,
+        date_start: NaiveDate,
+        date_end: NaiveDate,
+        hours_per_day: f32,
+    ) -> Self {
+        Self {
+            name,
+            description: String::new(),
+            date_start: Some(date_start),
+            date_end: Some(date_end),
+            dates: Vec::new(),
+            weekdays: Vec::new(),
+            hours_per_day,
+        }
+    }
+
+    fn start(&self) -> NaiveDate {
+        match self.dates.iter().min() {
+            Some(date) => *date,
+            None => self.date_start.unwrap_or(NaiveDate::MIN),
+        }
+    }
+
+    fn end(&self) -> NaiveDate {
+        match self.dates.iter().max() {
+            Some(date) => *date,
+            None => self.date_end.unwrap_or(NaiveDate::MAX),
+        }
+    }
+
+    /// Dates of the range, open ends are shown as `open`
+    fn range(&self) -> String {
+        let date = |date: Option<NaiveDate>| date.map_or("open".to_string(), |d| d.to_string());
+        match (self.dates.iter().min(), self.dates.iter().max()) {
+            (Some(first), Some(last)) => format!("{first} - {last}"),
+            _ => format!("{} - {}", date(self.date_start), date(self.date_end)),
+        }
+    }
+
+    /// Every day of the range, without single dates or weekdays
+    fn is_plain_range(&self) -> bool {
+        self.dates.is_empty() && self.weekdays.is_empty()
+    }
+
+    fn matches(&self, day: &NaiveDate) -> bool {
+        let in_dates = if self.dates.is_empty() {
+            self.start() <= *day && *day <= self.end()
+        } else {
+            self.dates.contains(day)
+        };
+        in_dates && (self.weekdays.is_empty() || self.weekdays.contains(&day.weekday()))
+    }
+
+    /// Lower is more specific: single dates, then weekdays of a range, then the shortest range
+    fn specificity(&self) -> (u8, i64) {
+        let kind = if !self.dates.is_empty() {
+            0
+        } else if !self.weekdays.is_empty() {
+            1
+        } else {
+            2
+        };
+        (kind, (self.end() - self.start()).num_days())
+    }
+}
+
+/// Weeks with the same expected hours per weekday
+#[derive(Clone, Debug)]
+pub struct SchedulePeriod {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    /// Monday to Friday
+    pub hours: [f32; 5],
+}
+
+impl fmt::Display for SchedulePeriod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hours = ["Mon", "Tue", "Wed", "Thu", "Fri"]
+            .iter()
+            .zip(self.hours)
+            .map(|(weekday, hours)| {
+                let (hours, minutes) = utils::hours_to_hours_and_minutes(hours);
+                format!("{weekday} {hours}:{minutes:02}")
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        write!(f, "{} - {}: {hours}", self.start, self.end)
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub(crate) struct ExpectedWorkingHours {
+pub struct PartialParentalLeave {
     name: String,
     description: String,
     date_start: NaiveDate,
     date_end: NaiveDate,
-    hours_per_day: f32,
+    /// Share of the normal working hours still worked, e.g. 50.0
+    work_percentage: f32,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayoutRule {
+    /// Balance hours above this are paid out
+    pub above_hours: f32,
+    pub multiplier: f32,
+}
+
+/// Enhanced accrual of the time logged on a day, e.g. beyond 10 hours or on public holidays
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OvertimeRule {
+    #[serde(default)]
+    pub name: String,
+    /// Only the time beyond these hours of the day, all of it when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub above_daily_hours: Option<f32>,
+    /// Only the days of the type, `WorkingDay` for the days without time off or a holiday.
+    /// Every day when missing.
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub type_: Option<DayType>,
+    pub multiplier: f32,
+}
+
+impl OvertimeRule {
+    fn threshold_secs(&self) -> i64 {
+        self.above_daily_hours
+            .map_or(0, |hours| (hours * 3600.0) as i64)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceGoal {
+    pub name: String,
+    /// Target balance in hours
+    pub hours: f32,
+    /// Date by which the balance should be reached
+    pub until: NaiveDate,
+}
+
+/// Period of a project budget, the calendar month, quarter or year of the date
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum BudgetPeriod {
+    Month,
+    #[default]
+    Quarter,
+    Year,
+}
+
+impl BudgetPeriod {
+    /// First and last day of the period containing the date
+    pub fn range(&self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let months = match self {
+            Self::Month => 1,
+            Self::Quarter => 3,
+            Self::Year => 12,
+        };
+        let month0 = date.month0() / months * months;
+        let start = NaiveDate::from_ymd_opt(date.year(), month0 + 1, 1).unwrap();
+        let end = start
+            .checked_add_months(Months::new(months))
+            .and_then(|next| next.pred_opt())
+            .unwrap();
+        (start, end)
+    }
+}
+
+/// Hours budgeted for the time logged on a project in every period
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectBudget {
+    /// Exact name of the project
+    pub project: String,
+    pub hours: f32,
+    #[serde(default)]
+    pub period: BudgetPeriod,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum RoundingTarget {
+    /// Every time entry is rounded
+    #[default]
+    Entry,
+    /// The logged time of the day is rounded
+    Day,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum RoundingDirection {
+    Up,
+    Down,
+    #[default]
+    Nearest,
+}
+
+/// Rounding of the logged time, e.g. to match a payroll system that rounds timesheets
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rounding {
+    /// Rounded to a multiple of the minutes, e.g. 5 or 15
+    pub minutes: u32,
+    #[serde(default)]
+    pub per: RoundingTarget,
+    #[serde(default)]
+    pub direction: RoundingDirection,
+}
+
+impl Rounding {
+    pub fn round(&self, secs: i64) -> i64 {
+        let step = 60 * i64::from(self.minutes.max(1));
+        let rounded = match self.direction {
+            RoundingDirection::Up => secs + step - 1,
+            RoundingDirection::Down => secs,
+            RoundingDirection::Nearest => secs + step / 2,
+        };
+        rounded.div_euclid(step) * step
+    }
+}
+
+/// Break deducted from the long days, when breaks are not tracked in Clockify
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreakDeduction {
+    /// Days with more logged hours than this
+    pub above_hours: f32,
+    pub minutes: u32,
+}
+
+fn default_warn_days() -> u32 {
+    30
+}
+
+/// Employer policy on the flex balance, e.g. a cap of 40 hours or an expiry of 6 months
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalancePolicy {
+    /// Hours above the cap are lost when they are earned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cap_hours: Option<f32>,
+    /// Hours not used within the months are lost, the oldest hours are used first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expiry_months: Option<u32>,
+    /// Hours lost within the days are warned about
+    #[serde(default = "default_warn_days")]
+    pub warn_days: u32,
+}
+
+/// Balance limits in hours that trigger a notification when crossed
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BalanceThresholds {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub below: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub above: Option<f32>,
+}
+
+impl BalanceThresholds {
+    /// Describe the threshold the balance crossed since the previous balance, in seconds.
+    /// Without a previous balance, a balance beyond a threshold counts as crossed.
+    pub fn crossed(&self, previous: Option<i64>, balance: i64) -> Option<String> {
+        let secs = |hours: f32| (hours * 3600.0) as i64;
+        if let Some(below) = self.below {
+            if balance < secs(below) && previous.is_none_or(|p| p >= secs(below)) {
+                return Some(format!("below {below} hours"));
+            }
+        }
+        if let Some(above) = self.above {
+            if balance > secs(above) && previous.is_none_or(|p| p <= secs(above)) {
+                return Some(format!("above {above} hours"));
+            }
+        }
+        None
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub enum Accrual {
+    /// Whole allowance at the start of the year
+    #[default]
+    Yearly,
+    /// A twelfth of the allowance for every started month
+    Monthly,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VacationAllowance {
+    pub days_per_year: f32,
+    #[serde(default)]
+    pub accrual: Accrual,
+    /// Unused days carried over from the previous year
+    #[serde(default)]
+    pub carried_over_days: f32,
+}
+
+impl VacationAllowance {
+    /// Vacation days earned in the year by the given date
+    pub fn earned_by(&self, date: &NaiveDate) -> f32 {
+        let earned = match self.accrual {
+            Accrual::Yearly => self.days_per_year,
+            Accrual::Monthly => self.days_per_year * date.month() as f32 / 12.0,
+        };
+        earned + self.carried_over_days
+    }
+}
+
+/// How time logged on weekends is counted: `"count"`, `"ignore"` or a multiplier, e.g. `1.5`
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(try_from = "WeekendWorkValue", into = "WeekendWorkValue")]
+pub enum WeekendWork {
+    /// Counted as worked like on weekdays
+    #[default]
+    Count,
+    /// Not counted as worked
+    Ignore,
+    Multiplier(f32),
+}
+
+impl WeekendWork {
+    /// Seconds counted as worked of the seconds logged on a weekend
+    pub fn counted_secs(&self, secs: i64) -> i64 {
+        match self {
+            Self::Count => secs,
+            Self::Ignore => 0,
+            Self::Multiplier(multiplier) => (secs as f32 * multiplier) as i64,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum WeekendWorkValue {
+    Number(f32),
+    Text(String),
+}
+
+impl TryFrom<WeekendWorkValue> for WeekendWork {
+    type Error = String;
+
+    fn try_from(value: WeekendWorkValue) -> Result<Self, Self::Error> {
+        match value {
+            WeekendWorkValue::Number(multiplier) if multiplier >= 0.0 => {
+                Ok(Self::Multiplier(multiplier))
+            }
+            WeekendWorkValue::Text(text) if text == "count" => Ok(Self::Count),
+            WeekendWorkValue::Text(text) if text == "ignore" => Ok(Self::Ignore),
+            WeekendWorkValue::Number(multiplier) => Err(format!(
+                "invalid weekendWork {multiplier}, a multiplier can't be negative"
+            )),
+            WeekendWorkValue::Text(text) => Err(format!(
+                "invalid weekendWork '{text}', expected \"count\", \"ignore\" or a multiplier"
+            )),
+        }
+    }
+}
+
+impl From<WeekendWork> for WeekendWorkValue {
+    fn from(value: WeekendWork) -> Self {
+        match value {
+            WeekendWork::Count => Self::Text("count".into()),
+            WeekendWork::Ignore => Self::Text("ignore".into()),
+            WeekendWork::Multiplier(multiplier) => Self::Number(multiplier),
+        }
+    }
 }
 
+/// How the time of a custom day type counts in the balance
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum CustomDayCounting {
+    /// Counts as worked time, time off days are credited with the expected time
+    #[default]
+    Worked,
+    /// Logged time is deducted from the expected time, time off days are not expected
+    ReducesExpected,
+    /// Logged time is not counted, time off days are expected working days
+    Ignored,
+}
+
+/// Day type of its own for the time logged on the projects or the time off of the policies,
+/// shown as separate rows in the results
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub(crate) struct ExtraSettings {
+pub struct CustomDayType {
+    pub name: String,
+    /// Project names of the time entries
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub projects: Vec<String>,
+    /// Time off policy names or IDs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub policies: Vec<String>,
+    #[serde(default)]
+    pub counting: CustomDayCounting,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub enum HolidayObservance {
+    /// Holidays are observed on their dates, weekend holidays are not moved
+    #[default]
+    Nominal,
+    /// Weekend holidays are observed on the next free weekday
+    NextWeekday,
+    /// Saturday holidays are observed on Friday and Sunday holidays on Monday
+    NearestWeekday,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub enum UnpaidLeavePolicy {
+    /// Unpaid leave days are left out of the expected time
+    #[default]
+    NotExpected,
+    /// Unpaid leave days are expected working days and reduce the balance like flex time off
+    Expected,
+}
+
+/// How sick days are credited: `true`, `false` or `"partial"` in the settings
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(try_from = "SickLeavePolicyValue", into = "SickLeavePolicyValue")]
+pub enum SickLeavePolicy {
+    /// Sick days are left out of the expected time, as if they were worked
+    #[default]
+    Worked,
+    /// Sick days are expected working days and reduce the balance
+    NotWorked,
+    /// Sick days are credited up to the expected time, logged time only counts beyond it
+    Partial,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum SickLeavePolicyValue {
+    Bool(bool),
+    Text(String),
+}
+
+impl TryFrom<SickLeavePolicyValue> for SickLeavePolicy {
+    type Error = String;
+
+    fn try_from(value: SickLeavePolicyValue) -> Result<Self, Self::Error> {
+        match value {
+            SickLeavePolicyValue::Bool(true) => Ok(Self::Worked),
+            SickLeavePolicyValue::Bool(false) => Ok(Self::NotWorked),
+            SickLeavePolicyValue::Text(text) if text == "partial" => Ok(Self::Partial),
+            SickLeavePolicyValue::Text(text) => Err(format!(
+                "invalid sickLeaveCountsAsWorked '{text}', expected true, false or \"partial\""
+            )),
+        }
+    }
+}
+
+impl From<SickLeavePolicy> for SickLeavePolicyValue {
+    fn from(value: SickLeavePolicy) -> Self {
+        match value {
+            SickLeavePolicy::Worked => Self::Bool(true),
+            SickLeavePolicy::NotWorked => Self::Bool(false),
+            SickLeavePolicy::Partial => Self::Text("partial".into()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtraSettings {
     pub email: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     ignore_items: Vec<IgnoreItem>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     expected_working_hours: Vec<ExpectedWorkingHours>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    partial_parental_leaves: Vec<PartialParentalLeave>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    payout_rules: Vec<PayoutRule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    overtime_rules: Vec<OvertimeRule>,
+    /// Maximum working hours of a single day, respected when planning
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_daily_hours: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    break_deduction: Option<BreakDeduction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rounding: Option<Rounding>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    vacation_allowance: Option<VacationAllowance>,
+    #[serde(default, rename = "sickLeaveCountsAsWorked")]
+    sick_leave_policy: SickLeavePolicy,
+    #[serde(default, rename = "unpaidLeave")]
+    unpaid_leave_policy: UnpaidLeavePolicy,
+    #[serde(default)]
+    weekend_work: WeekendWork,
+    /// Time off policy names or IDs mapped to types
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    policy_mapping: HashMap<String, TimeOffType>,
+    /// Day type precedence on dates with several classifications, first wins
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    day_precedence: Vec<DayType>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    custom_day_types: Vec<CustomDayType>,
+    /// Effects of the classified days, the first matching rule wins over the policies
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    day_rules: Vec<DayRule>,
+    /// Time logged on these projects is not counted as worked
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    exclude_projects: Vec<String>,
+    /// Time logged with any of these tags is not counted as worked
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    exclude_tags: Vec<String>,
+    #[serde(default)]
+    holiday_observance: HolidayObservance,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    goals: Vec<BalanceGoal>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    project_budgets: Vec<ProjectBudget>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    balance_thresholds: Option<BalanceThresholds>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    balance_policy: Option<BalancePolicy>,
+    /// Summary of the results is posted here after the calculation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notify_webhook: Option<Url>,
 }
 
 impl ExtraSettings {
-    pub(crate) fn empty() -> Self {
+    /// Settings of the email without any options
+    pub fn new(email: &str) -> Self {
+        Self {
+            email: email.to_string(),
+            ..Self::empty()
+        }
+    }
+
+    pub fn empty() -> Self {
         Self {
             email: String::with_capacity(0),
             ignore_items: Vec::with_capacity(0),
             expected_working_hours: Vec::with_capacity(0),
+            partial_parental_leaves: Vec::with_capacity(0),
+            payout_rules: Vec::with_capacity(0),
+            overtime_rules: Vec::with_capacity(0),
+            max_daily_hours: None,
+            break_deduction: None,
+            rounding: None,
+            vacation_allowance: None,
+            sick_leave_policy: SickLeavePolicy::Worked,
+            unpaid_leave_policy: UnpaidLeavePolicy::NotExpected,
+            weekend_work: WeekendWork::Count,
+            policy_mapping: HashMap::new(),
+            day_precedence: Vec::new(),
+            custom_day_types: Vec::new(),
+            day_rules: Vec::new(),
+            exclude_projects: Vec::new(),
+            exclude_tags: Vec::new(),
+            holiday_observance: HolidayObservance::Nominal,
+            goals: Vec::new(),
+            project_budgets: Vec::new(),
+            balance_thresholds: None,
+            balance_policy: None,
+            notify_webhook: None,
         }
     }
 
-    pub(crate) fn is_ignored(&self, day: &Day) -> bool {
-        let ignored = self.ignore_items.iter().any(|item| {
-            item.date_start <= day.date()
-                && item.date_end >= day.date()
-                && day.type_() == item.type_
-        });
+    fn matches_ignore_item(&self, date: NaiveDate, type_: DayType, text: Option<&str>) -> bool {
+        self.ignore_items
+            .iter()
+            .any(|item| item.matches(date, &type_, text))
+    }
+
+    pub fn is_ignored(&self, day: &Day) -> bool {
+        let ignored = self.matches_ignore_item(day.date(), day.type_(), day.note());
         if ignored {
             log::info!("Ignore day: {:?}", day)
         }
         ignored
     }
 
-    /// Return expected working seconds, if expectedWorkingHours is preset for the day
-    pub(crate) fn expected_working_secs(&self, day: &NaiveDate) -> Option<i64> {
-        if let Some(found) = self
-            .expected_working_hours
+    /// Logged time is ignored with the WorkingDay type. Items with a pattern drop only the
+    /// matching entries, see `is_excluded`.
+    pub fn is_ignored_work_day(&self, work_day: &WorkDay) -> bool {
+        let ignored = self.matches_ignore_item(work_day.date, DayType::WorkingDay, None);
+        if ignored {
+            log::info!("Ignore working day: {}", work_day.date)
+        }
+        ignored
+    }
+
+    /// Expected hours of the most specific matching entry, the first one of equally specific
+    pub fn expected_working_secs(&self, day: &NaiveDate) -> Option<i64> {
+        self.expected_working_hours
             .iter()
-            .find(|i| i.date_end >= *day && i.date_start <= *day)
-        {
-            return Some((found.hours_per_day * 3600f32) as i64);
+            .filter(|hours| hours.matches(day))
+            .min_by_key(|hours| hours.specificity())
+            .map(|found| (found.hours_per_day * 3600f32) as i64)
+    }
+
+    pub fn payout_rules(&self) -> &[PayoutRule] {
+        &self.payout_rules
+    }
+
+    /// Extra seconds of the overtime rules for the time logged on a day of the type. The
+    /// highest multiplier of the matching rules applies to each part of the day. Weekend time
+    /// not counted as worked earns nothing.
+    pub fn overtime_bonus_secs(
+        &self,
+        date: &NaiveDate,
+        day_type: &DayType,
+        worked_sec: i64,
+    ) -> i64 {
+        if self.counted_work_secs(date, worked_sec) == 0 {
+            return 0;
         }
+        let rules = self
+            .overtime_rules
+            .iter()
+            .filter(|rule| rule.type_.as_ref().is_none_or(|t| t == day_type))
+            .collect::<Vec<_>>();
+        let mut bounds = rules
+            .iter()
+            .map(|rule| rule.threshold_secs())
+            .filter(|secs| *secs < worked_sec)
+            .chain([0, worked_sec])
+            .collect::<Vec<_>>();
+        bounds.sort_unstable();
+        bounds.dedup();
+        bounds
+            .windows(2)
+            .map(|part| {
+                let multiplier = rules
+                    .iter()
+                    .filter(|rule| rule.threshold_secs() <= part[0])
+                    .map(|rule| rule.multiplier)
+                    .reduce(f32::max)
+                    .unwrap_or(1.0);
+                ((part[1] - part[0]) as f32 * (multiplier - 1.0)) as i64
+            })
+            .sum()
+    }
 
-        None
+    /// Break deducted from the seconds logged on a day
+    pub fn break_secs(&self, secs: i64) -> i64 {
+        match &self.break_deduction {
+            Some(deduction) if secs > (deduction.above_hours * 3600.0) as i64 => {
+                60 * i64::from(deduction.minutes)
+            }
+            _ => 0,
+        }
+    }
+
+    /// Logged seconds of the day with the rounding applied. With rounded entries, the
+    /// overlapping time is still counted once.
+    pub fn rounded_secs(&self, work_day: &WorkDay) -> i64 {
+        let secs = work_day.duration();
+        match &self.rounding {
+            Some(rounding) if rounding.per == RoundingTarget::Entry => {
+                let raw = work_day.items.iter().map(WorkItem::duration);
+                let overlap = raw.clone().sum::<i64>() - secs;
+                raw.map(|secs| rounding.round(secs)).sum::<i64>() - overlap
+            }
+            Some(rounding) => rounding.round(secs),
+            None => secs,
+        }
+    }
+
+    /// Logged seconds of the day as counted, rounded and with the break deducted
+    pub fn logged_secs(&self, work_day: &WorkDay) -> i64 {
+        let secs = self.rounded_secs(work_day);
+        secs - self.break_secs(secs)
+    }
+
+    pub fn max_daily_hours(&self) -> Option<f32> {
+        self.max_daily_hours
+    }
+
+    pub fn balance_thresholds(&self) -> Option<&BalanceThresholds> {
+        self.balance_thresholds.as_ref()
+    }
+
+    pub fn balance_policy(&self) -> Option<&BalancePolicy> {
+        self.balance_policy.as_ref()
+    }
+
+    pub fn notify_webhook(&self) -> Option<&Url> {
+        self.notify_webhook.as_ref()
+    }
+
+    pub fn goals(&self) -> &[BalanceGoal] {
+        &self.goals
+    }
+
+    pub fn project_budgets(&self) -> &[ProjectBudget] {
+        &self.project_budgets
+    }
+
+    /// Add the goal or replace the one with the same name
+    pub fn set_goal(&mut self, goal: BalanceGoal) {
+        match self.goals.iter_mut().find(|g| g.name == goal.name) {
+            Some(existing) => *existing = goal,
+            None => self.goals.push(goal),
+        }
+        self.goals.sort_by_key(|g| g.until);
+    }
+
+    /// Remove the goal by name, returns false if it didn't exist
+    pub fn remove_goal(&mut self, name: &str) -> bool {
+        let count = self.goals.len();
+        self.goals.retain(|g| g.name != name);
+        self.goals.len() != count
+    }
+
+    pub fn vacation_allowance(&self) -> Option<&VacationAllowance> {
+        self.vacation_allowance.as_ref()
+    }
+
+    /// Seconds counted as worked of the seconds logged on the date
+    pub fn counted_work_secs(&self, date: &NaiveDate, secs: i64) -> i64 {
+        if utils::is_weekday(date) {
+            secs
+        } else {
+            self.weekend_work.counted_secs(secs)
+        }
+    }
+
+    pub fn sick_leave_policy(&self) -> SickLeavePolicy {
+        self.sick_leave_policy.clone()
+    }
+
+    pub fn unpaid_leave_policy(&self) -> UnpaidLeavePolicy {
+        self.unpaid_leave_policy.clone()
+    }
+
+    /// Add the excluded projects and tags given as arguments
+    pub fn with_exclusions(mut self, projects: &[String], tags: &[String]) -> Self {
+        self.exclude_projects.extend_from_slice(projects);
+        self.exclude_tags.extend_from_slice(tags);
+        self
+    }
+
+    pub fn is_excluded(&self, item: &WorkItem) -> bool {
+        self.exclude_projects.iter().any(|p| p == item.project())
+            || item.tags().iter().any(|t| self.exclude_tags.contains(t))
+            || self.ignore_items.iter().any(|ignore_item| {
+                ignore_item.pattern.is_some()
+                    && ignore_item.matches(
+                        item.start().date_naive(),
+                        &DayType::WorkingDay,
+                        Some(item.description()),
+                    )
+            })
+    }
+
+    /// Whether excluding the items needs their tags or descriptions, which the daily totals
+    /// of the summary report don't have
+    pub fn excludes_by_details(&self) -> bool {
+        !self.exclude_tags.is_empty() || self.ignore_items.iter().any(|i| i.pattern.is_some())
+    }
+
+    pub fn holiday_observance(&self) -> HolidayObservance {
+        self.holiday_observance.clone()
+    }
+
+    pub fn day_precedence(&self) -> &[DayType] {
+        &self.day_precedence
+    }
+
+    /// How the day counts in the balance: the first matching rule of dayRules, or the
+    /// policy of the day type
+    pub fn day_effect(&self, day: &Day) -> DayEffect {
+        self.day_rules
+            .iter()
+            .find(|rule| rule.matches(day))
+            .map_or_else(
+                || rules::default_effect(self, &day.type_()),
+                DayRule::effect,
+            )
+    }
+
+    pub fn custom_day_types(&self) -> &[CustomDayType] {
+        &self.custom_day_types
+    }
+
+    pub fn custom_day_type(&self, name: &str) -> Option<&CustomDayType> {
+        self.custom_day_types.iter().find(|t| t.name == name)
+    }
+
+    /// Custom day type of the time logged on the project of the item
+    pub fn custom_project_type(&self, item: &WorkItem) -> Option<&CustomDayType> {
+        self.custom_day_types
+            .iter()
+            .find(|t| t.projects.iter().any(|p| p == item.project()))
+    }
+
+    /// Type of a time off policy, mapped by ID or name. Custom day types come first, and
+    /// the default English names are used for the policies that are not mapped.
+    pub fn time_off_type(&self, policy_id: &str, policy_name: &str) -> Option<TimeOffType> {
+        let custom = self.custom_day_types.iter().find(|t| {
+            t.policies
+                .iter()
+                .any(|p| p == policy_id || p == policy_name)
+        });
+        if let Some(custom) = custom {
+            return Some(TimeOffType::Custom(custom.name.clone()));
+        }
+        self.policy_mapping
+            .get(policy_id)
+            .or(self.policy_mapping.get(policy_name))
+            .cloned()
+            .or_else(|| TimeOffType::from_policy_name(policy_name))
+    }
+
+    /// Set the expected hours of the period. Overlapping ranges are trimmed or split so that
+    /// the ranges stay disjoint.
+    pub fn set_expected_hours(&mut self, hours: ExpectedWorkingHours) {
+        let mut ranges = Vec::with_capacity(self.expected_working_hours.len() + 2);
+        for range in self.expected_working_hours.drain(..) {
+            // Single dates and weekdays are more specific, so they still apply
+            if !range.is_plain_range() || range.end() < hours.start() || range.start() > hours.end()
+            {
+                ranges.push(range);
+                continue;
+            }
+            if range.start() < hours.start() {
+                ranges.push(ExpectedWorkingHours {
+                    date_end: hours.start().pred_opt(),
+                    ..range.clone()
+                });
+            }
+            if range.end() > hours.end() {
+                ranges.push(ExpectedWorkingHours {
+                    date_start: hours.end().succ_opt(),
+                    ..range
+                });
+            }
+        }
+        ranges.push(hours);
+        ranges.sort_by_key(|r| r.start());
+        self.expected_working_hours = ranges;
+    }
+
+    /// Indices of the plain date ranges of the expected hours that overlap an earlier range
+    fn overlapping_ranges(&self) -> Vec<(usize, usize)> {
+        let hours = &self.expected_working_hours;
+        let mut overlapping = Vec::new();
+        for (j, b) in hours.iter().enumerate().filter(|(_, b)| b.is_plain_range()) {
+            for (i, a) in hours.iter().enumerate().take(j) {
+                if a.is_plain_range() && a.start() <= b.end() && b.start() <= a.end() {
+                    overlapping.push((i, j));
+                }
+            }
+        }
+        overlapping
+    }
+
+    /// Date ranges of the expected hours that overlap another range
+    pub fn overlapping_expected_hours(&self) -> Vec<String> {
+        let mut indices = self
+            .overlapping_ranges()
+            .into_iter()
+            .flat_map(|(i, j)| [i, j])
+            .collect::<Vec<usize>>();
+        indices.sort();
+        indices.dedup();
+        indices
+            .into_iter()
+            .map(|i| self.expected_working_hours[i].range())
+            .collect()
+    }
+
+    /// Date ranges that end before they start, overlapping expected hours and unknown day
+    /// types, as the field paths with the problems
+    pub fn range_problems(&self) -> Vec<(String, String)> {
+        let mut problems = Vec::new();
+        let mut check = |field: &str, index: usize, start: NaiveDate, end: NaiveDate| {
+            if start > end {
+                problems.push((
+                    format!("{field}[{index}]"),
+                    format!("dateStart {start} is after dateEnd {end}"),
+                ));
+            }
+        };
+        for (i, item) in self.ignore_items.iter().enumerate() {
+            if let (Some(start), Some(end)) = (item.date_start, item.date_end) {
+                check("ignoreItems", i, start, end);
+            }
+        }
+        for (i, hours) in self.expected_working_hours.iter().enumerate() {
+            if let (Some(start), Some(end)) = (hours.date_start, hours.date_end) {
+                check("expectedWorkingHours", i, start, end);
+            }
+        }
+        for (i, leave) in self.partial_parental_leaves.iter().enumerate() {
+            check("partialParentalLeaves", i, leave.date_start, leave.date_end);
+        }
+        for (i, rule) in self.day_rules.iter().enumerate() {
+            if let Some((start, end)) = rule.range() {
+                check("dayRules", i, start, end);
+            }
+        }
+
+        for (i, hours) in self.expected_working_hours.iter().enumerate() {
+            if !hours.dates.is_empty() && (hours.date_start.is_some() || hours.date_end.is_some()) {
+                problems.push((
+                    format!("expectedWorkingHours[{i}]"),
+                    "dateStart and dateEnd are ignored with dates".to_string(),
+                ));
+            }
+        }
+
+        let hours = &self.expected_working_hours;
+        for (i, j) in self.overlapping_ranges() {
+            let (a, b) = (&hours[i], &hours[j]);
+            problems.push((
+                format!("expectedWorkingHours[{j}]"),
+                format!(
+                    "{} overlaps expectedWorkingHours[{i}] {}, the shorter range wins",
+                    b.range(),
+                    a.range()
+                ),
+            ));
+        }
+
+        // Unknown names are parsed as custom day types, e.g. typos of the built-in types
+        let unknown_type = |type_: &DayType| match type_ {
+            DayType::Custom(name) if self.custom_day_type(name).is_none() => Some(name.clone()),
+            _ => None,
+        };
+        let types = self
+            .ignore_items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (format!("ignoreItems[{i}].type"), &item.type_))
+            .chain(
+                self.day_precedence
+                    .iter()
+                    .enumerate()
+                    .map(|(i, type_)| (format!("dayPrecedence[{i}]"), type_)),
+            )
+            .chain(
+                self.day_rules
+                    .iter()
+                    .enumerate()
+                    .map(|(i, rule)| (format!("dayRules[{i}].type"), rule.type_())),
+            );
+        for (field, type_) in types {
+            if let Some(name) = unknown_type(type_) {
+                problems.push((
+                    field,
+                    format!("{name} is not a day type or in customDayTypes"),
+                ));
+            }
+        }
+        problems
+    }
+
+    pub fn first_expected_hours_date(&self) -> Option<NaiveDate> {
+        self.expected_working_hours
+            .iter()
+            .map(|r| r.start())
+            .filter(|start| *start != NaiveDate::MIN)
+            .min()
+    }
+
+    /// Expected hours are configured instead of the default for every day
+    pub fn has_custom_schedule(&self) -> bool {
+        !self.expected_working_hours.is_empty() || !self.partial_parental_leaves.is_empty()
+    }
+
+    /// Return the worked share of the day (0.0 - 1.0), if partial parental leave is set for the day
+    pub fn parental_leave_work_ratio(&self, day: &NaiveDate) -> Option<f32> {
+        self.partial_parental_leaves
+            .iter()
+            .find(|i| i.date_end >= *day && i.date_start <= *day)
+            .map(|found| (found.work_percentage / 100f32).clamp(0f32, 1f32))
     }
 }
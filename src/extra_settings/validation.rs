@@ -0,0 +1,102 @@
+use super::schema::ExtraSettings;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// The settings can't be used
+    Error,
+    /// The settings are used, but probably not as intended
+    Warning,
+}
+
+#[derive(Clone, Debug)]
+pub struct Problem {
+    pub severity: Severity,
+    /// Field path of the problem, e.g. `[0].expectedWorkingHours[1]`
+    pub location: String,
+    pub message: String,
+}
+
+impl Problem {
+    fn error(location: String, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            location,
+            message,
+        }
+    }
+
+    fn warning(location: String, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            location,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{severity}: {}: {}", self.location, self.message)
+    }
+}
+
+/// Same format as the paths of serde_path_to_error
+fn ignored_path(path: &serde_ignored::Path) -> String {
+    match path {
+        serde_ignored::Path::Root => String::new(),
+        serde_ignored::Path::Seq { parent, index } => format!("{}[{index}]", ignored_path(parent)),
+        serde_ignored::Path::Map { parent, key } => match ignored_path(parent) {
+            parent if parent.is_empty() => key.clone(),
+            parent => format!("{parent}.{key}"),
+        },
+        serde_ignored::Path::Some { parent }
+        | serde_ignored::Path::NewtypeStruct { parent }
+        | serde_ignored::Path::NewtypeVariant { parent } => ignored_path(parent),
+    }
+}
+
+/// Parse the extra settings and check them for unknown fields and invalid date ranges. The
+/// settings are None when there is an error.
+pub fn validate(json: &str) -> (Option<Vec<ExtraSettings>>, Vec<Problem>) {
+    let mut unknown_fields = Vec::new();
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let result: Result<Vec<ExtraSettings>, _> = serde_path_to_error::deserialize(
+        serde_ignored::Deserializer::new(&mut deserializer, &mut |path: serde_ignored::Path| {
+            unknown_fields.push(ignored_path(&path))
+        }),
+    );
+
+    let mut problems = unknown_fields
+        .into_iter()
+        .map(|field| {
+            let name = field.rsplit('.').next().unwrap_or_default().to_string();
+            Problem::warning(field, format!("unknown field `{name}` is ignored"))
+        })
+        .collect::<Vec<Problem>>();
+
+    let settings = match result {
+        Ok(settings) => settings,
+        Err(e) => {
+            let location = e.path().to_string();
+            // The message of serde_json has the line and the column
+            problems.push(Problem::error(location, e.into_inner().to_string()));
+            return (None, problems);
+        }
+    };
+    if let Err(e) = deserializer.end() {
+        problems.push(Problem::error(".".into(), e.to_string()));
+        return (None, problems);
+    }
+
+    for (i, user_settings) in settings.iter().enumerate() {
+        for (field, message) in user_settings.range_problems() {
+            problems.push(Problem::warning(format!("[{i}].{field}"), message));
+        }
+    }
+    (Some(settings), problems)
+}
@@ -0,0 +1,11 @@
+//! Flex time balance of a Clockify user: the API client, the models and the balance calculator.
+//! The `clockify-flex-rust` binary is a command line interface on top of this crate.
+
+pub mod calculator;
+pub mod clockify;
+pub mod extra_settings;
+pub mod models;
+pub mod utils;
+
+pub use calculator::{BalanceCalculator, CalculationContext, Results};
+pub use clockify::ClockifyClient;
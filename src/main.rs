@@ -1,299 +1,321 @@
 mod args;
-mod clockify;
-mod extra_settings;
-mod models;
-mod utils;
+mod commands;
+mod config;
+mod metrics;
+mod notifications;
+#[cfg(feature = "tray")]
+mod tray;
 
 use anyhow::Error;
-use args::get_settings;
-use chrono::{Datelike, NaiveDate};
-use clockify::{get_days_off, get_working_days};
-use clockify::{ClockifyClient, Token};
-use extra_settings::schema::ExtraSettings;
+use args::{get_settings, setup_log, Args, Command, OutputFormat};
+use calculator::{
+    apply_balance_policy, build_items, daily_balances, get_items, get_items_between, Items,
+};
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate};
+use clockify::{get_pending_days_off, time_entries_to_work_days, time_off_items_to_days};
+use clockify::{ClockifyClient, EntrySource, TimeEntry, TimeOffItem, Token};
+use clockify_flex::{calculator, clockify, extra_settings, models, utils};
+use clockify_flex::{BalanceCalculator, CalculationContext, Results};
+use extra_settings::schema::{ExtraSettings, PayoutRule};
 use extra_settings::GlobalSettings;
-use itertools::Itertools;
-use models::Day;
-use models::{HolidayType, WorkDay};
+use models::{Day, WorkDay};
 use spinners::{Spinner, Spinners};
-use std::env;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
 use std::time::Instant;
-use tokio::join;
-use utils::cache::{get_cache_first_date, set_cache_first_date};
-use utils::table::build_table;
-use utils::{get_public_holidays, setup_log};
-
-async fn get_items(
+use tokio::time::sleep;
+use utils::archive::{self, Archive};
+use utils::cache::Snapshot;
+use utils::cache::{add_snapshot, get_cache_first_date, get_snapshots, set_cache_first_date};
+use utils::clients::ClientBreakdown;
+use utils::dump::{self, Dump};
+use utils::get_public_holidays;
+use utils::holidays::PublicHoliday;
+use utils::insights::Insights;
+use utils::resolution::resolve_pending;
+use utils::stats::WorkStatistics;
+use utils::table::{format_balance, render, DataSource, ReportHeader, TableOptions};
+use utils::tags::TagBreakdown;
+
+/// Fetch the items and calculate the results for the user of the client
+async fn get_results(
     client: ClockifyClient,
     since: NaiveDate,
-) -> Result<(Vec<Day>, Vec<WorkDay>, Vec<Day>), Error> {
-    let public_holidays = get_public_holidays(&since);
-    let working_days = get_working_days(client.clone(), &since);
-    let days_off = get_days_off(client, &since);
-    let (public_holidays, working_days, days_off) = join!(public_holidays, working_days, days_off);
-    Ok((
-        public_holidays
-            .map_err(|e| Error::msg(format!("Failed to get public holidays: {:?}", e)))?,
-        working_days.map_err(|e| Error::msg(format!("Failed to get working days: {:?}", e)))?,
-        days_off.map_err(|e| Error::msg(format!("Failed to get fays off: {:?}", e)))?,
-    ))
-}
-
-struct Results {
-    first_working_day: NaiveDate,
-    working_day_count: usize,
-    worked_time: i64,
-    parental_leave_day_count: usize,
-    held_vacation_day_count: usize,
-    future_vacation_day_count: usize,
-    filtered_expected_working_day_count: usize,
-    public_holiday_count: usize,
-    sick_leave_day_count: usize,
-    held_flex_time_off_day_count: usize,
-    future_flex_time_off_day_count: usize,
-    longest_working_day: WorkDay,
-    expected_working_time_sec: i64,
-    balance: i64,
-}
-
-impl Results {
-    fn total_flex_time_off_day_count(&self) -> usize {
-        self.held_flex_time_off_day_count + self.future_flex_time_off_day_count
-    }
-
-    fn unfiltered_expected_working_day_count(&self) -> usize {
-        self.filtered_expected_working_day_count
-            + self.public_holiday_count
-            + self.sick_leave_day_count
-    }
-
-    fn total_weekdays_since_start(&self) -> usize {
-        self.public_holiday_count + self.sick_leave_day_count + self.working_day_count
-    }
-
-    fn weekdays_sick_leaves_filtered_since_start(&self) -> usize {
-        self.working_day_count + self.public_holiday_count
-    }
-
-    fn weekdays_public_holidays_filtered_since_start(&self) -> usize {
-        self.working_day_count + self.sick_leave_day_count
-    }
-
-    fn balance_days(&self) -> i64 {
-        let denominator_seconds = (*utils::WORK_DAY_HOURS * 3600.0f32) as i64;
-        self.balance / denominator_seconds
-    }
-}
-
-fn calculate_results(
-    mut public_holidays: Vec<Day>,
-    mut working_days: Vec<WorkDay>,
-    mut days_off: Vec<Day>,
     include_today: bool,
     start_balance: i64,
     user_settings: ExtraSettings,
 ) -> Result<Results, Error> {
-    let first_working_day = working_days
-        .iter()
-        .min_by_key(|wd| wd.date)
-        .ok_or(Error::msg("Working days is empty"))?
-        .date;
-    let mut all_weekdays = utils::get_all_weekdays_since(first_working_day).collect_vec();
-
-    if !include_today {
-        let today = utils::today();
-        working_days.retain(|wd| wd.date < today);
-        public_holidays.retain(|phd| phd.date() < today);
-        days_off.retain(|do_| {
-            matches!(do_, Day::Holiday(_)) || matches!(do_, Day::Sick(_)) && do_.date() < today
-        });
-        all_weekdays.retain(|d| d < &today)
-    }
-
-    let longest_working_day = working_days
-        .iter()
-        .max_by_key(|wd| wd.duration())
-        .ok_or(Error::msg("Days iterator is empty!"))?
-        .to_owned();
+    let Items {
+        public_holidays,
+        working_days,
+        days_off,
+        ..
+    } = get_items(client, since, &user_settings).await?;
+    let context = calculation_context(&*get_settings().await, user_settings);
+    BalanceCalculator::new(context)
+        .include_today(include_today)
+        .start_balance(start_balance)
+        .calculate(public_holidays, working_days, days_off)
+}
 
-    let public_holidays_filtered = public_holidays
-        .into_iter()
-        .filter_map(|day| {
-            let date = day.date();
-            if utils::not_in_future(&date)
-                && utils::is_weekday(&date)
-                && first_working_day < date
-                && !user_settings.is_ignored(&day)
-            {
-                Some(date)
-            } else {
-                None
-            }
-        })
-        .collect_vec();
+/// Fetch the data and calculate the results of the token, same as the default mode does. The
+/// first working day is cached unless the start date is given.
+async fn refresh_results(token: &Token) -> Result<Results, Error> {
+    let args = get_settings().await;
+    let since_date = get_since_date(&args, token).await?;
+    let (client, user_settings) = get_client(token).await?;
 
-    let public_holiday_count = public_holidays_filtered.len();
+    let results = get_results(
+        client,
+        since_date,
+        args.include_today,
+        args.start_balance.unwrap_or(0),
+        user_settings,
+    )
+    .await?;
 
-    let (sick_leave_days, time_off_days): (Vec<Day>, Vec<Day>) = days_off
-        .into_iter()
-        .partition(|day| matches!(day, Day::Sick(_)));
-    let sick_leave_days = sick_leave_days
-        .into_iter()
-        .map(Day::into_date)
-        .collect_vec();
-    let sick_leave_day_count = sick_leave_days.len();
-
-    let (parental_leave_days, time_off_days): (Vec<Day>, Vec<Day>) =
-        time_off_days.into_iter().partition(|day| match day {
-            Day::Holiday(hd) => matches!(hd.type_, HolidayType::ParentalLeave),
-            _ => false,
-        });
-    let parental_leave_days = parental_leave_days
-        .into_iter()
-        .filter_map(|d| {
-            if !utils::is_weekday(&d.date()) || user_settings.is_ignored(&d) {
-                return None;
-            }
-            Some(Day::into_date(d))
-        })
-        .collect_vec();
-    let parental_leave_day_count = parental_leave_days.len();
-
-    let (vacation_days, time_off_days): (Vec<Day>, Vec<Day>) =
-        time_off_days.into_iter().partition(|day| match day {
-            Day::Holiday(hd) => matches!(hd.type_, HolidayType::Vacation),
-            _ => false,
-        });
-    let vacation_days = vacation_days
-        .into_iter()
-        .filter_map(|d| {
-            if !utils::is_weekday(&d.date()) || user_settings.is_ignored(&d) {
-                return None;
-            }
-            Some(Day::into_date(d))
-        })
-        .collect_vec();
-    let (held_vacation_days, future_vacation_days): (Vec<NaiveDate>, Vec<NaiveDate>) =
-        vacation_days
-            .into_iter()
-            .partition(|day| day < &utils::today() || (include_today && day == &utils::today()));
-    let held_vacation_day_count = held_vacation_days.len();
-    let future_vacation_day_count = future_vacation_days.len();
+    if args.start_date.is_none() {
+        set_cache_first_date(token, &results.first_working_day)?;
+    }
+    Ok(results)
+}
 
-    let (held_flex_time_off_days, future_flex_time_off_days): (Vec<NaiveDate>, Vec<NaiveDate>) =
-        time_off_days
-            .into_iter()
-            .filter_map(|d| {
-                if !utils::is_weekday(&d.date()) || user_settings.is_ignored(&d) {
-                    return None;
-                }
-                Some(Day::into_date(d))
-            })
-            .partition(utils::not_in_future);
-    let held_flex_time_off_day_count = held_flex_time_off_days.len();
-    let future_flex_time_off_day_count = future_flex_time_off_days.len();
-
-    let filtered_expected_working_days = all_weekdays
-        .into_iter()
-        .filter(|day| {
-            !public_holidays_filtered.contains(day)
-                && !sick_leave_days.contains(day)
-                && !held_vacation_days.contains(day)
-                && !parental_leave_days.contains(day)
-        })
-        .collect_vec();
-
-    let filtered_expected_working_day_count = filtered_expected_working_days.len();
-    let expected_working_time_sec =
-        utils::workdays_to_secs(filtered_expected_working_days, &Some(user_settings));
-    let total_worked_time_sec = working_days.iter().map(|wd| wd.duration()).sum::<i64>();
-    let working_day_count = working_days.len();
-
-    let start_balance = 60i64 * start_balance;
-    let balance = start_balance + total_worked_time_sec - expected_working_time_sec;
-
-    Ok(Results {
-        first_working_day,
-        working_day_count,
-        public_holiday_count,
-        parental_leave_day_count,
-        held_vacation_day_count,
-        future_vacation_day_count,
-        filtered_expected_working_day_count,
-        sick_leave_day_count,
-        held_flex_time_off_day_count,
-        expected_working_time_sec,
-        future_flex_time_off_day_count,
-        longest_working_day,
-        worked_time: total_worked_time_sec,
-        balance,
-    })
+/// Spinner with the message, none with --no-spinner or --quiet
+fn start_spinner(args: &Args, message: &str) -> Option<Spinner> {
+    args.show_progress()
+        .then(|| Spinner::new(Spinners::Moon, message.into()))
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Error> {
-    dotenv::dotenv().ok();
+/// Replace the spinner with the message, without a spinner the message is printed unless quiet
+fn stop_spinner(args: &Args, spinner: Option<Spinner>, message: String) {
+    match spinner {
+        Some(mut spinner) => spinner.stop_with_message(message),
+        None if !args.quiet => println!("{message}"),
+        None => {}
+    }
+}
 
+/// Clockify client configured with the arguments
+async fn new_client(token: &Token) -> Result<ClockifyClient, Error> {
     let args = get_settings().await;
-    setup_log(&args.log_output, &args.log_level)?;
-
-    let token = if let Some(token) = &args.token {
-        token
-    } else if let Ok(token) = &env::var("TOKEN") {
-        &Token::new(token)
-    } else {
-        return Err(Error::msg("Clockify API token is missing! Please add your token to the .env file as 'TOKEN=your_token_here' or pass it using the -t argument."));
+    let client = match &args.replay {
+        Some(dir) => ClockifyClient::replay(dir).await?,
+        None => ClockifyClient::with_api(Arc::new(args.http_api(token)?)).await?,
     };
+    Ok(client
+        .strict(args.strict)
+        .save_responses(args.debug)
+        .concurrency(args.concurrency)
+        .progress(args.show_progress())
+        .timeout(args.timeout.map(Duration::from_secs))
+        // The commands need the entries themselves
+        .entry_source(match args.command {
+            Some(_) => EntrySource::Detailed,
+            None => args.source,
+        }))
+}
 
+/// Clockify client and the extra settings of its user
+async fn get_client(token: &Token) -> Result<(ClockifyClient, ExtraSettings), Error> {
     let extra_settings = GlobalSettings::create_settings().await?;
-
-    let cache_date = get_cache_first_date(token)?;
-    let since_date = args
-        .start_date
-        .unwrap_or(cache_date.unwrap_or(NaiveDate::from_ymd_opt(2022, 1, 1).unwrap()));
-
-    let start_balance = args.start_balance.unwrap_or(0);
-
-    let mut spinner = Spinner::new(Spinners::Moon, "Fetching user...".into());
-    let time = Instant::now();
-    let client = ClockifyClient::new(token)?;
+    let client = new_client(token).await?;
+    let args = get_settings().await;
 
     // Set empty options if not found.
     let user_settings = extra_settings
         .get_user_settings(&client.user.email)
-        .unwrap_or(ExtraSettings::empty());
-    spinner.stop_with_message(format!(
-        "User fetched from Clockify API! ({:.2} s)",
-        time.elapsed().as_secs_f32()
-    ));
+        .unwrap_or(ExtraSettings::empty())
+        .with_exclusions(&args.exclude_projects, &args.exclude_tags);
+    if client.get_entry_source() == EntrySource::Summary && user_settings.excludes_by_details() {
+        return Err(Error::msg(
+            "The daily totals have no tags or descriptions, use --source detailed with excludeTags or ignoreItems patterns!",
+        ));
+    }
+    Ok((client, user_settings))
+}
 
-    let mut spinner = Spinner::new(Spinners::Moon, "Fetching data...".into());
-    let time = Instant::now();
-    let (public_holidays, working_days, days_off) = get_items(client, since_date).await?;
+/// Clockify client and the context of the calculation for its user
+async fn get_context(token: &Token) -> Result<(ClockifyClient, CalculationContext), Error> {
+    let (client, user_settings) = get_client(token).await?;
+    let context = calculation_context(&*get_settings().await, user_settings);
+    Ok((client, context))
+}
 
-    spinner.stop_with_message(format!(
-        "{} items fetched from Clockify API! ({:.2} s)",
-        working_days.iter().map(WorkDay::item_count).sum::<usize>() + days_off.len(),
-        time.elapsed().as_secs_f32()
-    ));
+/// Start date given as argument, the cached first working day, or the date of the first time
+/// entry of the user
+async fn get_since_date(args: &Args, token: &Token) -> Result<NaiveDate, Error> {
+    if let Some(date) = args.start_date.or(get_cache_first_date(token)?) {
+        return Ok(date);
+    }
+    Ok(first_entry_date(&new_client(token).await?).await)
+}
 
-    let mut spinner = Spinner::new(Spinners::Moon, "Calculating results...".into());
-    let time = Instant::now();
-    let results = calculate_results(
+/// Date of the first time entry of the user of the client, today without entries
+async fn first_entry_date(client: &ClockifyClient) -> NaiveDate {
+    match client.get_first_entry_date().await {
+        Ok(date) => date.unwrap_or(utils::today()),
+        Err(e) => {
+            let date = utils::default_start_date();
+            log::warn!("Failed to find the first time entry, fetching since {date}: {e}");
+            date
+        }
+    }
+}
+
+/// Context of the calculation with the extra settings of the user and the dates of the
+/// arguments
+fn calculation_context(args: &Args, user_settings: ExtraSettings) -> CalculationContext {
+    let mut context = CalculationContext::new(user_settings);
+    if let Some(date) = args.as_of {
+        context = context.as_of(date);
+    }
+    if let Some(date) = args.end_date {
+        context = context.until(date);
+    }
+    context
+}
+
+/// Calculate and print the results of the items, caching the first working day of the token.
+/// The webhook is posted unless the balance is the one posted last.
+#[allow(clippy::too_many_arguments)]
+async fn report(
+    args: &Args,
+    items: Items,
+    user_settings: ExtraSettings,
+    pending_days_off: Option<Vec<Day>>,
+    project_clients: Option<&HashMap<String, String>>,
+    token: Option<&Token>,
+    source: DataSource,
+    posted_balance: &mut Option<i64>,
+) -> Result<(), Error> {
+    let context = calculation_context(args, user_settings);
+    let user_settings = &context.settings;
+    // The cap and the expiry depend on when the hours were earned
+    let today = context.last_date();
+    let include_today = context.includes_last_date(args.include_today);
+    let policy_days = user_settings
+        .balance_policy()
+        .map(|_| daily_balances(&items, &context, args.include_today));
+    let Items {
         public_holidays,
         working_days,
         days_off,
-        args.include_today,
-        start_balance,
-        user_settings,
-    )?;
-    spinner.stop_with_message(format!(
-        "Items calculated! ({:.2} s)\n",
-        time.elapsed().as_secs_f32()
-    ));
+        conflicts,
+    } = items;
 
-    // Save first day cache, if start_date was not given
-    if args.start_date.is_none() {
+    let spinner = start_spinner(args, "Calculating results...");
+    let time = Instant::now();
+    let insights = args
+        .insights
+        .then(|| Insights::new(&days_off, user_settings));
+    let tag_breakdown = args
+        .by_tag
+        .then(|| TagBreakdown::new(&working_days, user_settings, args.include_today));
+    let client_breakdown = project_clients.map(|clients| {
+        ClientBreakdown::new(&working_days, clients, user_settings, args.include_today)
+    });
+    let statistics = args
+        .stats
+        .then(|| WorkStatistics::new(&working_days, user_settings, args.include_today));
+    let pending_time_off_day_count = pending_days_off.map(|days| {
+        resolve_pending(days, &public_holidays, &days_off, user_settings)
+            .iter()
+            .filter(|d| utils::is_weekday(&d.date()) && !user_settings.is_ignored(d))
+            .count()
+    });
+    let schedule_start = working_days.iter().map(|wd| wd.date).min();
+    let schedule = match schedule_start {
+        Some(start) if context.settings.has_custom_schedule() => {
+            context.schedule_periods(start, context.last_date())
+        }
+        _ => Vec::new(),
+    };
+    let thresholds = context.settings.balance_thresholds().cloned();
+    let policy = context.settings.balance_policy().cloned();
+    let webhook = args
+        .notify_webhook
+        .clone()
+        .or(context.settings.notify_webhook().cloned());
+    let mut results = BalanceCalculator::new(context)
+        .include_today(args.include_today)
+        .start_balance(args.start_balance.unwrap_or(0))
+        .calculate(public_holidays, working_days, days_off)?;
+    results.pending_time_off_day_count = pending_time_off_day_count;
+    results.reclaimed_vacation_day_count = conflicts
+        .iter()
+        .filter(|c| c.reclaims_vacation() && utils::is_weekday(&c.date))
+        .count();
+    if let (Some(policy), Some(days)) = (&policy, policy_days) {
+        results.policy_balance = Some(apply_balance_policy(
+            &days,
+            60 * args.start_balance.unwrap_or(0),
+            results.balance,
+            policy,
+            today,
+        ));
+    }
+    stop_spinner(
+        args,
+        spinner,
+        format!(
+            "Items calculated! ({:.2} s)\n",
+            time.elapsed().as_secs_f32()
+        ),
+    );
+
+    // Save first day cache and the snapshot of today, if start_date, end_date and as_of
+    // were not given
+    let mut previous_balance = None;
+    let saved = args.start_date.is_none() && args.end_date.is_none() && args.as_of.is_none();
+    if let Some(token) = token.filter(|_| saved) {
+        previous_balance = get_snapshots(token)?.last().map(|s| s.balance);
         set_cache_first_date(token, &results.first_working_day)?;
+        let snapshot = Snapshot {
+            date: today,
+            balance: results.balance,
+            worked: results.worked_time,
+            expected: results.expected_working_time_sec,
+        };
+        add_snapshot(token, snapshot)?;
+    }
+
+    // The previous run is the latest snapshot, without it a balance beyond counts as crossed
+    if let Some(threshold) = thresholds
+        .as_ref()
+        .and_then(|t| t.crossed(previous_balance, results.balance))
+    {
+        notifications::notify_threshold(&threshold, results.balance);
+    }
+    if let Some(url) = webhook.filter(|_| *posted_balance != Some(results.balance)) {
+        notifications::post_webhook(&url, &results, today).await?;
+        *posted_balance = Some(results.balance);
+    }
+
+    if args.quiet {
+        println!("{}", format_balance(results.policy_adjusted_balance()));
+        return Ok(());
+    }
+
+    let header = ReportHeader {
+        start: args.start_date.unwrap_or(results.first_working_day),
+        end: today,
+        end_included: include_today,
+        as_of: args.as_of.is_some() || args.end_date.is_some(),
+        source,
+    };
+    let options = TableOptions {
+        start_balance: args.start_balance,
+        locale: args.locale,
+        thresholds: thresholds.as_ref(),
+        color: args.color.enabled(),
+        rows: &args.rows,
+        header: Some(&header),
+    };
+    let output = render(&results, &args.output, &schedule, &options);
+    if !matches!(args.output, OutputFormat::Table) {
+        println!("{output}");
+        return Ok(());
     }
 
     // TODO Support for first day even when the start_date is given
@@ -319,8 +341,464 @@ async fn main() -> Result<(), Error> {
         longest_day.date
     );
 
-    let table = build_table(results, args.start_balance);
-    println!("{table}");
+    println!("{output}");
+
+    if let Some(insights) = insights {
+        println!("Absence patterns:\n{insights}");
+    }
+
+    if results.overlap_day_count > 0 {
+        println!(
+            "Overlapping entries on {} days were counted once. Run `audit` to list them.",
+            results.overlap_day_count
+        );
+    }
+
+    if !conflicts.is_empty() {
+        println!("Conflicting day types, resolved by the precedence:");
+        for conflict in &conflicts {
+            println!("- {conflict}");
+        }
+    }
+
+    if let Some(tag_breakdown) = tag_breakdown {
+        println!("Time per tag:\n{tag_breakdown}");
+    }
+
+    if let Some(client_breakdown) = client_breakdown {
+        println!("Time per client:\n{client_breakdown}");
+    }
+
+    if let Some(statistics) = statistics {
+        println!("Logged time statistics:\n{statistics}");
+    }
 
     Ok(())
 }
+
+/// Client names of the projects for --by-client
+async fn get_project_clients(
+    args: &Args,
+    client: &ClockifyClient,
+) -> Result<Option<HashMap<String, String>>, Error> {
+    if !args.by_client {
+        return Ok(None);
+    }
+    let spinner = start_spinner(args, "Fetching projects...");
+    let clients = client.get_project_clients().await?;
+    stop_spinner(args, spinner, "Projects fetched from Clockify API!".into());
+    Ok(Some(clients))
+}
+
+/// Days refetched on every refresh of the watch mode, older items are kept
+const WATCH_REFETCH_DAYS: u64 = 7;
+
+/// Fetch the items once and redraw the results on every interval, refetching only the
+/// recent days. Failed refreshes are shown and retried on the next interval.
+async fn watch(
+    args: &Args,
+    token: &Token,
+    since_date: NaiveDate,
+    interval: Duration,
+) -> Result<(), Error> {
+    let (client, user_settings) = get_client(token).await?;
+    let project_clients = get_project_clients(args, &client).await?;
+    let mut items = get_items(client.clone(), since_date, &user_settings).await?;
+    let mut fetched_at = Local::now();
+    let mut error = None;
+    // The previous pending time off is shown when its refresh fails
+    let mut pending_days_off = None;
+    // The webhook is posted again only when the balance changes
+    let mut posted_balance = None;
+    loop {
+        if args.include_pending {
+            match get_pending_days_off(client.clone(), &since_date, &user_settings).await {
+                Ok(days) => pending_days_off = Some(days),
+                Err(e) => error = Some(e),
+            }
+        }
+
+        print!("\x1b[2J\x1b[H");
+        if let Err(e) = report(
+            args,
+            items.clone(),
+            user_settings.clone(),
+            pending_days_off.clone(),
+            project_clients.as_ref(),
+            Some(token).filter(|_| args.replay.is_none()),
+            DataSource::Live(fetched_at),
+            &mut posted_balance,
+        )
+        .await
+        {
+            error = Some(e);
+        }
+        if let Some(e) = error.take() {
+            println!("Refresh failed, trying again on the next interval: {e}");
+        }
+        println!(
+            "Updated at {}, refreshing every {} minutes. Press Ctrl+C to stop.",
+            Local::now().format("%H:%M"),
+            interval.as_secs() / 60
+        );
+
+        sleep(interval).await;
+        let refetch_since = (utils::today() - Days::new(WATCH_REFETCH_DAYS)).max(since_date);
+        match get_items(client.clone(), refetch_since, &user_settings).await {
+            Ok(newer) => {
+                items = items.merge(newer, refetch_since);
+                fetched_at = Local::now();
+            }
+            Err(e) => error = Some(e),
+        }
+    }
+}
+
+/// Calculate the results from items read without the network. The public holidays are
+/// taken from the dataset unless given.
+async fn report_offline(
+    args: &Args,
+    time_entries: Vec<TimeEntry>,
+    time_off_items: Vec<TimeOffItem>,
+    pending_time_off_items: Option<Vec<TimeOffItem>>,
+    holidays: Option<Vec<PublicHoliday>>,
+    source: DataSource,
+) -> Result<(), Error> {
+    let user_settings = GlobalSettings::create_settings()
+        .await?
+        .only_user_settings()
+        .unwrap_or_else(|| {
+            log::warn!(
+                "The offline data doesn't have the email, extra settings of one user are required"
+            );
+            ExtraSettings::empty()
+        })
+        .with_exclusions(&args.exclude_projects, &args.exclude_tags);
+
+    let since = args.start_date.unwrap_or(
+        time_entries
+            .first()
+            .map(|entry| entry.start.date_naive())
+            .unwrap_or(utils::default_start_date()),
+    );
+    let working_days = time_entries_to_work_days(
+        time_entries
+            .into_iter()
+            .filter(|entry| entry.start.date_naive() >= since)
+            .collect(),
+    );
+    let days_off = time_off_items_to_days(time_off_items, &since, &user_settings)?;
+    let pending_days_off = pending_time_off_items
+        .map(|items| time_off_items_to_days(items, &since, &user_settings))
+        .transpose()?;
+    let public_holidays = match holidays {
+        Some(holidays) => utils::observed_public_holidays(holidays, &since, &user_settings),
+        None => get_public_holidays(&since, &user_settings).await?,
+    };
+    let items = build_items(public_holidays, working_days, days_off, &user_settings);
+    // The offline data has only the project names
+    let project_clients = args.by_client.then(|| {
+        log::warn!("The offline data doesn't have the clients of the projects");
+        HashMap::new()
+    });
+    report(
+        args,
+        items,
+        user_settings,
+        pending_days_off,
+        project_clients.as_ref(),
+        None,
+        source,
+        &mut None,
+    )
+    .await
+}
+
+/// Calculate the results from debug dumps instead of the Clockify API
+async fn report_from_dump(args: &Args) -> Result<(), Error> {
+    let Dump {
+        time_entries,
+        time_off_items,
+    } = dump::load(&args.from_dump, args.strict)?;
+    let saved_at = args
+        .from_dump
+        .iter()
+        .filter_map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+        .max()
+        .map(DateTime::<Local>::from);
+    report_offline(
+        args,
+        time_entries,
+        time_off_items,
+        None,
+        None,
+        DataSource::Dump(saved_at),
+    )
+    .await
+}
+
+/// Calculate the results from the archive imported with `import`
+async fn report_from_archive(args: &Args) -> Result<(), Error> {
+    let dir = archive::imported_dir();
+    if !dir.is_dir() {
+        return Err(Error::msg(
+            "No archive has been imported! Import one with `import <dir>`.",
+        ));
+    }
+    let archive = Archive::load(&dir)?;
+    let (time_off_items, pending): (Vec<_>, Vec<_>) = archive
+        .time_off_items
+        .into_iter()
+        .filter(|item| item.status == "APPROVED" || item.status == "PENDING")
+        .partition(|item| item.status == "APPROVED");
+    report_offline(
+        args,
+        archive.entries,
+        time_off_items,
+        args.include_pending.then_some(pending),
+        Some(archive.holidays),
+        DataSource::Archive(archive.exported_at.into()),
+    )
+    .await
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    // Ctrl-C drops the run, which cancels the requests in flight. The caches are written
+    // atomically, so they are left as they were. The run is polled first, so that the
+    // blocking tray keeps the default handler.
+    tokio::select! {
+        biased;
+        result = run() => result,
+        _ = tokio::signal::ctrl_c() => {
+            eprintln!("\nInterrupted.");
+            std::process::exit(130);
+        }
+    }
+}
+
+async fn run() -> Result<(), Error> {
+    let args = get_settings().await;
+    // The log file of older versions is moved before it is opened
+    utils::dirs::migrate()?;
+    setup_log(&args.log_output, &args.log_level)?;
+    utils::table::set_hours_format(args.hours_format)?;
+    utils::set_work_day_hours(args.work_day_hours)?;
+    utils::holidays::set_country(&args.country)?;
+
+    if args.end_date.is_some() && (args.command.is_some() || args.watch.is_some()) {
+        return Err(Error::msg(
+            "--end-date works only without a command or --watch!",
+        ));
+    }
+    // The commands and the refreshing modes calculate as of the actual today
+    if args.as_of.is_some()
+        && (args.command.is_some() || args.watch.is_some() || args.serve_metrics.is_some())
+    {
+        return Err(Error::msg(
+            "--as-of works only without a command, --watch or --serve-metrics!",
+        ));
+    }
+
+    // Commands that don't need the token
+    match &args.command {
+        Some(Command::Auth { action }) => return commands::auth::run(action).await,
+        Some(Command::Config { action }) => return commands::config::run(action).await,
+        Some(Command::Doctor) => return commands::doctor::run().await,
+        Some(Command::Holidays { action }) => return commands::holidays::run(action).await,
+        Some(Command::Import { dir }) => return commands::import::run(dir),
+        // The tokens of the teammates replace the own token
+        Some(Command::Team { tokens, csv, .. }) if !tokens.is_empty() => {
+            return commands::team::run_tokens(tokens, csv.as_deref()).await
+        }
+        _ => {}
+    }
+    if !args.from_dump.is_empty() {
+        if args.command.is_some() {
+            return Err(Error::msg("--from-dump works only without a command!"));
+        }
+        return report_from_dump(&args).await;
+    }
+    if args.offline {
+        if args.command.is_some() {
+            return Err(Error::msg("--offline works only without a command!"));
+        }
+        return report_from_archive(&args).await;
+    }
+
+    // Replays don't send the token, it only keys the caches
+    let replay_token = Token::new("replay");
+    let token = if let Some(token) = &args.token {
+        token
+    } else if args.replay.is_some() {
+        &replay_token
+    } else if let Some(token) = Token::from_keyring()? {
+        &token.clone()
+    } else {
+        return Err(Error::msg("Clockify API token is missing! Please save it to the keyring with `auth login`, add it to the .env file as 'TOKEN=your_token_here', to config.toml as 'token = \"your_token_here\"' or pass it using the -t argument."));
+    };
+
+    if args.source == EntrySource::Summary && (args.stats || args.by_tag) {
+        return Err(Error::msg("--stats and --by-tag need --source detailed!"));
+    }
+    if args.watch.is_some() && args.command.is_some() {
+        return Err(Error::msg("--watch works only without a command!"));
+    }
+    if let Some(addr) = args.serve_metrics {
+        if args.command.is_some() {
+            return Err(Error::msg("--serve-metrics works only without a command!"));
+        }
+        let interval = Duration::from_secs(args.metrics_interval * 60);
+        return metrics::run(token.clone(), addr, interval).await;
+    }
+
+    match &args.command {
+        #[cfg(feature = "tray")]
+        Some(Command::Tray { interval }) => {
+            return tray::run(token.clone(), Duration::from_secs(interval * 60));
+        }
+        Some(Command::Api { action }) => return commands::api::run(token, action).await,
+        Some(Command::Audit { gaps }) => return commands::anomalies::run(token, *gaps).await,
+        Some(Command::Chart) => return commands::chart::run(token).await,
+        Some(Command::Calendar { year }) => return commands::calendar::run(token, *year).await,
+        Some(Command::Log {
+            date,
+            hours,
+            start,
+            project,
+            description,
+            force,
+        }) => {
+            return commands::log::run(
+                token,
+                *date,
+                *hours,
+                *start,
+                project.as_deref(),
+                description,
+                *force,
+            )
+            .await
+        }
+        Some(Command::Monthly { year }) => return commands::monthly::run(token, *year).await,
+        Some(Command::Report { action }) => return commands::report::run(token, action).await,
+        Some(Command::Cost { emails, csv }) => {
+            return commands::cost::run(token, emails, csv.as_deref()).await
+        }
+        Some(Command::HrExport {
+            year,
+            emails,
+            output,
+        }) => return commands::hr_export::run(token, *year, emails, output).await,
+        Some(Command::History { months }) => return commands::history::run(token, *months),
+        Some(Command::Prompt { format }) => {
+            return commands::prompt::run(token, format, args.color)
+        }
+        Some(Command::Export { action }) => return commands::export::run(token, action).await,
+        Some(Command::Forecast { until }) => return commands::forecast::run(token, *until).await,
+        Some(Command::Goals { action }) => return commands::goals::run(token, action).await,
+        Some(Command::Payout {
+            cutoff,
+            above,
+            multiplier,
+        }) => {
+            let rule = above
+                .zip(*multiplier)
+                .map(|(above_hours, multiplier)| PayoutRule {
+                    above_hours,
+                    multiplier,
+                });
+            return commands::payout::run(token, *cutoff, rule).await;
+        }
+        Some(Command::Plan {
+            until,
+            strategy,
+            long_days,
+            max_daily_hours,
+        }) => {
+            return commands::plan::run(token, *until, strategy, *long_days, *max_daily_hours).await
+        }
+        Some(Command::Schedule { action }) => return commands::schedule::run(token, action).await,
+        Some(Command::Show { date }) => return commands::show::run(token, *date).await,
+        Some(Command::Auth { .. })
+        | Some(Command::Config { .. })
+        | Some(Command::Doctor)
+        | Some(Command::Holidays { .. })
+        | Some(Command::Import { .. }) => unreachable!(),
+        Some(Command::State { action }) => return commands::state::run(token, action),
+        Some(Command::TakeFlex {
+            date,
+            note,
+            yes,
+            force,
+        }) => return commands::take_flex::run(token, *date, note.as_deref(), *yes, *force).await,
+        Some(Command::Team { emails, csv, .. }) => {
+            return commands::team::run(token, emails, csv.as_deref()).await
+        }
+        Some(Command::Timer { action }) => return commands::timer::run(token, action).await,
+        Some(Command::Undo { id, list }) => {
+            return commands::undo::run(token, id.as_deref(), *list).await
+        }
+        Some(Command::Upcoming { months }) => return commands::upcoming::run(token, *months).await,
+        None => {}
+    }
+
+    let since_date = get_since_date(&args, token).await?;
+    if let Some(minutes) = args.watch {
+        return watch(&args, token, since_date, Duration::from_secs(minutes * 60)).await;
+    }
+
+    let spinner = start_spinner(&args, "Fetching user...");
+    let time = Instant::now();
+    let (client, user_settings) = get_client(token).await?;
+    stop_spinner(
+        &args,
+        spinner,
+        format!(
+            "User fetched from Clockify API! ({:.2} s)",
+            time.elapsed().as_secs_f32()
+        ),
+    );
+
+    // The time entries show their own progress bar
+    let time = Instant::now();
+    let pending_days_off = if args.include_pending {
+        Some(get_pending_days_off(client.clone(), &since_date, &user_settings).await?)
+    } else {
+        None
+    };
+    let project_clients = get_project_clients(&args, &client).await?;
+    let items = get_items_between(client, since_date, args.end_date, &user_settings).await?;
+    let fetched_at = Local::now();
+
+    if !args.quiet {
+        println!(
+            "{} items fetched from Clockify API! ({:.2} s)",
+            items
+                .working_days
+                .iter()
+                .map(WorkDay::item_count)
+                .sum::<usize>()
+                + items.days_off.len(),
+            time.elapsed().as_secs_f32()
+        );
+    }
+
+    report(
+        &args,
+        items,
+        user_settings,
+        pending_days_off,
+        project_clients.as_ref(),
+        // Replays don't update the cache or the balance history
+        Some(token).filter(|_| args.replay.is_none()),
+        match args.replay {
+            Some(_) => DataSource::Dump(None),
+            None => DataSource::Live(fetched_at),
+        },
+        &mut None,
+    )
+    .await
+}
@@ -0,0 +1,155 @@
+use crate::clockify::Token;
+use crate::{refresh_results, Results};
+use anyhow::Error;
+use chrono::Utc;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::RwLock;
+use tokio::time::sleep;
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Values of the latest successful refresh with the refresh counters
+#[derive(Default)]
+struct Metrics {
+    results: Option<Results>,
+    refreshed_at: Option<i64>,
+    refresh_error_count: u64,
+}
+
+impl Metrics {
+    /// Prometheus text exposition of the metrics
+    fn render(&self) -> String {
+        let mut text = String::new();
+        let mut metric = |name: &str, type_: &str, help: &str, value: String| {
+            let _ = writeln!(text, "# HELP {name} {help}");
+            let _ = writeln!(text, "# TYPE {name} {type_}");
+            let _ = writeln!(text, "{name} {value}");
+        };
+
+        if let Some(r) = &self.results {
+            metric(
+                "clockify_flex_balance_seconds",
+                "gauge",
+                "Work time balance",
+                r.balance.to_string(),
+            );
+            metric(
+                "clockify_worked_seconds_total",
+                "counter",
+                "Total working time since the start date",
+                r.worked_time.to_string(),
+            );
+            metric(
+                "clockify_expected_seconds_total",
+                "counter",
+                "Expected working time since the start date",
+                r.expected_working_time_sec.to_string(),
+            );
+            metric(
+                "clockify_working_days_total",
+                "counter",
+                "Days with logged time",
+                r.working_day_count.to_string(),
+            );
+            metric(
+                "clockify_sick_leave_days_total",
+                "counter",
+                "Sick leave days",
+                r.sick_leave_day_count.to_string(),
+            );
+            metric(
+                "clockify_vacation_days_total",
+                "counter",
+                "Held vacation weekdays",
+                r.held_vacation_day_count.to_string(),
+            );
+            metric(
+                "clockify_flex_time_off_days_total",
+                "counter",
+                "Held flex time off days",
+                r.held_flex_time_off_day_count.to_string(),
+            );
+        }
+        if let Some(refreshed_at) = self.refreshed_at {
+            metric(
+                "clockify_flex_last_refresh_timestamp_seconds",
+                "gauge",
+                "Unix time of the latest successful refresh",
+                refreshed_at.to_string(),
+            );
+        }
+        metric(
+            "clockify_flex_refresh_errors_total",
+            "counter",
+            "Failed refreshes",
+            self.refresh_error_count.to_string(),
+        );
+        text
+    }
+}
+
+/// Refresh the metrics on every interval. The previous values are kept on errors.
+async fn refresh_loop(token: Token, interval: Duration, metrics: Arc<RwLock<Metrics>>) {
+    loop {
+        match refresh_results(&token).await {
+            Ok(results) => {
+                let mut metrics = metrics.write().await;
+                metrics.results = Some(results);
+                metrics.refreshed_at = Some(Utc::now().timestamp());
+            }
+            Err(e) => {
+                log::error!("Refreshing the metrics failed: {e}");
+                metrics.write().await.refresh_error_count += 1;
+            }
+        }
+        sleep(interval).await;
+    }
+}
+
+/// Answer a single HTTP request, only GET /metrics is served
+async fn handle(mut stream: TcpStream, metrics: Arc<RwLock<Metrics>>) -> Result<(), Error> {
+    let mut buffer = [0; 4096];
+    let length = stream.read(&mut buffer).await?;
+    let request = String::from_utf8_lossy(&buffer[..length]);
+    let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+
+    let (status, content_type, body) = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", CONTENT_TYPE, metrics.read().await.render()),
+        (Some("GET"), _) => ("404 Not Found", "text/plain", "Not found\n".into()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Method not allowed\n".into(),
+        ),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Serve the balance metrics for Prometheus until the process is stopped
+pub(crate) async fn run(token: Token, addr: SocketAddr, interval: Duration) -> Result<(), Error> {
+    let listener = TcpListener::bind(addr).await?;
+    println!("Serving metrics on http://{addr}/metrics");
+
+    let metrics = Arc::new(RwLock::new(Metrics::default()));
+    tokio::spawn(refresh_loop(token, interval, metrics.clone()));
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle(stream, metrics).await {
+                log::warn!("Serving a metrics request failed: {e}");
+            }
+        });
+    }
+}
@@ -1,65 +1,128 @@
 use crate::{clockify::TimeEntry, extra_settings::schema::DayType};
 use chrono::{DateTime, NaiveDate, Utc};
+use itertools::Itertools;
 use serde::Deserialize;
 
-#[derive(Deserialize, Debug)]
-pub(crate) enum HolidayType {
+#[derive(Deserialize, Clone, Debug)]
+pub enum HolidayType {
     Vacation,
     PublicHoliday,
     Flex,
     ParentalLeave,
+    UnpaidLeave,
     Unknown,
+    /// Name of the custom day type
+    Custom(String),
 }
 
-#[derive(Deserialize, Debug)]
-pub(crate) struct Holiday {
+#[derive(Deserialize, Clone, Debug)]
+pub struct Holiday {
     pub type_: HolidayType,
     pub title: String,
     pub date: NaiveDate,
+    /// Note of the time off request
+    #[serde(default)]
+    pub note: String,
 }
 
 impl Holiday {
-    pub(crate) fn new(title: String, date: NaiveDate, type_: HolidayType) -> Self {
-        Self { title, date, type_ }
+    pub fn new(title: String, date: NaiveDate, type_: HolidayType) -> Self {
+        Self {
+            title,
+            date,
+            type_,
+            note: String::new(),
+        }
+    }
+
+    pub fn with_note(mut self, note: String) -> Self {
+        self.note = note;
+        self
     }
 }
 
-#[derive(Deserialize, Debug)]
-pub(crate) struct SickLeaveDay {
+#[derive(Deserialize, Clone, Debug)]
+pub struct SickLeaveDay {
+    /// Note of the time off request
     title: String,
     date: NaiveDate,
 }
 
 impl SickLeaveDay {
-    pub(crate) fn new(title: String, date: NaiveDate) -> Self {
+    pub fn new(title: String, date: NaiveDate) -> Self {
         SickLeaveDay { title, date }
     }
 }
 
 #[derive(Deserialize, Clone, Debug)]
-pub(crate) struct WorkDay {
+pub struct WorkDay {
     pub date: NaiveDate,
     pub items: Vec<WorkItem>,
 }
 
 impl WorkDay {
-    pub(crate) fn new(date: NaiveDate, items: Vec<WorkItem>) -> Self {
+    pub fn new(date: NaiveDate, items: Vec<WorkItem>) -> Self {
         WorkDay { date, items }
     }
 
-    pub(crate) fn duration(&self) -> i64 {
-        self.items.iter().map(|wi| wi.duration()).sum()
+    /// Logged seconds of the day, overlapping time is counted once
+    pub fn duration(&self) -> i64 {
+        let mut total = 0;
+        let mut covered_until: Option<DateTime<Utc>> = None;
+        for item in self.items.iter().sorted_by_key(|wi| wi.start) {
+            let start = covered_until.map_or(item.start, |until| until.max(item.start));
+            if item.stop > start {
+                total += (item.stop - start).num_seconds();
+                covered_until = Some(item.stop);
+            }
+        }
+        total
     }
 
-    pub(crate) fn item_count(&self) -> usize {
+    /// Pairs of entries that overlap, the later starting one second
+    pub fn overlaps(&self) -> Vec<(&WorkItem, &WorkItem)> {
+        // Compare against the entry that ends last so far, it may not be the previous one
+        let mut overlaps = Vec::new();
+        let mut latest: Option<&WorkItem> = None;
+        for item in self.items.iter().sorted_by_key(|wi| wi.start) {
+            if let Some(previous) = latest.filter(|previous| item.start < previous.stop) {
+                overlaps.push((previous, item));
+            }
+            if latest.is_none_or(|previous| item.stop > previous.stop) {
+                latest = Some(item);
+            }
+        }
+        overlaps
+    }
+
+    /// Unlogged gaps longer than `min_sec` between entries, as the entries around the gap
+    pub fn gaps(&self, min_sec: i64) -> Vec<(&WorkItem, &WorkItem)> {
+        let mut gaps = Vec::new();
+        let mut latest: Option<&WorkItem> = None;
+        for item in self.items.iter().sorted_by_key(|wi| wi.start) {
+            if let Some(previous) =
+                latest.filter(|previous| (item.start - previous.stop).num_seconds() > min_sec)
+            {
+                gaps.push((previous, item));
+            }
+            if latest.is_none_or(|previous| item.stop > previous.stop) {
+                latest = Some(item);
+            }
+        }
+        gaps
+    }
+
+    pub fn item_count(&self) -> usize {
         self.items.len()
     }
 }
 
 #[derive(Deserialize, Clone, Debug)]
-pub(crate) struct WorkItem {
+pub struct WorkItem {
     description: String,
     project: String,
+    #[serde(default)]
+    tags: Vec<String>,
     start: DateTime<Utc>,
     stop: DateTime<Utc>,
 }
@@ -69,6 +132,7 @@ impl From<TimeEntry> for WorkItem {
         WorkItem {
             description: value.description,
             project: value.project_name,
+            tags: value.tags,
             start: value.start,
             stop: value.end,
         }
@@ -76,21 +140,41 @@ impl From<TimeEntry> for WorkItem {
 }
 
 impl WorkItem {
-    fn duration(&self) -> i64 {
+    pub fn duration(&self) -> i64 {
         (self.stop - self.start).num_seconds()
     }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn start(&self) -> DateTime<Utc> {
+        self.start
+    }
+
+    pub fn stop(&self) -> DateTime<Utc> {
+        self.stop
+    }
+
+    pub fn project(&self) -> &str {
+        &self.project
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Clone, Debug)]
 #[serde(untagged)]
-pub(crate) enum Day {
+pub enum Day {
     Holiday(Holiday),
     Sick(SickLeaveDay),
     Work(WorkDay),
 }
 
 impl Day {
-    pub(crate) fn date(&self) -> NaiveDate {
+    pub fn date(&self) -> NaiveDate {
         match self {
             Self::Holiday(d) => d.date,
             Self::Sick(d) => d.date,
@@ -98,7 +182,7 @@ impl Day {
         }
     }
 
-    pub(crate) fn into_date(self) -> NaiveDate {
+    pub fn into_date(self) -> NaiveDate {
         match self {
             Self::Holiday(d) => d.date,
             Self::Sick(d) => d.date,
@@ -106,14 +190,34 @@ impl Day {
         }
     }
 
-    pub(crate) fn type_(&self) -> DayType {
+    /// Name of the day, e.g. the localized name of a public holiday
+    pub fn title(&self) -> Option<&str> {
+        match self {
+            Self::Holiday(d) if !d.title.is_empty() => Some(&d.title),
+            _ => None,
+        }
+    }
+
+    /// Note of the time off request, if any
+    pub fn note(&self) -> Option<&str> {
+        let note = match self {
+            Self::Holiday(d) => &d.note,
+            Self::Sick(d) => &d.title,
+            Self::Work(_) => return None,
+        };
+        (!note.is_empty()).then_some(note.as_str())
+    }
+
+    pub fn type_(&self) -> DayType {
         match self {
-            Self::Holiday(d) => match d.type_ {
+            Self::Holiday(d) => match &d.type_ {
                 HolidayType::Flex => DayType::Flex,
                 HolidayType::ParentalLeave => DayType::ParentalLeave,
                 HolidayType::PublicHoliday => DayType::PublicHoliday,
                 HolidayType::Vacation => DayType::Vacation,
+                HolidayType::UnpaidLeave => DayType::UnpaidLeave,
                 HolidayType::Unknown => DayType::Unknown,
+                HolidayType::Custom(name) => DayType::Custom(name.clone()),
             },
             Self::Sick(_) => DayType::SickLeave,
             Self::Work(_) => DayType::WorkingDay,
@@ -0,0 +1,46 @@
+use crate::utils::{self, table::format_duration};
+use crate::Results;
+use anyhow::Error;
+use chrono::NaiveDate;
+use notify_rust::Notification;
+use serde_json::json;
+use url::Url;
+
+/// Show a desktop notification of the crossed balance threshold. Failures are only logged,
+/// e.g. on machines without a notification service.
+pub(crate) fn notify_threshold(threshold: &str, balance: i64) {
+    let body = format!("The balance {} is {threshold}.", format_duration(balance));
+    if let Err(e) = Notification::new()
+        .summary("Clockify flex")
+        .body(&body)
+        .show()
+    {
+        log::warn!("Failed to show the notification: {e}");
+    }
+}
+
+/// Post the summary of the results to the webhook. The `text` field is shown by Slack
+/// incoming webhooks, the other fields are for other integrations.
+pub(crate) async fn post_webhook(url: &Url, r: &Results, date: NaiveDate) -> Result<(), Error> {
+    let text = format!(
+        "Work time balance on {date}: *{}* (worked {}, expected {})",
+        format_duration(r.balance),
+        format_duration(r.worked_time),
+        format_duration(r.expected_working_time_sec)
+    );
+    let payload = json!({
+        "text": text,
+        "date": date,
+        "balanceSeconds": r.balance,
+        "workedSeconds": r.worked_time,
+        "expectedSeconds": r.expected_working_time_sec,
+    });
+    utils::http::client()?
+        .post(url.clone())
+        .json(&payload)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| Error::msg(format!("Failed to post the summary to the webhook: {e}")))?;
+    Ok(())
+}
@@ -0,0 +1,268 @@
+use crate::args::get_settings;
+use crate::clockify::Token;
+use crate::refresh_results;
+use crate::utils::table::{build_html, format_duration, TableOptions};
+use anyhow::Error;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::sleep;
+use tray_icon::menu::{Menu, MenuEvent, MenuId, MenuItem};
+use tray_icon::{Icon, MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+
+const ICON_SIZE: u32 = 32;
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+enum TrayMessage {
+    Refreshed(Result<Summary, String>),
+    Menu(MenuEvent),
+    Tray(TrayIconEvent),
+}
+
+struct Summary {
+    balance: i64,
+    report: String,
+}
+
+/// Results of the token with the report shown on a click
+async fn get_summary(token: &Token) -> Result<Summary, Error> {
+    let results = refresh_results(token).await?;
+    let args = get_settings().await;
+    Ok(Summary {
+        balance: results.balance,
+        report: build_html(
+            &results,
+            &TableOptions {
+                start_balance: args.start_balance,
+                locale: args.locale,
+                ..TableOptions::default()
+            },
+        ),
+    })
+}
+
+/// Refresh the summary on every interval, or when asked from the menu.
+async fn refresh_loop(
+    token: Token,
+    interval: Duration,
+    refresh: Arc<Notify>,
+    sender: Sender<TrayMessage>,
+) {
+    loop {
+        let summary = get_summary(&token).await.map_err(|e| e.to_string());
+        if sender.send(TrayMessage::Refreshed(summary)).is_err() {
+            return;
+        }
+        tokio::select! {
+            _ = sleep(interval) => {},
+            _ = refresh.notified() => {},
+        }
+    }
+}
+
+/// Round icon filled with the given color.
+fn circle_icon([r, g, b]: [u8; 3]) -> Result<Icon, Error> {
+    let center = ICON_SIZE as f32 / 2.0;
+    let mut rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let dx = x as f32 + 0.5 - center;
+            let dy = y as f32 + 0.5 - center;
+            let alpha = if dx * dx + dy * dy <= center * center {
+                255
+            } else {
+                0
+            };
+            rgba.extend_from_slice(&[r, g, b, alpha]);
+        }
+    }
+    Icon::from_rgba(rgba, ICON_SIZE, ICON_SIZE).map_err(Error::from)
+}
+
+struct TrayApp {
+    tray_icon: Option<TrayIcon>,
+    report_id: MenuId,
+    refresh_id: MenuId,
+    quit_id: MenuId,
+    summary: Option<Summary>,
+    refresh: Arc<Notify>,
+    receiver: Receiver<TrayMessage>,
+}
+
+impl TrayApp {
+    fn new(receiver: Receiver<TrayMessage>, refresh: Arc<Notify>) -> Self {
+        Self {
+            tray_icon: None,
+            report_id: MenuId::new("report"),
+            refresh_id: MenuId::new("refresh"),
+            quit_id: MenuId::new("quit"),
+            summary: None,
+            refresh,
+            receiver,
+        }
+    }
+
+    /// Create the tray icon, must be called when the event loop is running.
+    fn build_icon(&mut self) -> Result<(), Error> {
+        let menu = Menu::new();
+        menu.append(&MenuItem::with_id(
+            self.report_id.clone(),
+            "Open report",
+            true,
+            None,
+        ))?;
+        menu.append(&MenuItem::with_id(
+            self.refresh_id.clone(),
+            "Refresh",
+            true,
+            None,
+        ))?;
+        menu.append(&MenuItem::with_id(self.quit_id.clone(), "Quit", true, None))?;
+
+        let tray_icon = TrayIconBuilder::new()
+            .with_menu(Box::new(menu))
+            .with_tooltip("Fetching balance...")
+            .with_icon(circle_icon([0x80, 0x80, 0x80])?)
+            .build()?;
+        self.tray_icon = Some(tray_icon);
+        Ok(())
+    }
+
+    fn update(&mut self, summary: Result<Summary, String>) -> Result<(), Error> {
+        let Some(tray_icon) = &self.tray_icon else {
+            return Ok(());
+        };
+        match summary {
+            Ok(summary) => {
+                let color = if summary.balance < 0 {
+                    [0xd0, 0x30, 0x30]
+                } else {
+                    [0x30, 0xa0, 0x40]
+                };
+                tray_icon.set_tooltip(Some(format!(
+                    "Work time balance: {}",
+                    format_duration(summary.balance)
+                )))?;
+                tray_icon.set_icon(Some(circle_icon(color)?))?;
+                self.summary = Some(summary);
+            }
+            Err(e) => {
+                log::error!("Failed to refresh the balance: {e}");
+                tray_icon.set_tooltip(Some(format!("Failed to refresh the balance: {e}")))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn open_report(&self) -> Result<(), Error> {
+        let Some(summary) = &self.summary else {
+            return Ok(());
+        };
+        let path = std::env::temp_dir().join("clockify-flex-report.html");
+        std::fs::write(&path, &summary.report)?;
+        open::that(path)?;
+        Ok(())
+    }
+
+    /// Handle the pending messages, returns false when the application should quit.
+    fn handle_messages(&mut self) -> bool {
+        while let Ok(message) = self.receiver.try_recv() {
+            let result = match message {
+                TrayMessage::Refreshed(summary) => self.update(summary),
+                TrayMessage::Menu(event) if event.id == self.quit_id => return false,
+                TrayMessage::Menu(event) if event.id == self.refresh_id => {
+                    self.refresh.notify_one();
+                    Ok(())
+                }
+                TrayMessage::Menu(event) if event.id == self.report_id => self.open_report(),
+                TrayMessage::Tray(TrayIconEvent::Click {
+                    button: MouseButton::Left,
+                    button_state: MouseButtonState::Up,
+                    ..
+                }) => self.open_report(),
+                _ => Ok(()),
+            };
+            if let Err(e) = result {
+                log::error!("Tray error: {e}");
+            }
+        }
+        true
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn run_event_loop(mut app: TrayApp) -> Result<(), Error> {
+    use gtk::glib::{timeout_add_local, ControlFlow};
+
+    gtk::init()?;
+    app.build_icon()?;
+    timeout_add_local(POLL_INTERVAL, move || {
+        if app.handle_messages() {
+            ControlFlow::Continue
+        } else {
+            gtk::main_quit();
+            ControlFlow::Break
+        }
+    });
+    gtk::main();
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn run_event_loop(app: TrayApp) -> Result<(), Error> {
+    use std::time::Instant;
+    use winit::application::ApplicationHandler;
+    use winit::event::{StartCause, WindowEvent};
+    use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
+    use winit::window::WindowId;
+
+    struct Handler(TrayApp);
+
+    impl ApplicationHandler for Handler {
+        fn resumed(&mut self, _event_loop: &ActiveEventLoop) {}
+
+        fn window_event(&mut self, _: &ActiveEventLoop, _: WindowId, _: WindowEvent) {}
+
+        fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: StartCause) {
+            if cause == StartCause::Init {
+                if let Err(e) = self.0.build_icon() {
+                    log::error!("Failed to create the tray icon: {e}");
+                    event_loop.exit();
+                }
+            }
+        }
+
+        fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+            if !self.0.handle_messages() {
+                event_loop.exit();
+                return;
+            }
+            event_loop.set_control_flow(ControlFlow::WaitUntil(Instant::now() + POLL_INTERVAL));
+        }
+    }
+
+    let event_loop = EventLoop::new()?;
+    event_loop.run_app(&mut Handler(app))?;
+    Ok(())
+}
+
+/// Run the tray application until quit from the menu.
+pub(crate) fn run(token: Token, interval: Duration) -> Result<(), Error> {
+    let (sender, receiver) = mpsc::channel();
+    let refresh = Arc::new(Notify::new());
+
+    let menu_sender = sender.clone();
+    MenuEvent::set_event_handler(Some(move |event| {
+        let _ = menu_sender.send(TrayMessage::Menu(event));
+    }));
+    let tray_sender = sender.clone();
+    TrayIconEvent::set_event_handler(Some(move |event| {
+        let _ = tray_sender.send(TrayMessage::Tray(event));
+    }));
+
+    tokio::spawn(refresh_loop(token, interval, refresh.clone(), sender));
+
+    // The event loop blocks and has to stay on the main thread
+    tokio::task::block_in_place(|| run_event_loop(TrayApp::new(receiver, refresh)))
+}
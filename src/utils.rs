@@ -1,24 +1,47 @@
-pub(crate) mod cache;
-pub(crate) mod file_io;
-pub(crate) mod table;
+pub mod archive;
+pub mod audit;
+pub mod cache;
+pub mod clients;
+pub mod dirs;
+pub mod dump;
+pub mod file_io;
+pub mod holidays;
+pub mod http;
+pub mod insights;
+pub mod resolution;
+pub mod stats;
+pub mod table;
+pub mod tags;
 
 use crate::{
-    args::{LogLevel, LogOutput},
-    extra_settings::schema::ExtraSettings,
-    models::Day,
+    extra_settings::schema::{ExtraSettings, HolidayObservance},
+    models::{Day, Holiday, HolidayType},
 };
 use anyhow::Error;
 use chrono::{Datelike, Duration, NaiveDate, Utc, Weekday};
-use env_logger::Target;
-use lazy_static::lazy_static;
+use itertools::Itertools;
 use serde::Serialize;
-use std::{fs::OpenOptions, mem, path::Path};
-use tokio::{fs::File, io::AsyncWriteExt};
+use sha2::{Digest, Sha256};
+use std::{collections::HashSet, mem, path::Path, sync::OnceLock};
 
-lazy_static! {
-    pub(crate) static ref WORK_DAY_HOURS: f32 = 7.5;
+/// Hours of a work day without expected working hours in the extra settings
+pub const DEFAULT_WORK_DAY_HOURS: f32 = 7.5;
+
+static WORK_DAY_HOURS: OnceLock<f32> = OnceLock::new();
+
+/// Length of a work day of the run, for the days without expected working hours in the extra
+/// settings. Must be called before any of the balances are calculated.
+pub fn set_work_day_hours(hours: f32) -> Result<(), Error> {
+    WORK_DAY_HOURS
+        .set(hours)
+        .map_err(|_| Error::msg("Work day hours are already in use!"))
+}
+
+pub fn work_day_hours() -> f32 {
+    *WORK_DAY_HOURS.get_or_init(|| DEFAULT_WORK_DAY_HOURS)
 }
-pub(crate) struct DateRange(pub(crate) NaiveDate, pub(crate) NaiveDate);
+
+pub struct DateRange(pub NaiveDate, pub NaiveDate);
 
 impl Iterator for DateRange {
     type Item = NaiveDate;
@@ -32,23 +55,39 @@ impl Iterator for DateRange {
     }
 }
 
-pub(crate) fn not_in_future(date: &NaiveDate) -> bool {
+pub fn not_in_future(date: &NaiveDate) -> bool {
     &today() >= date
 }
 
-pub(crate) fn hours_to_hours_and_minutes(hours: f32) -> (i64, i64) {
+pub fn hours_to_hours_and_minutes(hours: f32) -> (i64, i64) {
     let whole_hours = hours.trunc() as i64;
     let minutes = ((hours - whole_hours as f32) * 60.0).round() as i64;
     (whole_hours, minutes.abs())
 }
 
-pub(crate) fn seconds_to_hours_and_minutes(seconds: i64) -> (i64, i64) {
+pub fn seconds_to_hours_and_minutes(seconds: i64) -> (i64, i64) {
     let hours = seconds / 3600;
     let minutes = (seconds % 3600) / 60;
     (hours, minutes.abs())
 }
 
-pub(crate) fn is_weekday(date: &NaiveDate) -> bool {
+/// Split seconds into work days of `day_hours`, hours and minutes
+pub fn seconds_to_days_hours_and_minutes(seconds: i64, day_hours: f32) -> (i64, i64, i64) {
+    let day_seconds = (day_hours * 3600f32) as i64;
+    let days = seconds / day_seconds;
+    let (hours, minutes) = seconds_to_hours_and_minutes(seconds % day_seconds);
+    (days, hours, minutes)
+}
+
+/// Lowercase hex of the SHA-256 digest
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+pub fn is_weekday(date: &NaiveDate) -> bool {
     [
         Weekday::Mon,
         Weekday::Tue,
@@ -59,25 +98,22 @@ pub(crate) fn is_weekday(date: &NaiveDate) -> bool {
     .contains(&date.weekday())
 }
 
-pub(crate) fn get_all_weekdays_since(date: NaiveDate) -> impl Iterator<Item = NaiveDate> {
+pub fn get_all_weekdays_since(date: NaiveDate) -> impl Iterator<Item = NaiveDate> {
     DateRange(date, today()).filter(is_weekday)
 }
 
-pub(crate) fn days_to_secs(day_count: usize) -> i64 {
-    (day_count as f32 * *WORK_DAY_HOURS * 3600f32) as i64
+pub fn days_to_secs(day_count: usize) -> i64 {
+    (day_count as f32 * work_day_hours() * 3600f32) as i64
 }
 
 /// Do proper calculations with ExtraSettings
-pub(crate) fn workdays_to_secs(
-    days: Vec<NaiveDate>,
-    extra_settings: &Option<ExtraSettings>,
-) -> i64 {
+pub fn workdays_to_secs(days: Vec<NaiveDate>, extra_settings: &Option<ExtraSettings>) -> i64 {
     if let Some(settings) = extra_settings {
         days.into_iter()
             .map(|d| {
                 settings
                     .expected_working_secs(&d)
-                    .unwrap_or((*WORK_DAY_HOURS * 3600f32) as i64)
+                    .unwrap_or(days_to_secs(1))
             })
             .sum()
     } else {
@@ -85,45 +121,85 @@ pub(crate) fn workdays_to_secs(
     }
 }
 
-pub(crate) fn today() -> NaiveDate {
+/// Start date when none is given or cached and the first time entry can't be looked up
+pub fn default_start_date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2022, 1, 1).unwrap()
+}
+
+pub fn today() -> NaiveDate {
     Utc::now().date_naive()
 }
 
-pub(crate) async fn get_public_holidays(since: &NaiveDate) -> Result<Vec<Day>, Error> {
-    let json_bytes = include_bytes!("../holidays.json");
-    let days = serde_json::from_slice::<Vec<Day>>(json_bytes).map_err(Error::from)?;
-    Ok(days
-        .into_iter()
-        .filter(|d| is_weekday(&d.date()) && &d.date() >= since)
-        .collect())
+/// Date a holiday is observed on. Weekend holidays move to the next free weekday, or with
+/// the nearest weekday observance Saturdays to the Friday before.
+fn observed_date(
+    date: NaiveDate,
+    observance: &HolidayObservance,
+    taken: &HashSet<NaiveDate>,
+) -> NaiveDate {
+    if is_weekday(&date) {
+        return date;
+    }
+    let mut observed = match observance {
+        HolidayObservance::Nominal => return date,
+        HolidayObservance::NearestWeekday if date.weekday() == Weekday::Sat => {
+            date - Duration::days(1)
+        }
+        HolidayObservance::NextWeekday | HolidayObservance::NearestWeekday => date,
+    };
+    while !is_weekday(&observed) || taken.contains(&observed) {
+        observed += Duration::days(1);
+    }
+    observed
+}
+
+pub async fn get_public_holidays(
+    since: &NaiveDate,
+    user_settings: &ExtraSettings,
+) -> Result<Vec<Day>, Error> {
+    Ok(observed_public_holidays(
+        holidays::load()?.holidays,
+        since,
+        user_settings,
+    ))
+}
+
+/// Public holidays on weekdays since the date, moved by the observance of the settings
+pub fn observed_public_holidays(
+    holidays: Vec<holidays::PublicHoliday>,
+    since: &NaiveDate,
+    user_settings: &ExtraSettings,
+) -> Vec<Day> {
+    // Holidays on weekdays keep their dates, so shifted ones don't land on them
+    let observance = user_settings.holiday_observance();
+    let mut taken = holidays
+        .iter()
+        .map(|h| h.date)
+        .filter(is_weekday)
+        .collect::<HashSet<NaiveDate>>();
+    let mut days = Vec::with_capacity(holidays.len());
+    for holiday in holidays.into_iter().sorted_by_key(|h| h.date) {
+        let date = observed_date(holiday.date, &observance, &taken);
+        if date != holiday.date {
+            log::info!("{} ({}) observed on {date}", holiday.title, holiday.date);
+            taken.insert(date);
+        }
+        if is_weekday(&date) && &date >= since {
+            days.push(Day::Holiday(Holiday::new(
+                holiday.title,
+                date,
+                HolidayType::PublicHoliday,
+            )));
+        }
+    }
+    days
 }
 
-pub(crate) async fn json_to_disk<T, P>(path: P, value: &T) -> Result<(), Error>
+pub async fn json_to_disk<T, P>(path: P, value: &T) -> Result<(), Error>
 where
     T: ?Sized + Serialize,
     P: AsRef<Path>,
 {
     let datat = serde_json::to_string_pretty(value)?;
-    let mut file = File::create(path).await?;
-    file.write_all(datat.as_bytes()).await.map_err(Error::from)
-}
-
-/// Setup logger
-pub(crate) fn setup_log(output: &LogOutput, level: &LogLevel) -> Result<(), Error> {
-    // TODO Async logger: https://docs.rs/tracing/0.1.40/tracing/
-    env_logger::builder()
-        .filter_level(level.clone().into())
-        .target(match output {
-            LogOutput::File => {
-                let log_file = OpenOptions::new()
-                    .append(true) // Open in append mode
-                    .create(true) // Create the file if it doesn't exist
-                    .open(".log")?;
-                let boxed_log_file = Box::new(log_file);
-                Target::Pipe(boxed_log_file)
-            }
-            LogOutput::Console => Target::Stdout,
-        })
-        .init();
-    Ok(())
+    dirs::write_atomic(path.as_ref(), datat.as_bytes())
 }
@@ -0,0 +1,210 @@
+use crate::clockify::{TimeEntry, TimeOffItem};
+use crate::utils::dirs;
+use crate::utils::holidays::PublicHoliday;
+use anyhow::Error;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Format version of the archive files, increased on incompatible changes
+pub const ARCHIVE_VERSION: u32 = 1;
+pub const ENTRIES_FILE: &str = "entries.json";
+pub const TIME_OFF_FILE: &str = "timeoff.json";
+pub const HOLIDAYS_FILE: &str = "holidays.json";
+const FILES: [&str; 3] = [ENTRIES_FILE, TIME_OFF_FILE, HOLIDAYS_FILE];
+
+/// Archive imported with `import`, read without the network
+pub fn imported_dir() -> PathBuf {
+    dirs::cache_path("archive")
+}
+
+/// One file of the archive, the items with the metadata of the export
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveFile<T> {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    /// Items before the date are not included
+    pub since: NaiveDate,
+    pub items: Vec<T>,
+}
+
+/// Time entry with the fields the calculations use, independent of the API format
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedEntry {
+    pub description: String,
+    /// Empty without a project
+    pub project: String,
+    pub tags: Vec<String>,
+    pub user_id: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl From<&TimeEntry> for ArchivedEntry {
+    fn from(entry: &TimeEntry) -> Self {
+        Self {
+            description: entry.description.clone(),
+            project: entry.project_name.clone(),
+            tags: entry.tags.clone(),
+            user_id: entry.user_id.clone(),
+            start: entry.start,
+            end: entry.end,
+        }
+    }
+}
+
+impl From<ArchivedEntry> for TimeEntry {
+    fn from(entry: ArchivedEntry) -> Self {
+        Self {
+            description: entry.description,
+            project_name: entry.project,
+            tags: entry.tags,
+            user_id: entry.user_id,
+            start: entry.start,
+            end: entry.end,
+        }
+    }
+}
+
+/// Time off request with its status, independent of the API format
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedTimeOff {
+    pub policy_id: String,
+    pub policy_name: String,
+    pub note: String,
+    /// APPROVED or PENDING
+    pub status: String,
+    pub user_id: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl From<&TimeOffItem> for ArchivedTimeOff {
+    fn from(item: &TimeOffItem) -> Self {
+        Self {
+            policy_id: item.policy_id.clone(),
+            policy_name: item.policy_name.clone(),
+            note: item.note.clone(),
+            status: item.status.clone(),
+            user_id: item.user_id.clone(),
+            start: item.start,
+            end: item.end,
+        }
+    }
+}
+
+impl From<ArchivedTimeOff> for TimeOffItem {
+    fn from(item: ArchivedTimeOff) -> Self {
+        Self {
+            note: item.note,
+            user_id: item.user_id,
+            policy_id: item.policy_id,
+            policy_name: item.policy_name,
+            start: item.start,
+            end: item.end,
+            status: item.status,
+        }
+    }
+}
+
+/// Fetched data of a user for backups and external analysis
+pub struct Archive {
+    pub since: NaiveDate,
+    pub entries: Vec<TimeEntry>,
+    pub time_off_items: Vec<TimeOffItem>,
+    /// Public holidays of the dataset, before the observance of the settings
+    pub holidays: Vec<PublicHoliday>,
+    /// Time of the export, the oldest of the files when read
+    pub exported_at: DateTime<Utc>,
+}
+
+fn write_file<T: Serialize>(
+    dir: &Path,
+    name: &str,
+    archive: &Archive,
+    items: Vec<T>,
+) -> Result<(), Error> {
+    let file = ArchiveFile {
+        version: ARCHIVE_VERSION,
+        exported_at: archive.exported_at,
+        since: archive.since,
+        items,
+    };
+    std::fs::write(dir.join(name), serde_json::to_string_pretty(&file)?)?;
+    Ok(())
+}
+
+fn read_file<T: DeserializeOwned>(dir: &Path, name: &str) -> Result<ArchiveFile<T>, Error> {
+    let path = dir.join(name);
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| Error::msg(format!("Failed to read {}: {e}", path.display())))?;
+    let file: ArchiveFile<T> = serde_json::from_str(&text)
+        .map_err(|e| Error::msg(format!("Invalid {}: {e}", path.display())))?;
+    if file.version > ARCHIVE_VERSION {
+        return Err(Error::msg(format!(
+            "{} has version {}, this version reads up to {ARCHIVE_VERSION}. Please update.",
+            path.display(),
+            file.version
+        )));
+    }
+    Ok(file)
+}
+
+impl Archive {
+    /// Read the archive files of the directory
+    pub fn load(dir: &Path) -> Result<Self, Error> {
+        let entries = read_file::<ArchivedEntry>(dir, ENTRIES_FILE)?;
+        let time_off = read_file::<ArchivedTimeOff>(dir, TIME_OFF_FILE)?;
+        let holidays = read_file::<PublicHoliday>(dir, HOLIDAYS_FILE)?;
+        let since = entries.since.max(time_off.since).max(holidays.since);
+        let exported_at = entries
+            .exported_at
+            .min(time_off.exported_at)
+            .min(holidays.exported_at);
+        let mut entries = entries
+            .items
+            .into_iter()
+            .map(TimeEntry::from)
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.start);
+        Ok(Self {
+            since,
+            entries,
+            time_off_items: time_off.items.into_iter().map(TimeOffItem::from).collect(),
+            holidays: holidays.items,
+            exported_at,
+        })
+    }
+
+    /// Check the archive of the directory and copy it to the cache directory, replacing the
+    /// previously imported one
+    pub fn import(dir: &Path) -> Result<Self, Error> {
+        let archive = Self::load(dir)?;
+        let target = imported_dir();
+        std::fs::create_dir_all(&target)?;
+        for name in FILES {
+            std::fs::copy(dir.join(name), target.join(name))?;
+        }
+        Ok(archive)
+    }
+
+    /// Write the archive files to the directory, created if it doesn't exist. Existing files
+    /// are overwritten.
+    pub fn save(&self, dir: &Path) -> Result<(), Error> {
+        std::fs::create_dir_all(dir)?;
+        let entries = self.entries.iter().map(ArchivedEntry::from).collect();
+        write_file::<ArchivedEntry>(dir, ENTRIES_FILE, self, entries)?;
+        let time_off = self
+            .time_off_items
+            .iter()
+            .map(ArchivedTimeOff::from)
+            .collect();
+        write_file::<ArchivedTimeOff>(dir, TIME_OFF_FILE, self, time_off)?;
+        write_file(dir, HOLIDAYS_FILE, self, self.holidays.clone())?;
+        Ok(())
+    }
+}
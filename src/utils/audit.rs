@@ -0,0 +1,83 @@
+use super::dirs;
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+/// Append-only log of the write operations, one JSON object per line
+pub fn log_path() -> PathBuf {
+    dirs::cache_path("audit.jsonl")
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub enum AuditAction {
+    Create,
+    Undo,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub action: AuditAction,
+    /// ID of the created resource in the response
+    pub response_id: String,
+    /// API path of the created resource, deleted on undo
+    pub resource_path: String,
+    pub payload: Value,
+}
+
+impl AuditEntry {
+    pub fn new(
+        action: AuditAction,
+        response_id: String,
+        resource_path: String,
+        payload: Value,
+    ) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            action,
+            response_id,
+            resource_path,
+            payload,
+        }
+    }
+}
+
+pub fn append(entry: &AuditEntry) -> Result<(), Error> {
+    let path = log_path();
+    dirs::create_parent(&path)?;
+    let mut file = OpenOptions::new().append(true).create(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+pub fn read_entries() -> Result<Vec<AuditEntry>, Error> {
+    let path = log_path();
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let reader = BufReader::new(std::fs::File::open(path)?);
+    reader
+        .lines()
+        .filter(|line| !line.as_ref().is_ok_and(|l| l.trim().is_empty()))
+        .map(|line| Ok(serde_json::from_str(&line?)?))
+        .collect()
+}
+
+/// Created resources that have not been undone, oldest first
+pub fn undoable_entries() -> Result<Vec<AuditEntry>, Error> {
+    let entries = read_entries()?;
+    let undone = entries
+        .iter()
+        .filter(|e| e.action == AuditAction::Undo)
+        .map(|e| e.response_id.clone())
+        .collect::<Vec<String>>();
+    Ok(entries
+        .into_iter()
+        .filter(|e| e.action == AuditAction::Create && !undone.contains(&e.response_id))
+        .collect())
+}
@@ -1,42 +1,107 @@
 use crate::clockify::Token;
+use crate::utils::dirs;
 use anyhow::Error;
 use chrono::NaiveDate;
-use std::{
-    collections::HashMap,
-    io::{Read, Write},
-    path::Path,
-};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, io::Read, path::PathBuf};
 
-type CachedDates = HashMap<Token, NaiveDate>;
+/// Keyed by the token hashes
+type CachedDates = HashMap<String, NaiveDate>;
+
+fn is_token_hash(key: &str) -> bool {
+    key.len() == 64 && key.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Older caches were keyed by the raw tokens, hash them so that they are never saved again
+fn migrate_keys<T>(cached: HashMap<String, T>) -> HashMap<String, T> {
+    cached
+        .into_iter()
+        .map(|(key, value)| {
+            if is_token_hash(&key) {
+                (key, value)
+            } else {
+                (Token::new(&key).hash(), value)
+            }
+        })
+        .collect()
+}
+
+fn cache_file_path() -> PathBuf {
+    dirs::cache_path("cache")
+}
 
 fn read_cached_dates() -> Result<CachedDates, Error> {
-    let path = Path::new(".clockify-rust");
+    let path = cache_file_path();
     if path.exists() && path.is_file() {
         let mut file = std::fs::File::open(path)?;
         let mut bytes = Vec::new();
         file.read_to_end(&mut bytes)?;
-        Ok(bincode::deserialize(bytes.as_slice())?)
+        Ok(migrate_keys(bincode::deserialize(bytes.as_slice())?))
     } else {
         Ok(HashMap::new())
     }
 }
 
-fn save_cached_dates(dates: HashMap<Token, NaiveDate>) -> Result<(), Error> {
-    let path = Path::new(".clockify-rust");
+fn save_cached_dates(dates: CachedDates) -> Result<(), Error> {
     let bytes = bincode::serialize(&dates)?;
-    let mut file = std::fs::File::create(path)?;
-    file.write_all(bytes.as_slice())?;
-    Ok(())
+    dirs::write_atomic(&cache_file_path(), &bytes)
 }
 
-pub(crate) fn set_cache_first_date(token: &Token, date: &NaiveDate) -> Result<(), Error> {
+pub fn set_cache_first_date(token: &Token, date: &NaiveDate) -> Result<(), Error> {
     let mut cached_dates: CachedDates = read_cached_dates()?;
-    cached_dates.insert(token.clone(), *date);
+    cached_dates.insert(token.hash(), *date);
     save_cached_dates(cached_dates)?;
     Ok(())
 }
 
-pub(crate) fn get_cache_first_date(token: &Token) -> Result<Option<NaiveDate>, Error> {
+pub fn get_cache_first_date(token: &Token) -> Result<Option<NaiveDate>, Error> {
     let cached_dates: CachedDates = read_cached_dates()?;
-    Ok(cached_dates.get(token).copied())
+    Ok(cached_dates.get(&token.hash()).copied())
+}
+
+/// Balance of a run, in seconds
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Snapshot {
+    pub date: NaiveDate,
+    pub balance: i64,
+    pub worked: i64,
+    pub expected: i64,
+}
+
+/// Keyed by the token hashes
+type CachedSnapshots = HashMap<String, Vec<Snapshot>>;
+
+fn history_path() -> PathBuf {
+    dirs::cache_path("history")
+}
+
+fn read_cached_snapshots() -> Result<CachedSnapshots, Error> {
+    let path = history_path();
+    if path.exists() && path.is_file() {
+        let bytes = std::fs::read(path)?;
+        Ok(migrate_keys(bincode::deserialize(bytes.as_slice())?))
+    } else {
+        Ok(HashMap::new())
+    }
+}
+
+fn save_cached_snapshots(snapshots: &CachedSnapshots) -> Result<(), Error> {
+    let bytes = bincode::serialize(snapshots)?;
+    dirs::write_atomic(&history_path(), &bytes)
+}
+
+/// Save the snapshot of the token, replacing an earlier snapshot of the same date
+pub fn add_snapshot(token: &Token, snapshot: Snapshot) -> Result<(), Error> {
+    let mut cached_snapshots = read_cached_snapshots()?;
+    let snapshots = cached_snapshots.entry(token.hash()).or_default();
+    snapshots.retain(|s| s.date != snapshot.date);
+    snapshots.push(snapshot);
+    snapshots.sort_by_key(|s| s.date);
+    save_cached_snapshots(&cached_snapshots)
+}
+
+/// Snapshots of the token in date order
+pub fn get_snapshots(token: &Token) -> Result<Vec<Snapshot>, Error> {
+    let mut cached_snapshots = read_cached_snapshots()?;
+    Ok(cached_snapshots.remove(&token.hash()).unwrap_or_default())
 }
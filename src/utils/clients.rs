@@ -0,0 +1,79 @@
+use crate::extra_settings::schema::ExtraSettings;
+use crate::models::WorkDay;
+use crate::utils;
+use crate::utils::table::format_duration;
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+/// Label of the entries whose project has no client
+const NO_CLIENT: &str = "(no client)";
+
+pub struct ClientBreakdown {
+    /// Client with its project count, entry count and logged seconds, most time first
+    clients: Vec<(String, usize, usize, i64)>,
+    total_sec: i64,
+}
+
+impl ClientBreakdown {
+    /// Logged time per client, mapped from the project name of the entries
+    pub fn new(
+        working_days: &[WorkDay],
+        project_clients: &HashMap<String, String>,
+        user_settings: &ExtraSettings,
+        include_today: bool,
+    ) -> Self {
+        let mut clients: HashMap<&str, (HashSet<&str>, usize, i64)> = HashMap::new();
+        let mut total_sec = 0;
+        for item in working_days
+            .iter()
+            .filter(|wd| include_today || wd.date < utils::today())
+            .filter(|wd| !user_settings.is_ignored_work_day(wd))
+            .flat_map(|wd| &wd.items)
+        {
+            let duration = item.duration();
+            total_sec += duration;
+            let name = project_clients
+                .get(item.project())
+                .map_or(NO_CLIENT, String::as_str);
+            let entry = clients.entry(name).or_default();
+            if !item.project().is_empty() {
+                entry.0.insert(item.project());
+            }
+            entry.1 += 1;
+            entry.2 += duration;
+        }
+
+        let clients = clients
+            .into_iter()
+            .map(|(name, (projects, count, secs))| (name.to_string(), projects.len(), count, secs))
+            .sorted_by(|a, b| b.3.cmp(&a.3).then(a.0.cmp(&b.0)))
+            .collect();
+        Self { clients, total_sec }
+    }
+}
+
+impl std::fmt::Display for ClientBreakdown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.clients.is_empty() {
+            return writeln!(f, "No time entries to break down.");
+        }
+
+        let mut builder = Builder::default();
+        builder.push_record(["Client", "Projects", "Entries", "Duration", "Share"]);
+        for (name, project_count, count, secs) in &self.clients {
+            let share = *secs as f32 / self.total_sec.max(1) as f32 * 100.0;
+            builder.push_record([
+                name.clone(),
+                project_count.to_string(),
+                count.to_string(),
+                format_duration(*secs),
+                format!("{share:.0} %"),
+            ]);
+        }
+        let mut table = builder.build();
+        table.with(Style::modern_rounded());
+        writeln!(f, "{table}")
+    }
+}
@@ -0,0 +1,120 @@
+use anyhow::Error;
+use directories::ProjectDirs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Configuration and cache directories of the tool
+struct Dirs {
+    config: PathBuf,
+    cache: PathBuf,
+}
+
+static DIRS: OnceLock<Dirs> = OnceLock::new();
+
+/// Files of older versions in the working directory, with their config or cache file names
+const LEGACY_CONFIG_FILES: [(&str, &str); 1] = [(".settings.json", "settings.json")];
+const LEGACY_CACHE_FILES: [(&str, &str); 5] = [
+    (".clockify-rust", "cache"),
+    (".clockify-rust-history", "history"),
+    (".audit.jsonl", "audit.jsonl"),
+    (".holidays.json", "holidays.json"),
+    (".log", "log"),
+];
+
+fn default_dirs() -> Dirs {
+    match ProjectDirs::from("", "", "clockify-flex") {
+        Some(dirs) => Dirs {
+            config: dirs.config_dir().to_path_buf(),
+            cache: dirs.cache_dir().to_path_buf(),
+        },
+        // No home directory, fall back to the working directory
+        None => Dirs {
+            config: PathBuf::from("."),
+            cache: PathBuf::from("."),
+        },
+    }
+}
+
+fn dirs() -> &'static Dirs {
+    DIRS.get_or_init(default_dirs)
+}
+
+/// Use the directories instead of ~/.config/clockify-flex and ~/.cache/clockify-flex, or the
+/// platform equivalents. Must be called before any of the paths are used.
+pub fn init(config: Option<PathBuf>, cache: Option<PathBuf>) -> Result<(), Error> {
+    let defaults = default_dirs();
+    let dirs = Dirs {
+        config: config.unwrap_or(defaults.config),
+        cache: cache.unwrap_or(defaults.cache),
+    };
+    DIRS.set(dirs)
+        .map_err(|_| Error::msg("Directories are already in use!"))
+}
+
+fn move_file(from: &Path, to: &Path) -> Result<(), Error> {
+    create_parent(to)?;
+    // Renaming fails across file systems
+    if std::fs::rename(from, to).is_err() {
+        std::fs::copy(from, to)?;
+        std::fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+/// Move the files of older versions from the working directory, if they don't exist yet
+pub fn migrate() -> Result<(), Error> {
+    let legacy_files = LEGACY_CONFIG_FILES
+        .iter()
+        .map(|(legacy, name)| (legacy, config_path(name)))
+        .chain(
+            LEGACY_CACHE_FILES
+                .iter()
+                .map(|(legacy, name)| (legacy, cache_path(name))),
+        );
+    for (legacy, path) in legacy_files {
+        let legacy = Path::new(legacy);
+        if legacy.is_file() && !path.exists() {
+            move_file(legacy, &path)?;
+            eprintln!("Moved {} to {}", legacy.display(), path.display());
+        }
+    }
+    Ok(())
+}
+
+pub fn config_dir() -> &'static Path {
+    &dirs().config
+}
+
+pub fn cache_dir() -> &'static Path {
+    &dirs().cache
+}
+
+/// Path of the file in the config directory
+pub fn config_path(name: &str) -> PathBuf {
+    config_dir().join(name)
+}
+
+/// Path of the file in the cache directory
+pub fn cache_path(name: &str) -> PathBuf {
+    cache_dir().join(name)
+}
+
+/// Create the parent directory of the path before writing to it
+pub fn create_parent(path: &Path) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    Ok(())
+}
+
+/// Write the file through a temporary file next to it, so that an interrupted run leaves
+/// either the old or the new contents
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), Error> {
+    create_parent(path)?;
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    let temporary = path.with_file_name(name);
+    std::fs::write(&temporary, bytes)?;
+    std::fs::rename(&temporary, path)?;
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use crate::clockify::{parse_records, TimeEntry, TimeOffItem};
+use anyhow::Error;
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// Raw responses saved with --debug, read back with --from-dump
+pub struct Dump {
+    pub time_entries: Vec<TimeEntry>,
+    pub time_off_items: Vec<TimeOffItem>,
+}
+
+/// Read the dump files, recognized by the prefix of the file name
+pub fn load(paths: &[PathBuf], strict: bool) -> Result<Dump, Error> {
+    let mut dump = Dump {
+        time_entries: Vec::new(),
+        time_off_items: Vec::new(),
+    };
+    for path in paths {
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        // The user of the dumps is only used by --replay
+        if file_name.starts_with("user_") {
+            continue;
+        }
+        let values = serde_json::from_str::<Vec<Value>>(&std::fs::read_to_string(path)?)
+            .map_err(|e| Error::msg(format!("Failed to read {}: {e}", path.display())))?;
+        if file_name.starts_with("work_items_") {
+            dump.time_entries
+                .extend(parse_records::<TimeEntry>(values, "time entry", strict)?);
+        } else if file_name.starts_with("time_off_items_") {
+            // Pending requests are dumped to the same files
+            dump.time_off_items.extend(
+                parse_records::<TimeOffItem>(values, "time off request", strict)?
+                    .into_iter()
+                    .filter(|item| item.status == "APPROVED"),
+            );
+        } else {
+            return Err(Error::msg(format!(
+                "Unknown dump file {}, expected work_items_*.json or time_off_items_*.json",
+                path.display()
+            )));
+        }
+    }
+    dump.time_entries.sort_by_key(|entry| entry.start);
+    Ok(dump)
+}
@@ -0,0 +1,144 @@
+use super::{dirs, http, sha256_hex};
+use anyhow::Error;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+const RELEASE_URL: &str =
+    "https://github.com/Lemminkyinen/clockify-flex-rust/releases/latest/download/";
+
+/// Country of the bundled dataset
+const BUNDLED_COUNTRY: &str = "FI";
+
+/// Dataset shipped with the binary
+const BUNDLED: &str = include_str!("../../holidays.json");
+
+static COUNTRY: OnceLock<String> = OnceLock::new();
+
+/// Use the public holidays of the country, an uppercase ISO 3166 code. Must be called before
+/// the dataset is loaded.
+pub fn set_country(country: &str) -> Result<(), Error> {
+    COUNTRY
+        .set(country.to_owned())
+        .map_err(|_| Error::msg("Holidays country is already in use!"))
+}
+
+pub fn country() -> &'static str {
+    COUNTRY.get_or_init(|| BUNDLED_COUNTRY.to_owned())
+}
+
+/// Name of the dataset of the country in the releases and in the cache directory
+fn dataset_file() -> String {
+    match country() {
+        BUNDLED_COUNTRY => "holidays.json".into(),
+        country => format!("holidays-{}.json", country.to_lowercase()),
+    }
+}
+
+/// Downloaded dataset, used instead of the bundled one when it exists
+pub fn dataset_path() -> PathBuf {
+    dirs::cache_path(&dataset_file())
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PublicHoliday {
+    pub date: NaiveDate,
+    pub title: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Dataset {
+    pub version: String,
+    pub holidays: Vec<PublicHoliday>,
+}
+
+/// The bundled dataset is a plain list of holidays
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DatasetFile {
+    Versioned(Dataset),
+    Plain(Vec<PublicHoliday>),
+}
+
+impl Dataset {
+    fn parse(json: &str, default_version: &str) -> Result<Self, Error> {
+        Ok(match serde_json::from_str::<DatasetFile>(json)? {
+            DatasetFile::Versioned(dataset) => dataset,
+            DatasetFile::Plain(holidays) => Dataset {
+                version: default_version.to_string(),
+                holidays,
+            },
+        })
+    }
+
+    pub fn first_date(&self) -> Option<NaiveDate> {
+        self.holidays.iter().map(|h| h.date).min()
+    }
+
+    pub fn last_date(&self) -> Option<NaiveDate> {
+        self.holidays.iter().map(|h| h.date).max()
+    }
+}
+
+/// Where the dataset was loaded from
+pub fn source() -> String {
+    let path = dataset_path();
+    if path.is_file() {
+        path.display().to_string()
+    } else {
+        "bundled".into()
+    }
+}
+
+/// The downloaded dataset if there is one, otherwise the bundled one. Only the holidays of
+/// the bundled country are shipped with the binary.
+pub fn load() -> Result<Dataset, Error> {
+    let path = dataset_path();
+    if path.is_file() {
+        let json = std::fs::read_to_string(path)?;
+        return Dataset::parse(&json, "unknown");
+    }
+    if country() != BUNDLED_COUNTRY {
+        return Err(Error::msg(format!(
+            "No holidays dataset of {}, download it with `holidays update`!",
+            country()
+        )));
+    }
+    Dataset::parse(BUNDLED, concat!("bundled-", env!("CARGO_PKG_VERSION")))
+}
+
+/// Download the latest dataset from the releases and save it after verifying the checksum
+pub async fn update() -> Result<Dataset, Error> {
+    let client = http::client()?;
+    let get = |file: &str| {
+        let request = client.get(format!("{RELEASE_URL}{file}"));
+        async move {
+            let response = request.send().await?.error_for_status()?;
+            Ok::<_, Error>(response.bytes().await?)
+        }
+    };
+    let dataset_file = dataset_file();
+    let dataset_bytes = get(&dataset_file).await?;
+    let checksum_bytes = get(&format!("{dataset_file}.sha256")).await?;
+
+    // Checksum files have the hash first, optionally followed by the file name
+    let checksum = String::from_utf8_lossy(&checksum_bytes);
+    let expected = checksum
+        .split_whitespace()
+        .next()
+        .ok_or(Error::msg("Checksum file is empty!"))?
+        .to_lowercase();
+    let actual = sha256_hex(&dataset_bytes);
+    if expected != actual {
+        return Err(Error::msg(format!(
+            "Checksum mismatch! Expected {expected}, got {actual}."
+        )));
+    }
+
+    let json = std::str::from_utf8(&dataset_bytes)?;
+    let dataset = Dataset::parse(json, &actual[..12])?;
+    let json = serde_json::to_string_pretty(&dataset)?;
+    dirs::write_atomic(&dataset_path(), json.as_bytes())?;
+    Ok(dataset)
+}
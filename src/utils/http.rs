@@ -0,0 +1,75 @@
+use anyhow::Error;
+use reqwest::{Certificate, Client, ClientBuilder, NoProxy, Proxy};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+use url::Url;
+
+/// Network options of all the HTTP clients, the Clockify API as well as e.g. the extra
+/// settings URL and the holidays dataset
+#[derive(Clone, Debug)]
+pub struct HttpOptions {
+    /// Proxy of all the requests. Without one, HTTPS_PROXY, HTTP_PROXY and ALL_PROXY are used.
+    pub proxy: Option<Url>,
+    /// PEM file of root certificates trusted in addition to the system ones, e.g. of a proxy
+    /// intercepting TLS
+    pub ca_bundle: Option<PathBuf>,
+    /// Limit of one request from connecting until the end of the response, so that a stuck
+    /// connection fails instead of hanging
+    pub timeout: Duration,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            ca_bundle: None,
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+static OPTIONS: OnceLock<HttpOptions> = OnceLock::new();
+
+/// Use the options in the clients. Must be called before the first request.
+pub fn set_options(options: HttpOptions) -> Result<(), Error> {
+    OPTIONS
+        .set(options)
+        .map_err(|_| Error::msg("HTTP options are already in use!"))
+}
+
+pub fn options() -> &'static HttpOptions {
+    OPTIONS.get_or_init(HttpOptions::default)
+}
+
+/// Client builder with the proxy, the root certificates and the timeout of the options
+pub fn client_builder() -> Result<ClientBuilder, Error> {
+    let options = options();
+    let mut builder = Client::builder().timeout(options.timeout);
+    if let Some(url) = &options.proxy {
+        // NO_PROXY still applies to the given proxy
+        let proxy = Proxy::all(url.clone())?.no_proxy(NoProxy::from_env());
+        builder = builder.proxy(proxy);
+    }
+    if let Some(path) = &options.ca_bundle {
+        let pem = std::fs::read(path)
+            .map_err(|e| Error::msg(format!("Failed to read {}: {e}", path.display())))?;
+        let certificates = Certificate::from_pem_bundle(&pem)
+            .map_err(|e| Error::msg(format!("Invalid certificates in {}: {e}", path.display())))?;
+        if certificates.is_empty() {
+            return Err(Error::msg(format!(
+                "No PEM certificates in {}!",
+                path.display()
+            )));
+        }
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    Ok(builder)
+}
+
+/// Client with the options
+pub fn client() -> Result<Client, Error> {
+    Ok(client_builder()?.build()?)
+}
@@ -0,0 +1,121 @@
+use crate::extra_settings::schema::{DayType, ExtraSettings};
+use crate::models::Day;
+use crate::utils;
+use chrono::{Datelike, Weekday};
+use std::collections::HashMap;
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+const WEEKDAYS: [Weekday; 5] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+];
+
+/// Day types that are analyzed, with their plural labels
+const TYPES: [(DayType, &str); 4] = [
+    (DayType::SickLeave, "Sick leaves"),
+    (DayType::Flex, "Flex days"),
+    (DayType::Vacation, "Vacations"),
+    (DayType::ParentalLeave, "Parental leaves"),
+];
+
+/// Minimum amount of days before a type is looked for patterns
+const MIN_DAYS: usize = 5;
+/// Share of the days on a single weekday that counts as a cluster. Even spread would be 20 %.
+const CLUSTER_SHARE: f32 = 0.4;
+
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "Mondays",
+        Weekday::Tue => "Tuesdays",
+        Weekday::Wed => "Wednesdays",
+        Weekday::Thu => "Thursdays",
+        Weekday::Fri => "Fridays",
+        Weekday::Sat => "Saturdays",
+        Weekday::Sun => "Sundays",
+    }
+}
+
+pub struct Insights {
+    /// Day counts per weekday, Monday first
+    distribution: Vec<(&'static str, [usize; 5])>,
+    findings: Vec<String>,
+}
+
+impl Insights {
+    /// Day type × weekday distribution of the days off and the patterns found in it
+    pub fn new(days_off: &[Day], user_settings: &ExtraSettings) -> Self {
+        let mut counts: HashMap<DayType, [usize; 5]> = HashMap::new();
+        for day in days_off
+            .iter()
+            .filter(|d| utils::is_weekday(&d.date()) && !user_settings.is_ignored(d))
+        {
+            let weekday = day.date().weekday().num_days_from_monday() as usize;
+            counts.entry(day.type_()).or_insert([0; 5])[weekday] += 1;
+        }
+
+        let mut distribution = Vec::new();
+        let mut findings = Vec::new();
+        for (type_, label) in TYPES {
+            let Some(days) = counts.get(&type_) else {
+                continue;
+            };
+            distribution.push((label, *days));
+
+            let total = days.iter().sum::<usize>();
+            if total < MIN_DAYS {
+                continue;
+            }
+            for (i, count) in days.iter().enumerate() {
+                let share = *count as f32 / total as f32;
+                if share >= CLUSTER_SHARE {
+                    let weekday = weekday_name(WEEKDAYS[i]);
+                    let text = if *count == total {
+                        format!("{label} are always on {weekday} ({total} days)")
+                    } else {
+                        format!(
+                            "{label} cluster on {weekday} ({:.0} % of {total} days)",
+                            share * 100.0
+                        )
+                    };
+                    findings.push(text);
+                }
+            }
+        }
+
+        Self {
+            distribution,
+            findings,
+        }
+    }
+}
+
+impl std::fmt::Display for Insights {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.distribution.is_empty() {
+            return writeln!(f, "No days off to analyze.");
+        }
+
+        let mut builder = Builder::default();
+        builder.push_record(["Type", "Mon", "Tue", "Wed", "Thu", "Fri"]);
+        for (label, days) in &self.distribution {
+            let mut record = vec![label.to_string()];
+            record.extend(days.iter().map(usize::to_string));
+            builder.push_record(record);
+        }
+        let mut table = builder.build();
+        table.with(Style::modern_rounded());
+        writeln!(f, "{table}")?;
+
+        if self.findings.is_empty() {
+            writeln!(f, "No patterns found.")?;
+        }
+        for finding in &self.findings {
+            writeln!(f, "- {finding}")?;
+        }
+        Ok(())
+    }
+}
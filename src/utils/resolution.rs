@@ -0,0 +1,122 @@
+use crate::extra_settings::schema::{DayType, ExtraSettings};
+use crate::models::Day;
+use chrono::NaiveDate;
+use itertools::Itertools;
+use std::collections::{BTreeMap, HashSet};
+
+/// Used for the day types missing from the dayPrecedence setting, first wins
+pub const DEFAULT_PRECEDENCE: [DayType; 7] = [
+    DayType::SickLeave,
+    DayType::PublicHoliday,
+    DayType::ParentalLeave,
+    DayType::Vacation,
+    DayType::UnpaidLeave,
+    DayType::Flex,
+    DayType::Unknown,
+];
+
+/// Date with more than one classification
+#[derive(Clone, Debug)]
+pub struct Conflict {
+    pub date: NaiveDate,
+    pub chosen: DayType,
+    pub dropped: Vec<DayType>,
+}
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let dropped = self.dropped.iter().map(|t| format!("{t:?}")).join(", ");
+        write!(f, "{}: {:?} overrides {dropped}", self.date, self.chosen)
+    }
+}
+
+impl Conflict {
+    /// A vacation day on a public holiday, which is not used from the vacation days
+    pub fn reclaims_vacation(&self) -> bool {
+        self.chosen == DayType::PublicHoliday && self.dropped.contains(&DayType::Vacation)
+    }
+}
+
+pub struct Resolution {
+    pub public_holidays: Vec<Day>,
+    pub days_off: Vec<Day>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Merge the public holidays and days off into exactly one classification per date. Ignored
+/// days are dropped first, and on conflicts the type earliest in the precedence wins.
+pub fn resolve_days(
+    public_holidays: Vec<Day>,
+    days_off: Vec<Day>,
+    user_settings: &ExtraSettings,
+) -> Resolution {
+    let precedence = user_settings.day_precedence();
+    let rank = |type_: &DayType| {
+        precedence
+            .iter()
+            .chain(DEFAULT_PRECEDENCE.iter())
+            .position(|t| t == type_)
+            .unwrap_or(usize::MAX)
+    };
+
+    let mut dates: BTreeMap<NaiveDate, Vec<Day>> = BTreeMap::new();
+    for day in public_holidays.into_iter().chain(days_off) {
+        if !user_settings.is_ignored(&day) {
+            dates.entry(day.date()).or_default().push(day);
+        }
+    }
+
+    let mut resolution = Resolution {
+        public_holidays: Vec::new(),
+        days_off: Vec::new(),
+        conflicts: Vec::new(),
+    };
+    for (date, days) in dates {
+        let mut days = days
+            .into_iter()
+            .sorted_by_key(|d| rank(&d.type_()))
+            .collect_vec();
+        let chosen = days.remove(0);
+        let dropped = days
+            .iter()
+            .map(Day::type_)
+            .filter(|t| *t != chosen.type_())
+            .unique()
+            .collect_vec();
+        if !dropped.is_empty() {
+            let conflict = Conflict {
+                date,
+                chosen: chosen.type_(),
+                dropped,
+            };
+            log::warn!("Conflicting day types on {conflict}");
+            resolution.conflicts.push(conflict);
+        }
+
+        match chosen.type_() {
+            DayType::PublicHoliday => resolution.public_holidays.push(chosen),
+            _ => resolution.days_off.push(chosen),
+        }
+    }
+    resolution
+}
+
+/// Pending days off on the dates without a public holiday or an approved day off, with one
+/// type per date like the approved days off
+pub fn resolve_pending(
+    pending_days_off: Vec<Day>,
+    public_holidays: &[Day],
+    days_off: &[Day],
+    user_settings: &ExtraSettings,
+) -> Vec<Day> {
+    let booked = public_holidays
+        .iter()
+        .chain(days_off)
+        .map(Day::date)
+        .collect::<HashSet<NaiveDate>>();
+    let pending_days_off = pending_days_off
+        .into_iter()
+        .filter(|d| !booked.contains(&d.date()))
+        .collect_vec();
+    resolve_days(Vec::new(), pending_days_off, user_settings).days_off
+}
@@ -0,0 +1,174 @@
+use crate::extra_settings::schema::ExtraSettings;
+use crate::models::WorkDay;
+use crate::utils;
+use crate::utils::table::format_hours;
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use std::collections::BTreeMap;
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+/// Average, median and standard deviation of logged seconds
+struct Summary {
+    count: usize,
+    mean: f64,
+    median: f64,
+    std_dev: f64,
+}
+
+impl Summary {
+    fn new(mut secs: Vec<i64>) -> Option<Self> {
+        if secs.is_empty() {
+            return None;
+        }
+        secs.sort_unstable();
+        let count = secs.len();
+        let mean = secs.iter().sum::<i64>() as f64 / count as f64;
+        let median = if count.is_multiple_of(2) {
+            (secs[count / 2 - 1] + secs[count / 2]) as f64 / 2.0
+        } else {
+            secs[count / 2] as f64
+        };
+        // Population deviation, the days are all of the days and not a sample
+        let variance = secs.iter().map(|&s| (s as f64 - mean).powi(2)).sum::<f64>() / count as f64;
+        Some(Self {
+            count,
+            mean,
+            median,
+            std_dev: variance.sqrt(),
+        })
+    }
+}
+
+/// Seconds from the midnight of the day, beyond a day when the work continues past midnight
+fn day_secs(date: NaiveDate, time: NaiveDateTime) -> i64 {
+    (time - date.and_time(NaiveTime::MIN)).num_seconds()
+}
+
+fn time_of_day(secs: i64) -> String {
+    let secs = secs.rem_euclid(24 * 3600);
+    format!("{:02}:{:02}", secs / 3600, secs % 3600 / 60)
+}
+
+/// Average first start and last stop of the days per weekday, in local time
+struct StartStop {
+    weekday: Weekday,
+    day_count: usize,
+    start_secs: i64,
+    stop_secs: i64,
+}
+
+/// Statistics of the logged time per working day and per ISO week
+pub struct WorkStatistics {
+    days: Option<Summary>,
+    weeks: Option<Summary>,
+    start_stops: Vec<StartStop>,
+}
+
+impl WorkStatistics {
+    /// Only the days with logged time count, e.g. vacations don't lower the averages
+    pub fn new(
+        working_days: &[WorkDay],
+        user_settings: &ExtraSettings,
+        include_today: bool,
+    ) -> Self {
+        let mut days: BTreeMap<_, i64> = BTreeMap::new();
+        for wd in working_days
+            .iter()
+            .filter(|wd| include_today || wd.date < utils::today())
+            .filter(|wd| !user_settings.is_ignored_work_day(wd))
+        {
+            *days.entry(wd.date).or_default() += wd.duration();
+        }
+        days.retain(|_, secs| *secs > 0);
+
+        // The entries are grouped by their local start date, the work days by the UTC date
+        let mut local_days: BTreeMap<NaiveDate, (i64, i64)> = BTreeMap::new();
+        for item in working_days
+            .iter()
+            .filter(|wd| include_today || wd.date < utils::today())
+            .filter(|wd| !user_settings.is_ignored_work_day(wd))
+            .flat_map(|wd| &wd.items)
+        {
+            let start = item.start().with_timezone(&Local).naive_local();
+            let stop = item.stop().with_timezone(&Local).naive_local();
+            let date = start.date();
+            let (first, last) = local_days.entry(date).or_insert((i64::MAX, i64::MIN));
+            *first = (*first).min(day_secs(date, start));
+            *last = (*last).max(day_secs(date, stop));
+        }
+        // Monday first
+        let mut weekdays: BTreeMap<u32, StartStop> = BTreeMap::new();
+        for (date, (first, last)) in local_days {
+            let weekday = date.weekday();
+            let entry = weekdays
+                .entry(weekday.num_days_from_monday())
+                .or_insert(StartStop {
+                    weekday,
+                    day_count: 0,
+                    start_secs: 0,
+                    stop_secs: 0,
+                });
+            entry.day_count += 1;
+            entry.start_secs += first;
+            entry.stop_secs += last;
+        }
+        let start_stops = weekdays
+            .into_values()
+            .map(|sums| StartStop {
+                start_secs: sums.start_secs / sums.day_count as i64,
+                stop_secs: sums.stop_secs / sums.day_count as i64,
+                ..sums
+            })
+            .collect();
+
+        let mut weeks: BTreeMap<_, i64> = BTreeMap::new();
+        for (date, secs) in &days {
+            let week = date.iso_week();
+            *weeks.entry((week.year(), week.week())).or_default() += secs;
+        }
+        Self {
+            days: Summary::new(days.into_values().collect()),
+            weeks: Summary::new(weeks.into_values().collect()),
+            start_stops,
+        }
+    }
+}
+
+impl std::fmt::Display for WorkStatistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.days.is_none() {
+            return writeln!(f, "No logged time for statistics.");
+        }
+
+        let mut builder = Builder::default();
+        builder.push_record(["Per", "Count", "Average", "Median", "Std deviation"]);
+        for (name, summary) in [("Working day", &self.days), ("ISO week", &self.weeks)] {
+            if let Some(summary) = summary {
+                builder.push_record([
+                    name.to_string(),
+                    summary.count.to_string(),
+                    format_hours(summary.mean.round() as i64),
+                    format_hours(summary.median.round() as i64),
+                    format_hours(summary.std_dev.round() as i64),
+                ]);
+            }
+        }
+        let mut table = builder.build();
+        table.with(Style::modern_rounded());
+        writeln!(f, "{table}")?;
+
+        let mut builder = Builder::default();
+        builder.push_record(["Weekday", "Days", "Average start", "Average stop"]);
+        for start_stop in &self.start_stops {
+            builder.push_record([
+                start_stop.weekday.to_string(),
+                start_stop.day_count.to_string(),
+                time_of_day(start_stop.start_secs),
+                time_of_day(start_stop.stop_secs),
+            ]);
+        }
+        let mut table = builder.build();
+        table.with(Style::modern_rounded());
+        writeln!(f, "{table}")
+    }
+}
@@ -1,108 +1,1017 @@
-use crate::{utils, Results};
+use crate::calculator::Results;
+use crate::extra_settings::schema::{BalanceThresholds, CustomDayCounting, SchedulePeriod};
+use crate::utils;
+use anyhow::Error;
+use chrono::{DateTime, Local, NaiveDate};
+use clap::ValueEnum;
+use itertools::Itertools;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
 use tabled::builder::Builder;
+use tabled::settings::object::Rows;
 use tabled::settings::themes::ColumnNames;
 use tabled::settings::{Color, Style};
 use tabled::Table;
 
-pub(crate) fn build_table(r: Results, start_balance: Option<i64>) -> Table {
-    fn add_row(builder: &mut Builder, text: &str, days: Option<usize>, seconds: Option<i64>) {
-        let hours_and_minutes = if let Some(seconds) = seconds {
-            Some(utils::seconds_to_hours_and_minutes(seconds))
-        } else {
-            days.map(|days| utils::hours_to_hours_and_minutes(days as f32 * *utils::WORK_DAY_HOURS))
-        };
+#[derive(ValueEnum, Clone, Debug)]
+pub enum OutputFormat {
+    Table,
+    Markdown,
+    Html,
+}
+
+/// Language and number conventions of the results table
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    En,
+    Fi,
+    Sv,
+    De,
+}
 
-        let hours_and_minutes_str = if let Some((hours, minutes)) = hours_and_minutes {
-            let minutes = if minutes != 0 {
-                format!(", {} minutes", minutes)
-            } else {
-                "".into()
-            };
-            &format!("{} hours{}", hours, minutes)
+/// How the durations are shown
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HoursFormat {
+    /// In words in English and on the 24-hour clock in the other locales
+    #[default]
+    Locale,
+    /// Hours with two decimals, e.g. "7.62 h"
+    Decimal,
+}
+
+static HOURS_FORMAT: OnceLock<HoursFormat> = OnceLock::new();
+
+/// Show every duration of the run in the format, including the outputs of the commands.
+/// Must be called before any of the durations are formatted.
+pub fn set_hours_format(format: HoursFormat) -> Result<(), Error> {
+    HOURS_FORMAT
+        .set(format)
+        .map_err(|_| Error::msg("Hours format is already in use!"))
+}
+
+fn hours_format() -> HoursFormat {
+    *HOURS_FORMAT.get_or_init(HoursFormat::default)
+}
+
+/// Row labels in the order of the locales, English is the key
+const LABELS: [[&str; 4]; 46] = [
+    ["Item", "Kohde", "Post", "Posten"],
+    ["Days", "Päivät", "Dagar", "Tage"],
+    ["Duration", "Kesto", "Tid", "Dauer"],
+    [
+        "Public holidays (on weekdays)",
+        "Arkipyhät (arkipäivinä)",
+        "Helgdagar (på vardagar)",
+        "Feiertage (an Werktagen)",
+    ],
+    [
+        "Held parental leave weekdays",
+        "Pidetyt perhevapaan arkipäivät",
+        "Uttagna vardagar föräldraledighet",
+        "Genommene Elternzeit-Werktage",
+    ],
+    [
+        "Partial parental leave (deducted from expected)",
+        "Osittainen perhevapaa (vähennetty odotetusta)",
+        "Partiell föräldraledighet (avdragen från förväntad)",
+        "Teilweise Elternzeit (vom Soll abgezogen)",
+    ],
+    [
+        "Held vacation weekdays",
+        "Pidetyt loman arkipäivät",
+        "Uttagna semestervardagar",
+        "Genommene Urlaubs-Werktage",
+    ],
+    [
+        "Future vacation weekdays",
+        "Tulevat loman arkipäivät",
+        "Kommande semestervardagar",
+        "Künftige Urlaubs-Werktage",
+    ],
+    [
+        "Held unpaid leave weekdays",
+        "Pidetyt palkattoman vapaan arkipäivät",
+        "Uttagna vardagar obetald ledighet",
+        "Genommene Werktage unbezahlter Urlaub",
+    ],
+    [
+        "Held flex time off",
+        "Pidetyt liukumavapaat",
+        "Uttagen flexledighet",
+        "Genommener Gleitzeitausgleich",
+    ],
+    [
+        "Future flex time off",
+        "Tulevat liukumavapaat",
+        "Kommande flexledighet",
+        "Künftiger Gleitzeitausgleich",
+    ],
+    [
+        "Sick leave time",
+        "Sairauslomat",
+        "Sjukfrånvaro",
+        "Krankheitszeit",
+    ],
+    [
+        "Expected working time (sick leaves & public holidays deducted)",
+        "Odotettu työaika (sairauslomat ja arkipyhät vähennetty)",
+        "Förväntad arbetstid (sjukfrånvaro och helgdagar avdragna)",
+        "Sollarbeitszeit (Krankheit und Feiertage abgezogen)",
+    ],
+    [
+        "Total working time",
+        "Työaika yhteensä",
+        "Total arbetstid",
+        "Gesamtarbeitszeit",
+    ],
+    [
+        "Breaks deducted from long days",
+        "Pitkiltä päiviltä vähennetyt tauot",
+        "Raster avdragna från långa dagar",
+        "Von langen Tagen abgezogene Pausen",
+    ],
+    [
+        "Weekend work (as counted in the total)",
+        "Viikonlopputyö (yhteismäärään laskettuna)",
+        "Helgarbete (som det räknas i totalen)",
+        "Wochenendarbeit (wie in der Summe gezählt)",
+    ],
+    [
+        "Overtime bonus (included in the total)",
+        "Ylityölisä (sisältyy yhteismäärään)",
+        "Övertidstillägg (ingår i totalen)",
+        "Überstundenzuschlag (in der Summe enthalten)",
+    ],
+    [
+        "counted as worked",
+        "laskettu työajaksi",
+        "räknas som arbetad",
+        "als gearbeitet gezählt",
+    ],
+    [
+        "deducted from expected",
+        "vähennetty odotetusta",
+        "avdragen från förväntad",
+        "vom Soll abgezogen",
+    ],
+    ["not counted", "ei laskettu", "räknas inte", "nicht gezählt"],
+    [
+        "Vacation days earned this year",
+        "Tänä vuonna ansaitut lomapäivät",
+        "Intjänade semesterdagar i år",
+        "Dieses Jahr erworbene Urlaubstage",
+    ],
+    [
+        "Vacation days used this year",
+        "Tänä vuonna käytetyt lomapäivät",
+        "Uttagna semesterdagar i år",
+        "Dieses Jahr genommene Urlaubstage",
+    ],
+    [
+        "Vacation days remaining (booked deducted)",
+        "Jäljellä olevat lomapäivät (varatut vähennetty)",
+        "Återstående semesterdagar (bokade avdragna)",
+        "Verbleibende Urlaubstage (gebuchte abgezogen)",
+    ],
+    [
+        "Vacation days reclaimed by public holidays",
+        "Arkipyhien takia palautuneet lomapäivät",
+        "Semesterdagar återfådda genom helgdagar",
+        "Durch Feiertage zurückerhaltene Urlaubstage",
+    ],
+    [
+        "Pending time off requests (not counted)",
+        "Odottavat vapaapyynnöt (ei laskettu)",
+        "Väntande ledighetsansökningar (räknas inte)",
+        "Ausstehende Abwesenheitsanträge (nicht gezählt)",
+    ],
+    ["Start balance", "Alkusaldo", "Startsaldo", "Anfangssaldo"],
+    [
+        "Hours lost to the cap and expiry",
+        "Katon ja vanhenemisen takia menetetyt tunnit",
+        "Timmar förlorade genom tak och förfall",
+        "Durch Obergrenze und Verfall verlorene Stunden",
+    ],
+    [
+        "WARNING: expiring within {} days unless used",
+        "VAROITUS: vanhenee {} päivän kuluessa, ellei käytetä",
+        "VARNING: förfaller inom {} dagar om de inte används",
+        "WARNUNG: verfällt innerhalb von {} Tagen, wenn nicht genutzt",
+    ],
+    [
+        "WARNING: at the cap, further hours are lost",
+        "VAROITUS: katossa, lisätunnit menetetään",
+        "VARNING: vid taket, ytterligare timmar går förlorade",
+        "WARNUNG: an der Obergrenze, weitere Stunden verfallen",
+    ],
+    [
+        "Raw work time balance",
+        "Työaikasaldo ilman sääntöjä",
+        "Arbetstidssaldo utan regler",
+        "Arbeitszeitsaldo ohne Regeln",
+    ],
+    [
+        "Work time balance (policy)",
+        "Työaikasaldo (säännöt)",
+        "Arbetstidssaldo (regler)",
+        "Arbeitszeitsaldo (Regeln)",
+    ],
+    [
+        "Work time balance",
+        "Työaikasaldo",
+        "Arbetstidssaldo",
+        "Arbeitszeitsaldo",
+    ],
+    [
+        "Expected hours per weekday:",
+        "Odotetut tunnit viikonpäivittäin:",
+        "Förväntade timmar per veckodag:",
+        "Sollstunden pro Wochentag:",
+    ],
+    [
+        "today included",
+        "tänään mukaan lukien",
+        "i dag inräknad",
+        "heute eingeschlossen",
+    ],
+    [
+        "today excluded",
+        "tänään ei mukana",
+        "i dag inte inräknad",
+        "heute ausgeschlossen",
+    ],
+    [
+        "end date included",
+        "loppupäivä mukaan lukien",
+        "slutdatum inräknat",
+        "Enddatum eingeschlossen",
+    ],
+    [
+        "end date excluded",
+        "loppupäivä ei mukana",
+        "slutdatum inte inräknat",
+        "Enddatum ausgeschlossen",
+    ],
+    [
+        "Fetched from Clockify at {}",
+        "Haettu Clockifysta {}",
+        "Hämtad från Clockify {}",
+        "Von Clockify abgerufen am {}",
+    ],
+    [
+        "Read from debug dumps saved at {}",
+        "Luettu {} tallennetuista dumpeista",
+        "Läst från dumpar sparade {}",
+        "Aus Dumps gelesen, gespeichert am {}",
+    ],
+    [
+        "Read from debug dumps",
+        "Luettu dumpeista",
+        "Läst från dumpar",
+        "Aus Dumps gelesen",
+    ],
+    [
+        "Read from the archive exported at {}",
+        "Luettu {} viedystä arkistosta",
+        "Läst från arkivet exporterat {}",
+        "Aus dem am {} exportierten Archiv gelesen",
+    ],
+    ["Mon", "ma", "mån", "Mo"],
+    ["Tue", "ti", "tis", "Di"],
+    ["Wed", "ke", "ons", "Mi"],
+    ["Thu", "to", "tor", "Do"],
+    ["Fri", "pe", "fre", "Fr"],
+];
+
+impl Locale {
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    /// The label in the language of the locale, untranslated labels stay in English
+    pub fn label(self, text: &str) -> &str {
+        LABELS
+            .iter()
+            .find(|labels| labels[0] == text)
+            .map(|labels| labels[self.index()])
+            .unwrap_or(text)
+    }
+
+    fn thousands_separator(self) -> &'static str {
+        match self {
+            Self::En => ",",
+            Self::Fi | Self::Sv => "\u{a0}",
+            Self::De => ".",
+        }
+    }
+
+    fn decimal_separator(self) -> &'static str {
+        match self {
+            Self::En => ".",
+            Self::Fi | Self::Sv | Self::De => ",",
+        }
+    }
+
+    /// Whole number with the thousands separators, e.g. "1,234" or "1.234"
+    pub fn number(self, number: i64) -> String {
+        let digits = number.unsigned_abs().to_string();
+        let mut grouped = String::new();
+        for (i, digit) in digits.chars().enumerate() {
+            if i != 0 && (digits.len() - i).is_multiple_of(3) {
+                grouped.push_str(self.thousands_separator());
+            }
+            grouped.push(digit);
+        }
+        let sign = if number < 0 { "-" } else { "" };
+        format!("{sign}{grouped}")
+    }
+
+    fn fixed(self, number: f64, places: usize) -> String {
+        let text = format!("{:.places$}", number.abs());
+        let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+        let sign = if number < 0.0 && text.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            "-"
         } else {
             ""
         };
+        format!(
+            "{sign}{}{}{fraction}",
+            self.number(whole.parse().unwrap_or_default()),
+            self.decimal_separator()
+        )
+    }
 
-        let days_str = if let Some(days) = days {
-            &days.to_string()
-        } else {
-            ""
+    /// Number with one decimal, e.g. "12.5" or "12,5"
+    pub fn decimal(self, number: f32) -> String {
+        self.fixed(number as f64, 1)
+    }
+
+    /// Hours with two decimals, e.g. "7.62 h" or "7,62 h"
+    fn decimal_hours(self, hours: f64) -> String {
+        format!("{} h", self.fixed(hours, 2))
+    }
+
+    /// ISO 8601 in English and Swedish, DD.MM.YYYY in Finnish and German
+    pub fn date(self, date: NaiveDate) -> String {
+        match self {
+            Self::En | Self::Sv => date.format("%Y-%m-%d").to_string(),
+            Self::Fi | Self::De => date.format("%d.%m.%Y").to_string(),
+        }
+    }
+
+    /// Date and the time of day, e.g. "2025-06-10 14:32" or "10.06.2025 14:32"
+    pub fn timestamp(self, time: DateTime<Local>) -> String {
+        format!("{} {}", self.date(time.date_naive()), time.format("%H:%M"))
+    }
+
+    /// Hours and minutes of less than a work day, in words in English and on the 24-hour
+    /// clock otherwise, e.g. "7:45 h"
+    fn hours_and_minutes(self, hours: i64, minutes: i64) -> String {
+        match self {
+            Self::En => format_hours_and_minutes(&self.number(hours), minutes),
+            Self::Fi | Self::Sv | Self::De => format!("{}:{minutes:02} h", self.number(hours)),
+        }
+    }
+
+    /// Format seconds as days of `day_hours` and hours when the duration is at least one work
+    /// day long, otherwise as hours. Negative durations get a leading minus.
+    pub fn duration(self, seconds: i64, day_hours: f32) -> String {
+        if hours_format() == HoursFormat::Decimal {
+            return self.decimal_hours(seconds as f64 / 3600.0);
+        }
+        let sign = if seconds < 0 { "-" } else { "" };
+        let (days, hours, minutes) =
+            utils::seconds_to_days_hours_and_minutes(seconds.abs(), day_hours);
+        if days == 0 {
+            return format!("{sign}{}", self.hours_and_minutes(hours, minutes));
+        }
+        let days = self.number(days);
+        match self {
+            Self::En => format!("{sign}{days}d + {hours}h {minutes}m"),
+            Self::Fi => format!("{sign}{days} pv + {hours}:{minutes:02} h"),
+            Self::Sv => format!("{sign}{days} d + {hours}:{minutes:02} h"),
+            Self::De => format!("{sign}{days} T + {hours}:{minutes:02} h"),
+        }
+    }
+
+    /// The schedule period with the dates and the weekdays of the locale
+    pub fn schedule_period(self, period: &SchedulePeriod) -> String {
+        let hours = ["Mon", "Tue", "Wed", "Thu", "Fri"]
+            .iter()
+            .zip(period.hours)
+            .map(|(weekday, hours)| {
+                let (hours, minutes) = utils::hours_to_hours_and_minutes(hours);
+                format!("{} {hours}:{minutes:02}", self.label(weekday))
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        format!(
+            "{} - {}: {hours}",
+            self.date(period.start),
+            self.date(period.end)
+        )
+    }
+}
+
+/// Signed balance for shell prompts and scripts, e.g. "+47:37", or "+47.62" in decimal hours
+pub fn format_balance(seconds: i64) -> String {
+    let sign = if seconds < 0 { "-" } else { "+" };
+    match hours_format() {
+        HoursFormat::Locale => {
+            let (hours, minutes) = utils::seconds_to_hours_and_minutes(seconds.abs());
+            format!("{sign}{hours}:{minutes:02}")
+        }
+        HoursFormat::Decimal => format!("{sign}{:.2}", seconds.abs() as f64 / 3600.0),
+    }
+}
+
+/// Hours without the work days, e.g. "39:15 h", or "39.25 h" in decimal hours
+pub fn format_hours(seconds: i64) -> String {
+    if hours_format() == HoursFormat::Decimal {
+        return Locale::En.decimal_hours(seconds as f64 / 3600.0);
+    }
+    let sign = if seconds < 0 { "-" } else { "" };
+    let (hours, minutes) = utils::seconds_to_hours_and_minutes(seconds.abs());
+    format!("{sign}{hours}:{minutes:02} h")
+}
+
+/// Format seconds as "Nd + Hh Mm" when the duration is at least one work day long,
+/// otherwise as "H hours, M minutes". Negative durations get a leading minus.
+pub fn format_duration(seconds: i64) -> String {
+    Locale::En.duration(seconds, utils::work_day_hours())
+}
+
+fn format_hours_and_minutes(hours: &str, minutes: i64) -> String {
+    let minutes = if minutes != 0 {
+        format!(", {} minutes", minutes)
+    } else {
+        "".into()
+    };
+    format!("{} hours{}", hours, minutes)
+}
+
+/// When the terminal table is colored
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// When the output is a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn enabled(self) -> bool {
+        match self {
+            Self::Auto => std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal(),
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+}
+
+/// Options of the results in every output format
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TableOptions<'a> {
+    /// Shown as a row when given, in minutes
+    pub start_balance: Option<i64>,
+    pub locale: Locale,
+    /// The balance turns yellow or red beyond the thresholds, otherwise red when negative
+    pub thresholds: Option<&'a BalanceThresholds>,
+    /// ANSI colors of the terminal table
+    pub color: bool,
+    /// Only these rows in this order, every row when empty
+    pub rows: &'a [RowKey],
+    /// Shown above the results
+    pub header: Option<&'a ReportHeader>,
+}
+
+/// Where the items of the results came from
+#[derive(Clone, Copy, Debug)]
+pub enum DataSource {
+    /// Fetched from the Clockify API at the time
+    Live(DateTime<Local>),
+    /// Read from debug dumps, saved at the time of the newest dump when it is known
+    Dump(Option<DateTime<Local>>),
+    /// Read from the imported archive, exported at the time
+    Archive(DateTime<Local>),
+}
+
+/// Period and freshness of the results, so that shared outputs and screenshots describe
+/// themselves
+#[derive(Clone, Debug)]
+pub struct ReportHeader {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    /// The last day is counted
+    pub end_included: bool,
+    /// The end is set with `--as-of` or `--end-date` instead of being today
+    pub as_of: bool,
+    pub source: DataSource,
+}
+
+impl ReportHeader {
+    /// The period, e.g. "2022-03-01 → 2025-06-10, today excluded", and the freshness
+    fn lines(&self, locale: Locale) -> [String; 2] {
+        let end = match (self.as_of, self.end_included) {
+            (false, true) => "today included",
+            (false, false) => "today excluded",
+            (true, true) => "end date included",
+            (true, false) => "end date excluded",
+        };
+        let period = format!(
+            "{} → {}, {}",
+            locale.date(self.start),
+            locale.date(self.end),
+            locale.label(end)
+        );
+        let source = match self.source {
+            DataSource::Live(time) => locale
+                .label("Fetched from Clockify at {}")
+                .replace("{}", &locale.timestamp(time)),
+            DataSource::Dump(Some(time)) => locale
+                .label("Read from debug dumps saved at {}")
+                .replace("{}", &locale.timestamp(time)),
+            DataSource::Dump(None) => locale.label("Read from debug dumps").into(),
+            DataSource::Archive(time) => locale
+                .label("Read from the archive exported at {}")
+                .replace("{}", &locale.timestamp(time)),
         };
+        [period, source]
+    }
+}
+
+/// Status of the balance against the thresholds
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BalanceStatus {
+    Ok,
+    /// Negative above the lower threshold, or above the upper threshold
+    Warning,
+    /// Below the lower threshold, or negative without one
+    Alarm,
+}
 
-        builder.push_record([text, days_str, hours_and_minutes_str])
+impl BalanceStatus {
+    fn new(balance: i64, thresholds: Option<&BalanceThresholds>) -> Self {
+        let secs = |hours: f32| (hours * 3600.0) as i64;
+        let below = thresholds.and_then(|t| t.below).map(secs).unwrap_or(0);
+        let above = thresholds.and_then(|t| t.above).map(secs);
+        if balance < below {
+            Self::Alarm
+        } else if balance < 0 || above.is_some_and(|above| balance > above) {
+            Self::Warning
+        } else {
+            Self::Ok
+        }
     }
+}
 
-    let mut table_builder = Builder::default();
+/// Rows of the results, `--rows` shows only the chosen rows in its order. Some keys have
+/// several rows, and the rows without anything to show are left out in any case.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RowKey {
+    PublicHolidays,
+    ParentalLeave,
+    PartialParentalLeave,
+    Vacation,
+    FutureVacation,
+    UnpaidLeave,
+    Flex,
+    FutureFlex,
+    SickLeave,
+    Expected,
+    Worked,
+    Breaks,
+    WeekendWork,
+    OvertimeBonus,
+    CustomDays,
+    VacationBudget,
+    ReclaimedVacation,
+    Pending,
+    StartBalance,
+    /// The cap and expiry rows with the raw balance
+    Policy,
+    Balance,
+}
+
+type Row = [String; 3];
 
-    table_builder.push_record(["Item", "Days", "Hours & minutes"]);
+fn header(locale: Locale) -> Row {
+    [
+        locale.label("Item").into(),
+        locale.label("Days").into(),
+        locale.label("Duration").into(),
+    ]
+}
+
+/// The rows of the results chosen in the options, without the header
+fn build_rows(r: &Results, options: &TableOptions) -> Vec<(RowKey, Row)> {
+    let locale = options.locale;
+    let row = |text: &str, days: Option<usize>, seconds: Option<i64>| -> Row {
+        let duration_str = if let Some(seconds) = seconds {
+            locale.duration(seconds, r.work_day_hours)
+        } else if let Some(days) = days {
+            let hours = days as f32 * r.work_day_hours;
+            match hours_format() {
+                HoursFormat::Locale => {
+                    let (hours, minutes) = utils::hours_to_hours_and_minutes(hours);
+                    locale.hours_and_minutes(hours, minutes)
+                }
+                HoursFormat::Decimal => locale.decimal_hours(hours as f64),
+            }
+        } else {
+            String::new()
+        };
+
+        let days_str = days
+            .map(|days| locale.number(days as i64))
+            .unwrap_or_default();
+
+        [locale.label(text).to_owned(), days_str, duration_str]
+    };
+
+    let mut rows = Vec::new();
 
     let items = [
         (
+            RowKey::PublicHolidays,
             "Public holidays (on weekdays)",
             Some(r.public_holiday_count),
             None,
         ),
         (
+            RowKey::ParentalLeave,
             "Held parental leave weekdays",
             Some(r.parental_leave_day_count),
             None,
         ),
         (
+            RowKey::PartialParentalLeave,
+            "Partial parental leave (deducted from expected)",
+            Some(r.partial_parental_leave_day_count),
+            Some(r.partial_parental_leave_sec),
+        ),
+        (
+            RowKey::Vacation,
             "Held vacation weekdays",
             Some(r.held_vacation_day_count),
             None,
         ),
         (
+            RowKey::FutureVacation,
             "Future vacation weekdays",
             Some(r.future_vacation_day_count),
             None,
         ),
         (
+            RowKey::UnpaidLeave,
+            "Held unpaid leave weekdays",
+            Some(r.unpaid_leave_day_count),
+            None,
+        ),
+        (
+            RowKey::Flex,
             "Held flex time off",
             Some(r.held_flex_time_off_day_count),
             None,
         ),
         (
+            RowKey::FutureFlex,
             "Future flex time off",
             Some(r.future_flex_time_off_day_count),
             None,
         ),
-        ("Sick leave time", Some(r.sick_leave_day_count), None),
         (
+            RowKey::SickLeave,
+            "Sick leave time",
+            Some(r.sick_leave_day_count),
+            None,
+        ),
+        (
+            RowKey::Expected,
             "Expected working time (sick leaves & public holidays deducted)",
             Some(r.filtered_expected_working_day_count),
             Some(r.expected_working_time_sec),
         ),
         (
+            RowKey::Worked,
             "Total working time",
             Some(r.working_day_count),
             Some(r.worked_time),
         ),
     ];
 
-    for item in items {
-        add_row(&mut table_builder, item.0, item.1, item.2);
+    for (key, text, days, seconds) in items {
+        rows.push((key, row(text, days, seconds)));
     }
 
-    if let Some(start_balance) = start_balance {
-        add_row(
-            &mut table_builder,
-            "Start balance",
-            None,
-            Some(start_balance * 60),
-        );
+    if r.break_deducted_day_count > 0 {
+        rows.push((
+            RowKey::Breaks,
+            row(
+                "Breaks deducted from long days",
+                Some(r.break_deducted_day_count),
+                Some(r.break_deducted_sec),
+            ),
+        ));
     }
 
-    let (balance_hours, balance_minutes) = utils::seconds_to_hours_and_minutes(r.balance);
-    table_builder.push_record([
-        "Work time balance",
-        &format!("{}+", r.balance_days()),
-        &format!("{balance_hours} hours, {balance_minutes} minutes"),
-    ]);
+    if r.weekend_work_day_count > 0 {
+        rows.push((
+            RowKey::WeekendWork,
+            row(
+                "Weekend work (as counted in the total)",
+                Some(r.weekend_work_day_count),
+                Some(r.weekend_work_sec),
+            ),
+        ));
+    }
 
-    let mut table = table_builder.build();
-    table
-        .with(Style::modern_rounded())
-        .with(ColumnNames::default().color(Color::FG_GREEN));
+    if r.overtime_bonus_sec != 0 {
+        rows.push((
+            RowKey::OvertimeBonus,
+            row(
+                "Overtime bonus (included in the total)",
+                None,
+                Some(r.overtime_bonus_sec),
+            ),
+        ));
+    }
+
+    for custom in &r.custom_days {
+        let counting = match custom.counting {
+            CustomDayCounting::Worked => "counted as worked",
+            CustomDayCounting::ReducesExpected => "deducted from expected",
+            CustomDayCounting::Ignored => "not counted",
+        };
+        rows.push((
+            RowKey::CustomDays,
+            row(
+                &format!("{} ({})", custom.name, locale.label(counting)),
+                Some(custom.day_count),
+                Some(custom.time_sec),
+            ),
+        ));
+    }
+
+    if let Some(budget) = &r.vacation_budget {
+        rows.push((
+            RowKey::VacationBudget,
+            [
+                locale.label("Vacation days earned this year").into(),
+                locale.decimal(budget.earned),
+                String::new(),
+            ],
+        ));
+        rows.push((
+            RowKey::VacationBudget,
+            row("Vacation days used this year", Some(budget.used), None),
+        ));
+        rows.push((
+            RowKey::VacationBudget,
+            [
+                locale
+                    .label("Vacation days remaining (booked deducted)")
+                    .into(),
+                locale.decimal(budget.remaining()),
+                String::new(),
+            ],
+        ));
+    }
+
+    if r.reclaimed_vacation_day_count > 0 {
+        rows.push((
+            RowKey::ReclaimedVacation,
+            row(
+                "Vacation days reclaimed by public holidays",
+                Some(r.reclaimed_vacation_day_count),
+                None,
+            ),
+        ));
+    }
+
+    if let Some(count) = r.pending_time_off_day_count {
+        rows.push((
+            RowKey::Pending,
+            row("Pending time off requests (not counted)", Some(count), None),
+        ));
+    }
+
+    if let Some(start_balance) = options.start_balance {
+        rows.push((
+            RowKey::StartBalance,
+            row("Start balance", None, Some(start_balance * 60)),
+        ));
+    }
+
+    match &r.policy_balance {
+        Some(policy) => {
+            rows.push((
+                RowKey::Policy,
+                row(
+                    "Hours lost to the cap and expiry",
+                    None,
+                    Some(policy.lost_sec),
+                ),
+            ));
+            if policy.expiring_sec > 0 {
+                let label = locale
+                    .label("WARNING: expiring within {} days unless used")
+                    .replace("{}", &policy.warn_days.to_string());
+                rows.push((
+                    RowKey::Policy,
+                    [
+                        label,
+                        String::new(),
+                        locale.duration(policy.expiring_sec, r.work_day_hours),
+                    ],
+                ));
+            }
+            if policy.at_cap {
+                rows.push((
+                    RowKey::Policy,
+                    [
+                        locale
+                            .label("WARNING: at the cap, further hours are lost")
+                            .into(),
+                        String::new(),
+                        String::new(),
+                    ],
+                ));
+            }
+            rows.push((
+                RowKey::Policy,
+                row("Raw work time balance", None, Some(r.balance)),
+            ));
+            rows.push((
+                RowKey::Balance,
+                row("Work time balance (policy)", None, Some(policy.balance)),
+            ));
+        }
+        None => rows.push((
+            RowKey::Balance,
+            row("Work time balance", None, Some(r.balance)),
+        )),
+    }
+
+    if options.rows.is_empty() {
+        return rows;
+    }
+    options
+        .rows
+        .iter()
+        .unique()
+        .flat_map(|key| rows.iter().filter(move |(k, _)| k == key).cloned())
+        .collect()
+}
+
+fn build_builder(rows: &[(RowKey, Row)], locale: Locale) -> Builder {
+    let mut builder = Builder::default();
+    builder.push_record(header(locale));
+    for (_, row) in rows {
+        builder.push_record(row.clone());
+    }
+    builder
+}
+
+/// Table for the terminal. The balance is colored by the thresholds, the future absences are
+/// dimmed and the sick leaves cyan.
+pub fn build_table(r: &Results, options: &TableOptions) -> Table {
+    let rows = build_rows(r, options);
+    let mut table = build_builder(&rows, options.locale).build();
+    table.with(Style::modern_rounded());
+    if !options.color {
+        table.with(ColumnNames::default());
+        return table;
+    }
+
+    // The column names are moved to the border, so the rows are indexed without them
+    table.with(ColumnNames::default().color(Color::FG_GREEN));
+    let balance_color = match BalanceStatus::new(r.policy_adjusted_balance(), options.thresholds) {
+        BalanceStatus::Ok => Color::FG_GREEN,
+        BalanceStatus::Warning => Color::FG_YELLOW,
+        BalanceStatus::Alarm => Color::FG_RED,
+    };
+    for (i, (key, _)) in rows.iter().enumerate() {
+        let color = match key {
+            RowKey::FutureVacation | RowKey::FutureFlex => Color::FG_BRIGHT_BLACK,
+            RowKey::SickLeave => Color::FG_CYAN,
+            RowKey::Balance => balance_color.clone(),
+            _ => continue,
+        };
+        table.modify(Rows::single(i), color);
+    }
     table
 }
+
+/// Markdown table, e.g. for wiki pages and pull requests
+pub fn build_markdown(r: &Results, options: &TableOptions) -> String {
+    let mut table = build_builder(&build_rows(r, options), options.locale).build();
+    table.with(Style::markdown());
+    table.to_string()
+}
+
+/// HTML document with the results table, e.g. for emailed status reports
+pub fn build_html(r: &Results, options: &TableOptions) -> String {
+    fn escape(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    }
+
+    let balance_color = match BalanceStatus::new(r.policy_adjusted_balance(), options.thresholds) {
+        BalanceStatus::Ok => "#2e7d32",
+        BalanceStatus::Warning => "#f9a825",
+        BalanceStatus::Alarm => "#c62828",
+    };
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Clockify flex</title>\n</head>\n<body>\n<table border=\"1\" cellpadding=\"4\" style=\"border-collapse: collapse\">\n",
+    );
+    html.push_str("<thead>\n<tr>");
+    for cell in header(options.locale) {
+        html.push_str(&format!("<th>{}</th>", escape(&cell)));
+    }
+    html.push_str("</tr>\n</thead>\n");
+
+    html.push_str("<tbody>\n");
+    for (key, row) in build_rows(r, options) {
+        if key == RowKey::Balance {
+            html.push_str(&format!(
+                "<tr style=\"color: {balance_color}; font-weight: bold\">"
+            ));
+        } else {
+            html.push_str("<tr>");
+        }
+        for cell in row {
+            html.push_str(&format!("<td>{}</td>", escape(&cell)));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</tbody>\n</table>\n</body>\n</html>\n");
+    html
+}
+
+const SCHEDULE_TITLE: &str = "Expected hours per weekday:";
+
+/// Render the results in the chosen output format. The header is added above the results
+/// and the schedule periods as a footnote, so that shared outputs explain the expected time.
+pub fn render(
+    r: &Results,
+    output: &OutputFormat,
+    schedule: &[SchedulePeriod],
+    options: &TableOptions,
+) -> String {
+    let locale = options.locale;
+    let title = locale.label(SCHEDULE_TITLE);
+    let header = options.header.map(|header| header.lines(locale));
+    let footnote = schedule
+        .iter()
+        .map(|period| format!("\n- {}", locale.schedule_period(period)))
+        .collect::<String>();
+    match output {
+        OutputFormat::Table => {
+            let mut text = String::new();
+            if let Some([period, source]) = header {
+                text.push_str(&format!("{period}\n{source}\n"));
+            }
+            text.push_str(&build_table(r, options).to_string());
+            if !schedule.is_empty() {
+                text.push_str(&format!("\n{title}{footnote}"));
+            }
+            text
+        }
+        OutputFormat::Markdown => {
+            let mut text = String::new();
+            if let Some([period, source]) = header {
+                text.push_str(&format!("{period}  \n{source}\n\n"));
+            }
+            text.push_str(&build_markdown(r, options));
+            if !schedule.is_empty() {
+                text.push_str(&format!("\n\n{title}\n{footnote}"));
+            }
+            text
+        }
+        OutputFormat::Html => {
+            let mut html = build_html(r, options);
+            if let Some([period, source]) = header {
+                html = html.replacen(
+                    "<table",
+                    &format!("<p>{period}<br>\n{source}</p>\n<table"),
+                    1,
+                );
+            }
+            if !schedule.is_empty() {
+                let items = schedule
+                    .iter()
+                    .map(|period| format!("<li>{}</li>\n", locale.schedule_period(period)))
+                    .collect::<String>();
+                html = html.replace(
+                    "</body>",
+                    &format!("<p>{title}</p>\n<ul>\n{items}</ul>\n</body>"),
+                );
+            }
+            html
+        }
+    }
+}
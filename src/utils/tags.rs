@@ -0,0 +1,78 @@
+use crate::extra_settings::schema::ExtraSettings;
+use crate::models::WorkDay;
+use crate::utils;
+use crate::utils::table::format_duration;
+use itertools::Itertools;
+use std::collections::HashMap;
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+/// Label of the entries without tags
+const UNTAGGED: &str = "(no tag)";
+
+pub struct TagBreakdown {
+    /// Tag with its entry count and logged seconds, most time first
+    tags: Vec<(String, usize, i64)>,
+    total_sec: i64,
+}
+
+impl TagBreakdown {
+    /// Logged time per tag. Entries with several tags count for each of them.
+    pub fn new(
+        working_days: &[WorkDay],
+        user_settings: &ExtraSettings,
+        include_today: bool,
+    ) -> Self {
+        let mut tags: HashMap<String, (usize, i64)> = HashMap::new();
+        let mut total_sec = 0;
+        for item in working_days
+            .iter()
+            .filter(|wd| include_today || wd.date < utils::today())
+            .filter(|wd| !user_settings.is_ignored_work_day(wd))
+            .flat_map(|wd| &wd.items)
+        {
+            let duration = item.duration();
+            total_sec += duration;
+            let names = if item.tags().is_empty() {
+                vec![UNTAGGED.to_string()]
+            } else {
+                item.tags().to_vec()
+            };
+            for name in names {
+                let entry = tags.entry(name).or_default();
+                entry.0 += 1;
+                entry.1 += duration;
+            }
+        }
+
+        let tags = tags
+            .into_iter()
+            .map(|(name, (count, secs))| (name, count, secs))
+            .sorted_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(&b.0)))
+            .collect();
+        Self { tags, total_sec }
+    }
+}
+
+impl std::fmt::Display for TagBreakdown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.tags.is_empty() {
+            return writeln!(f, "No time entries to break down.");
+        }
+
+        let mut builder = Builder::default();
+        builder.push_record(["Tag", "Entries", "Duration", "Share"]);
+        for (name, count, secs) in &self.tags {
+            let share = *secs as f32 / self.total_sec.max(1) as f32 * 100.0;
+            builder.push_record([
+                name.clone(),
+                count.to_string(),
+                format_duration(*secs),
+                format!("{share:.0} %"),
+            ]);
+        }
+        let mut table = builder.build();
+        table.with(Style::modern_rounded());
+        writeln!(f, "{table}")
+    }
+}
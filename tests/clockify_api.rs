@@ -0,0 +1,509 @@
+use anyhow::Error;
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use clockify_flex::clockify::{
+    time_entries_to_work_days, ApiHost, ApiRequest, ApiResponse, ClockifyApi, ClockifyClient,
+    HttpApi, RecordedApi, TimeOffQuery, TimesheetQuery, Token,
+};
+use futures::future::BoxFuture;
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+use std::fmt;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+type Responder = Box<dyn Fn(&ApiRequest, usize) -> ApiResponse + Send + Sync>;
+
+/// Answers the user request itself and the rest with the responder, which also gets the number
+/// of the request among the others. The requests are recorded for the assertions.
+struct MockApi {
+    requests: Mutex<Vec<ApiRequest>>,
+    responder: Responder,
+}
+
+impl MockApi {
+    fn new(
+        responder: impl Fn(&ApiRequest, usize) -> ApiResponse + Send + Sync + 'static,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            requests: Mutex::new(Vec::new()),
+            responder: Box::new(responder),
+        })
+    }
+
+    fn requests(&self) -> Vec<ApiRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl fmt::Debug for MockApi {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MockApi").finish_non_exhaustive()
+    }
+}
+
+impl ClockifyApi for MockApi {
+    fn send(&self, request: ApiRequest) -> BoxFuture<'_, Result<ApiResponse, Error>> {
+        Box::pin(async move {
+            if request.path == "v1/user" {
+                return Ok(ApiResponse::ok(&json!({
+                    "id": "1a",
+                    "activeWorkspace": "2b",
+                    "name": "Test User",
+                    "email": "test@example.com",
+                })));
+            }
+            let mut requests = self.requests.lock().unwrap();
+            let response = (self.responder)(&request, requests.len());
+            requests.push(request);
+            Ok(response)
+        })
+    }
+}
+
+/// Answers the user request and never the others, like a stuck connection
+#[derive(Debug)]
+struct StuckApi;
+
+impl ClockifyApi for StuckApi {
+    fn send(&self, request: ApiRequest) -> BoxFuture<'_, Result<ApiResponse, Error>> {
+        Box::pin(async move {
+            if request.path == "v1/user" {
+                return Ok(ApiResponse::ok(&json!({
+                    "id": "1a",
+                    "activeWorkspace": "2b",
+                    "name": "Test User",
+                    "email": "test@example.com",
+                })));
+            }
+            futures::future::pending().await
+        })
+    }
+}
+
+async fn client(api: Arc<MockApi>) -> ClockifyClient {
+    ClockifyClient::with_api(api)
+        .await
+        .expect("Failed to build the client")
+        .progress(false)
+}
+
+fn time_entry(start: DateTime<Utc>) -> Value {
+    json!({
+        "description": "Work",
+        "project": {"name": "Project"},
+        "tags": [],
+        "user": {"id": "1a"},
+        "timeInterval": {
+            "start": start.to_rfc3339(),
+            "end": (start + TimeDelta::hours(8)).to_rfc3339(),
+        },
+    })
+}
+
+fn time_off_request(date: &str, status: &str) -> Value {
+    json!({
+        "timeUnit": "DAYS",
+        "userId": "1a",
+        "policyName": "Vacation",
+        "status": {"statusType": status},
+        "timeOffPeriod": {
+            "period": {
+                "start": format!("{date}T00:00:00Z"),
+                "end": format!("{date}T23:59:59Z"),
+            },
+        },
+    })
+}
+
+fn query_time(request: &ApiRequest, key: &str) -> DateTime<Utc> {
+    let value = request.query_value(key).expect("Missing query time");
+    DateTime::parse_from_rfc3339(value)
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+#[tokio::test]
+async fn timesheet_range_is_split_into_windows() {
+    let api = MockApi::new(|request, _| {
+        // One entry at the start of every window
+        ApiResponse::ok(&json!([time_entry(query_time(request, "start"))]))
+    });
+    let client = client(api.clone()).await;
+    let query = TimesheetQuery::since(date(2024, 1, 1))
+        .until(date(2024, 3, 31))
+        .window_days(30)
+        .concurrency(1);
+    let entries = client.get_time_entries(&query).await.unwrap();
+
+    let mut windows = api
+        .requests()
+        .iter()
+        .map(|request| (query_time(request, "start"), query_time(request, "end")))
+        .collect::<Vec<_>>();
+    windows.sort();
+    assert_eq!(windows.len(), 4);
+    assert_eq!(windows[0].0.date_naive(), date(2024, 1, 1));
+    assert_eq!(windows[3].1.date_naive(), date(2024, 3, 31));
+    // The windows are contiguous and within the limit of the API
+    for pair in windows.windows(2) {
+        assert_eq!(pair[0].1, pair[1].0);
+    }
+    for (start, end) in &windows {
+        assert!(*end - *start <= TimeDelta::days(30));
+    }
+    assert_eq!(entries.len(), 4);
+    assert!(entries
+        .windows(2)
+        .all(|pair| pair[0].start <= pair[1].start));
+}
+
+#[tokio::test]
+async fn timesheet_pages_are_fetched_until_a_short_page() {
+    let start = "2024-05-01T08:00:00Z".parse::<DateTime<Utc>>().unwrap();
+    let api = MockApi::new(move |request, _| {
+        let page = request
+            .query_value("page")
+            .unwrap()
+            .parse::<usize>()
+            .unwrap();
+        let count = if page < 3 { 2 } else { 1 };
+        let entries = (0..count)
+            .map(|i| time_entry(start + TimeDelta::days((page * 2 + i) as i64)))
+            .collect::<Vec<_>>();
+        ApiResponse::ok(&Value::Array(entries))
+    });
+    let client = client(api.clone()).await;
+    let query = TimesheetQuery::since(date(2024, 5, 1))
+        .until(date(2024, 5, 20))
+        .page_size(2);
+    let entries = client.get_time_entries(&query).await.unwrap();
+
+    let pages = api
+        .requests()
+        .iter()
+        .map(|request| request.query_value("page").unwrap().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(pages, ["1", "2", "3"]);
+    assert!(api
+        .requests()
+        .iter()
+        .all(|request| request.query_value("page-size") == Some("2")));
+    assert_eq!(entries.len(), 5);
+}
+
+#[tokio::test]
+async fn unsuccessful_timesheet_window_fails_the_fetch() {
+    let api = MockApi::new(|request, _| {
+        let start = query_time(request, "start");
+        if start.date_naive() == date(2024, 1, 1) {
+            ApiResponse::new(StatusCode::INTERNAL_SERVER_ERROR, String::new())
+        } else {
+            ApiResponse::ok(&json!([time_entry(start)]))
+        }
+    });
+    let client = client(api.clone()).await;
+    let query = TimesheetQuery::since(date(2024, 1, 1))
+        .until(date(2024, 1, 20))
+        .window_days(10);
+    let error = client.get_time_entries(&query).await.unwrap_err();
+
+    assert!(error.to_string().contains("500"), "{error}");
+}
+
+#[tokio::test]
+async fn timesheet_page_is_retried_after_rate_limiting() {
+    let api = MockApi::new(|request, nth| {
+        if nth == 0 {
+            return ApiResponse::new(StatusCode::TOO_MANY_REQUESTS, String::new());
+        }
+        let start = query_time(request, "start");
+        match request.query_value("page") {
+            Some("1") => ApiResponse::ok(&json!([time_entry(start), time_entry(start)])),
+            _ => ApiResponse::ok(&json!([time_entry(start + TimeDelta::days(1))])),
+        }
+    });
+    let client = client(api.clone()).await;
+    let query = TimesheetQuery::since(date(2024, 1, 1))
+        .until(date(2024, 1, 5))
+        .page_size(2);
+    let entries = client.get_time_entries(&query).await.unwrap();
+
+    let pages = api
+        .requests()
+        .iter()
+        .map(|request| request.query_value("page").unwrap().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(pages, ["1", "1", "2"]);
+    assert_eq!(entries.len(), 3);
+}
+
+#[tokio::test]
+async fn time_off_is_retried_after_rate_limiting() {
+    let api = MockApi::new(|_, nth| {
+        if nth < 2 {
+            ApiResponse::new(StatusCode::TOO_MANY_REQUESTS, String::new())
+        } else {
+            ApiResponse::ok(&json!({
+                "count": 1,
+                "requests": [time_off_request("2024-06-03", "APPROVED")],
+            }))
+        }
+    });
+    let client = client(api.clone()).await;
+    let items = client
+        .get_time_off_items(&TimeOffQuery::new())
+        .await
+        .unwrap();
+
+    let requests = api.requests();
+    assert_eq!(requests.len(), 3);
+    assert!(requests.iter().all(|request| request.method == Method::POST
+        && request.path == "workspaces/2b/time-off/requests"));
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].policy_name, "Vacation");
+}
+
+#[tokio::test]
+async fn time_off_pages_are_fetched_until_the_count() {
+    let api = MockApi::new(|request, _| {
+        let body = request.body.as_ref().unwrap();
+        let page = body["page"].as_u64().unwrap();
+        let requests = match page {
+            1 => vec![
+                time_off_request("2024-06-03", "PENDING"),
+                time_off_request("2024-06-04", "PENDING"),
+            ],
+            _ => vec![time_off_request("2024-06-05", "PENDING")],
+        };
+        ApiResponse::ok(&json!({"count": 3, "requests": requests}))
+    });
+    let client = client(api.clone()).await;
+    let query = TimeOffQuery::new().statuses(&["PENDING"]).page_size(2);
+    let items = client.get_time_off_items(&query).await.unwrap();
+
+    let requests = api.requests();
+    assert_eq!(requests.len(), 2);
+    let body = requests[0].body.as_ref().unwrap();
+    assert_eq!(body["status"], json!(["PENDING"]));
+    assert_eq!(body["pageSize"], json!(2));
+    assert_eq!(items.len(), 3);
+}
+
+#[tokio::test]
+async fn daily_totals_are_fetched_a_year_at_a_time() {
+    let api = MockApi::new(|request, _| {
+        let body = request.body.as_ref().unwrap();
+        let day = &body["dateRangeStart"].as_str().unwrap()[..10];
+        ApiResponse::ok(&json!({
+            "groupOne": [{
+                "_id": day,
+                "name": day,
+                "duration": 30600,
+                "children": [
+                    {"_id": "3c", "name": "Project", "duration": 27000},
+                    {"_id": "", "name": "Without project", "duration": 3600},
+                ],
+            }],
+        }))
+    });
+    let client = client(api.clone()).await;
+    let entries = client
+        .get_daily_totals(date(2023, 1, 1), date(2024, 6, 30))
+        .await
+        .unwrap();
+
+    let requests = api.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests
+        .iter()
+        .all(|request| request.host == ApiHost::Reports
+            && request.path == "v1/workspaces/2b/reports/summary"));
+    let body = requests[0].body.as_ref().unwrap();
+    assert_eq!(body["dateRangeEnd"], json!("2023-12-31T23:59:59.999Z"));
+    assert_eq!(body["summaryFilter"]["groups"], json!(["DATE", "PROJECT"]));
+
+    // The projects of a day follow each other, so that they are counted in full
+    let days = time_entries_to_work_days(entries);
+    assert_eq!(days.len(), 2);
+    assert_eq!(days[1].date, date(2024, 1, 1));
+    assert_eq!(days[1].duration(), 30600);
+    assert_eq!(days[1].items[0].project(), "Project");
+    assert_eq!(days[1].items[1].project(), "");
+}
+
+#[tokio::test]
+async fn first_entry_date_is_the_earliest_entry_of_the_report() {
+    let api = MockApi::new(|request, nth| {
+        let body = request.body.as_ref().unwrap();
+        assert_eq!(body["sortOrder"], json!("ASCENDING"));
+        assert_eq!(body["detailedFilter"]["pageSize"], json!(1));
+        let entries = match nth {
+            0 => json!([{"timeInterval": {
+                "start": "2021-03-01T23:30:00-02:00",
+                "end": "2021-03-02T08:00:00-02:00",
+            }}]),
+            _ => json!([]),
+        };
+        ApiResponse::ok(&json!({"totals": [], "timeentries": entries}))
+    });
+    let client = client(api.clone()).await;
+
+    // The date is in UTC like the other dates of the entries
+    assert_eq!(
+        client.get_first_entry_date().await.unwrap(),
+        Some(date(2021, 3, 2))
+    );
+    assert_eq!(client.get_first_entry_date().await.unwrap(), None);
+    let requests = api.requests();
+    assert!(requests
+        .iter()
+        .all(|request| request.host == ApiHost::Reports
+            && request.path == "v1/workspaces/2b/reports/detailed"));
+}
+
+#[tokio::test]
+async fn hourly_time_off_is_skipped_unless_strict() {
+    let api = MockApi::new(|_, _| {
+        let mut hourly = time_off_request("2024-06-04", "APPROVED");
+        hourly["timeUnit"] = json!("HOURS");
+        ApiResponse::ok(&json!({
+            "count": 2,
+            "requests": [time_off_request("2024-06-03", "APPROVED"), hourly],
+        }))
+    });
+    let client = client(api.clone()).await;
+    let items = client
+        .get_time_off_items(&TimeOffQuery::new())
+        .await
+        .unwrap();
+    assert_eq!(items.len(), 1);
+
+    let error = client
+        .strict(true)
+        .get_time_off_items(&TimeOffQuery::new())
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("DAYS"), "{error}");
+}
+
+#[tokio::test]
+async fn recorded_responses_are_filtered_like_the_api() {
+    let dir = std::env::temp_dir().join(format!("clockify-flex-replay-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let entries = [
+        "2024-01-31T08:00:00Z",
+        "2024-02-01T08:00:00Z",
+        "2024-02-29T08:00:00Z",
+        "2024-03-01T08:00:00Z",
+    ]
+    .map(|start| time_entry(start.parse().unwrap()));
+    std::fs::write(
+        dir.join("work_items_20240301000000.json"),
+        Value::from(entries.to_vec()).to_string(),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("time_off_items_20240301000000.json"),
+        json!([
+            time_off_request("2024-02-05", "APPROVED"),
+            time_off_request("2024-02-06", "PENDING"),
+        ])
+        .to_string(),
+    )
+    .unwrap();
+
+    let api = Arc::new(RecordedApi::new(&dir).unwrap());
+    let client = ClockifyClient::with_api(api).await.unwrap().progress(false);
+    let query = TimesheetQuery::since(date(2024, 2, 1))
+        .until(date(2024, 2, 29))
+        .page_size(1);
+    let entries = client.get_time_entries(&query).await;
+    let pending = client
+        .get_time_off_items(&TimeOffQuery::new().statuses(&["PENDING"]))
+        .await;
+    let projects = client.find_project("Project").await;
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let entries = entries.unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].start.date_naive(), date(2024, 2, 1));
+    assert_eq!(entries[1].start.date_naive(), date(2024, 2, 29));
+    let pending = pending.unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].status, "PENDING");
+    assert!(projects.is_err());
+}
+
+#[tokio::test]
+async fn stuck_fetch_fails_after_the_timeout() {
+    let client = ClockifyClient::with_api(Arc::new(StuckApi))
+        .await
+        .unwrap()
+        .progress(false)
+        .timeout(Some(Duration::from_millis(100)));
+    let query = TimesheetQuery::since(date(2024, 1, 1)).until(date(2024, 1, 31));
+    let error = client.get_time_entries(&query).await.unwrap_err();
+    assert!(error.to_string().contains("time entries"));
+    let error = client
+        .get_time_off_items(&TimeOffQuery::new())
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("time off"));
+}
+
+/// Serve the responses in order on a local port, one connection each, and send the request
+/// heads back
+fn serve(responses: Vec<&'static str>) -> (String, std::sync::mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for response in responses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut head = String::new();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                head.push_str(&line.to_lowercase());
+            }
+            sender.send(head).unwrap();
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    (url, receiver)
+}
+
+#[tokio::test]
+async fn unchanged_response_is_answered_from_the_store() {
+    let (url, heads) = serve(vec![
+        "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: 7\r\nConnection: close\r\n\r\n[1,2,3]",
+        "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n",
+    ]);
+    let dir = std::env::temp_dir().join(format!("clockify-flex-responses-{}", std::process::id()));
+    let api = HttpApi::new(&Token::new("token"))
+        .unwrap()
+        .api_url(url.parse().unwrap())
+        .conditional(dir.clone());
+    let request = ApiRequest::get("v1/things").query(&[("page", 1)]);
+    let first = api.send(request.clone()).await.unwrap();
+    let second = api.send(request).await.unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(!heads.recv().unwrap().contains("if-none-match"));
+    let head = heads.recv().unwrap();
+    assert!(head.starts_with("get /v1/things?page=1 "));
+    assert!(head.contains("if-none-match: \"v1\""));
+    assert_eq!(first.body, "[1,2,3]");
+    assert!(second.is_success());
+    assert_eq!(second.json::<Vec<u32>>().unwrap(), [1, 2, 3]);
+}